@@ -0,0 +1,610 @@
+#![feature(ascii_char)]
+#![deny(clippy::pedantic, clippy::nursery)]
+#![forbid(unsafe_code)]
+
+//! plays games between two players and writes every position with its score and the result,
+//! for tuning the evaluation
+//!
+//! ```text
+//! schach-selfplay --games 100 --white depth:4 --black mcts:2000 --openings book.epd \
+//!     --format plain --out games.plain --seed 1
+//! ```
+//!
+//! players are `random`, `mcts:<iterations>`, `depth:<plies>` and `nodes:<nodes>`. the openings
+//! are one fen or epd per line, taken in turn, the starting position without them.
+
+use std::io::BufRead;
+use std::io::BufWriter;
+use std::io::Write;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use schach::eval::Score;
+use schach::eval::score_from_win_rate;
+use schach::game::GameResult;
+use schach::game::GameResultKind;
+use schach::game::GameState;
+use schach::game::GameStateCore;
+use schach::game::Ongoing;
+use schach::game::StepResult;
+use schach::mcts::MctsPlayer;
+use schach::mcts::Playout;
+use schach::mv::Move;
+use schach::notation::san::standard_algebraic_notation;
+use schach::notation::uci::uci_notation;
+use schach::player::PlayerKind;
+use schach::search::Searcher;
+use schach::time::SearchLimits;
+
+fn main() {
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{error}");
+            eprintln!(
+                "usage: schach-selfplay [--games N] [--white PLAYER] [--black PLAYER] \
+                 [--openings FILE] [--format plain|epd|binary] [--out FILE] [--seed N] \
+                 [--max-plies N]"
+            );
+            std::process::exit(2);
+        }
+    };
+    if let Err(error) = run(&config) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlayerConfig {
+    Random,
+    Mcts { iterations: u64 },
+    Depth(u32),
+    Nodes(u64),
+}
+
+impl PlayerConfig {
+    fn parse(text: &str) -> Result<Self, String> {
+        let (kind, amount) = text.split_once(':').unwrap_or((text, ""));
+        let amount = || {
+            amount
+                .parse::<u64>()
+                .map_err(|_| format!("`{text}` needs a number after the colon"))
+        };
+        match kind {
+            "random" => Ok(Self::Random),
+            "mcts" => match amount()? {
+                0 => Err(format!("`{text}` needs at least one iteration")),
+                iterations => Ok(Self::Mcts { iterations }),
+            },
+            "depth" => Ok(Self::Depth(
+                u32::try_from(amount()?).map_err(|_| format!("`{text}` is too deep"))?,
+            )),
+            "nodes" => Ok(Self::Nodes(amount()?)),
+            _ => Err(format!("unknown player `{text}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// stockfish's plain text: `fen`, `move`, `score`, `ply` and `result` lines, ended by `e`
+    Plain,
+    /// the first four fen fields with the score as `ce` and the result as `c9`
+    Epd,
+    /// fixed records of [`RECORD_SIZE`] bytes, see [`write_binary`]
+    Binary,
+}
+
+#[derive(Debug)]
+struct Config {
+    games: u64,
+    white: PlayerConfig,
+    black: PlayerConfig,
+    openings: Option<String>,
+    format: Format,
+    /// stdout without one
+    out: Option<String>,
+    seed: u64,
+    /// games still going after this many plies have no result, so they're left out
+    max_plies: u32,
+}
+
+impl Config {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut config = Self {
+            games: 1,
+            white: PlayerConfig::Depth(3),
+            black: PlayerConfig::Depth(3),
+            openings: None,
+            format: Format::Plain,
+            out: None,
+            seed: 0,
+            max_plies: 400,
+        };
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("`{flag}` needs a value"))?;
+            let number = || {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("`{flag}` needs a number, not `{value}`"))
+            };
+            match flag.as_str() {
+                "--games" => config.games = number()?,
+                "--white" => config.white = PlayerConfig::parse(&value)?,
+                "--black" => config.black = PlayerConfig::parse(&value)?,
+                "--openings" => config.openings = Some(value),
+                "--format" => {
+                    config.format = match value.as_str() {
+                        "plain" => Format::Plain,
+                        "epd" => Format::Epd,
+                        "binary" => Format::Binary,
+                        _ => return Err(format!("unknown format `{value}`")),
+                    }
+                }
+                "--out" => config.out = Some(value),
+                "--seed" => config.seed = number()?,
+                "--max-plies" => {
+                    config.max_plies =
+                        u32::try_from(number()?).map_err(|_| "`--max-plies` is too big")?;
+                }
+                _ => return Err(format!("unknown option `{flag}`")),
+            }
+        }
+        Ok(config)
+    }
+}
+
+fn run(config: &Config) -> Result<(), String> {
+    let openings = match &config.openings {
+        Some(path) => read_openings(path)?,
+        None => vec![GameState::new().core],
+    };
+    let mut out: Box<dyn Write> = match &config.out {
+        Some(path) => Box::new(BufWriter::new(
+            std::fs::File::create(path).map_err(|error| format!("{path}: {error}"))?,
+        )),
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut white = Player::new(config.white, config.seed);
+    let mut black = Player::new(config.black, config.seed.wrapping_add(1));
+
+    for (game, opening) in (0..config.games).zip(openings.iter().cycle()) {
+        let (records, outcome) = play(
+            GameState::with_core(*opening),
+            &mut white,
+            &mut black,
+            &mut rng,
+            config.max_plies,
+        );
+        let winner = match outcome {
+            Outcome::Won(winner) => Some(winner),
+            Outcome::Drawn => None,
+            Outcome::CutOff => {
+                eprintln!(
+                    "game {}: cut off after {} plies, left out",
+                    game + 1,
+                    records.len()
+                );
+                continue;
+            }
+        };
+        for record in &records {
+            match config.format {
+                Format::Plain => write_plain(&mut out, record, winner),
+                Format::Epd => write_epd(&mut out, record, winner),
+                Format::Binary => write_binary(&mut out, record, winner),
+            }
+            .map_err(|error| error.to_string())?;
+        }
+        eprintln!(
+            "game {}: {} after {} plies",
+            game + 1,
+            result_text(winner),
+            records.len()
+        );
+    }
+    out.flush().map_err(|error| error.to_string())
+}
+
+fn read_openings(path: &str) -> Result<Vec<GameStateCore>, String> {
+    let file = std::fs::File::open(path).map_err(|error| format!("{path}: {error}"))?;
+    let mut openings = vec![];
+    for (number, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|error| format!("{path}: {error}"))?;
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.is_empty() || fields[0].starts_with('#') {
+            continue;
+        }
+        if fields.len() < 4 {
+            return Err(format!("{path}:{}: not a fen or epd", number + 1));
+        }
+        // epd has operations where fen has its two counters
+        let counters = match fields.get(4..6) {
+            Some([halfmoves, fullmoves])
+                if halfmoves.parse::<u64>().is_ok() && fullmoves.parse::<u64>().is_ok() =>
+            {
+                format!("{halfmoves} {fullmoves}")
+            }
+            _ => "0 1".to_owned(),
+        };
+        let fen = format!("{} {counters}", fields[..4].join(" "));
+        let core = GameStateCore::try_from_fen(&fen)
+            .map_err(|error| format!("{path}:{}: {error:?}", number + 1))?;
+        if GameState::with_core(core).legal_moves().next().is_none() {
+            return Err(format!("{path}:{}: the game is already over", number + 1));
+        }
+        openings.push(core);
+    }
+    if openings.is_empty() {
+        return Err(format!("{path}: no openings in it"));
+    }
+    Ok(openings)
+}
+
+enum Player {
+    Random,
+    Mcts(MctsPlayer),
+    Search(Box<Searcher>, SearchLimits),
+}
+
+impl Player {
+    fn new(config: PlayerConfig, seed: u64) -> Self {
+        match config {
+            PlayerConfig::Random => Self::Random,
+            PlayerConfig::Mcts { iterations } => Self::Mcts(
+                MctsPlayer::new()
+                    .with_playout(Playout::RandomThenEvaluate { plies: 2 })
+                    .with_iterations(Some(iterations))
+                    .with_seed(Some(seed)),
+            ),
+            PlayerConfig::Depth(depth) => {
+                Self::Search(Box::new(Searcher::new()), SearchLimits::depth(depth))
+            }
+            PlayerConfig::Nodes(nodes) => {
+                Self::Search(Box::new(Searcher::new()), SearchLimits::nodes(nodes))
+            }
+        }
+    }
+
+    /// forgets what it learned from the last game
+    fn new_game(&mut self) {
+        match self {
+            Self::Random => {}
+            Self::Mcts(player) => player.clear(),
+            Self::Search(searcher, _) => searcher.table().clear(),
+        }
+    }
+
+    /// the move and its score for the side to move, `None` if the player doesn't score moves
+    fn play(&mut self, game: &GameState<Ongoing>, rng: &mut StdRng) -> (Move, Option<Score>) {
+        match self {
+            Self::Random => {
+                let legal_moves = game.legal_moves().collect::<Vec<_>>();
+                let mv = *legal_moves
+                    .choose(rng)
+                    .expect("a GameState<Ongoing> to always have legal moves");
+                (mv, None)
+            }
+            Self::Mcts(player) => {
+                let result = player.search(game);
+                let mv = result
+                    .best_move
+                    .expect("an iteration budget to always find a move");
+                let win_rate = result.win_rate.clamp(0.001, 0.999);
+                #[allow(clippy::cast_possible_truncation)]
+                let score = Score(score_from_win_rate(win_rate).round() as i32);
+                (mv, Some(score))
+            }
+            Self::Search(searcher, limits) => {
+                let result = searcher.search(game, limits);
+                let mv = result
+                    .best_move
+                    .expect("a GameState<Ongoing> to always have legal moves");
+                (mv, Some(result.score))
+            }
+        }
+    }
+}
+
+/// a position and what was played in it
+struct Record {
+    core: GameStateCore,
+    mv: Move,
+    score: Option<Score>,
+    ply: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Won(PlayerKind),
+    Drawn,
+    /// still going after `--max-plies`
+    CutOff,
+}
+
+/// every position of the game and how it ended
+fn play(
+    mut game: GameState<Ongoing>,
+    white: &mut Player,
+    black: &mut Player,
+    rng: &mut StdRng,
+    max_plies: u32,
+) -> (Vec<Record>, Outcome) {
+    white.new_game();
+    black.new_game();
+    let mut records = vec![];
+    for ply in 0..max_plies {
+        let player = match game.core.active_player {
+            PlayerKind::White => &mut *white,
+            PlayerKind::Black => &mut *black,
+        };
+        let (mv, score) = player.play(&game, rng);
+        records.push(Record {
+            core: game.core,
+            mv,
+            score,
+            ply,
+        });
+        game = match game.step(mv) {
+            StepResult::Ongoing(next) => next,
+            StepResult::Terminated(GameResult {
+                kind,
+                final_game_state,
+            }) => {
+                // the result is for the player who made the last move, who is still to move in
+                // the final state
+                let mover = final_game_state.core.active_player;
+                let outcome = match kind {
+                    GameResultKind::Win => Outcome::Won(mover),
+                    GameResultKind::Loss => Outcome::Won(mover.opponent()),
+                    GameResultKind::Draw(_) => Outcome::Drawn,
+                };
+                return (records, outcome);
+            }
+        };
+    }
+    (records, Outcome::CutOff)
+}
+
+/// the pgn result for a finished game, `None` for a draw
+const fn result_text(winner: Option<PlayerKind>) -> &'static str {
+    match winner {
+        Some(PlayerKind::White) => "1-0",
+        Some(PlayerKind::Black) => "0-1",
+        None => "1/2-1/2",
+    }
+}
+
+/// 1 for a win of the side to move, 0 for a draw and -1 for a loss
+const fn result_for(record: &Record, winner: Option<PlayerKind>) -> i8 {
+    match (winner, record.core.active_player) {
+        (Some(PlayerKind::White), PlayerKind::White)
+        | (Some(PlayerKind::Black), PlayerKind::Black) => 1,
+        (Some(_), _) => -1,
+        (None, _) => 0,
+    }
+}
+
+fn write_plain(
+    out: &mut impl Write,
+    record: &Record,
+    winner: Option<PlayerKind>,
+) -> std::io::Result<()> {
+    writeln!(out, "fen {}", record.core.to_fen().as_str())?;
+    writeln!(out, "move {}", uci_notation(record.mv).as_str())?;
+    if let Some(score) = record.score {
+        writeln!(out, "score {}", score.0)?;
+    }
+    writeln!(out, "ply {}", record.ply)?;
+    writeln!(out, "result {}", result_for(record, winner))?;
+    writeln!(out, "e")
+}
+
+fn write_epd(
+    out: &mut impl Write,
+    record: &Record,
+    winner: Option<PlayerKind>,
+) -> std::io::Result<()> {
+    let fen = record.core.to_fen();
+    let epd = fen
+        .as_str()
+        .split(' ')
+        .take(4)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let best_move = standard_algebraic_notation(GameState::with_core(record.core), record.mv);
+    write!(out, "{epd} bm {};", best_move.as_str())?;
+    if let Some(score) = record.score {
+        write!(out, " ce {};", score.0)?;
+    }
+    writeln!(out, " c9 \"{}\";", result_text(winner))
+}
+
+const RECORD_SIZE: usize = 41;
+
+/// one record of [`RECORD_SIZE`] bytes, little endian:
+///
+/// - 32 bytes of board, a nibble per square from a1 to h8, low nibble first. 0 is empty, 1 to 6
+///   are the white pawn, knight, bishop, rook, queen and king, 9 to 14 the same for black.
+/// - 1 byte of flags: black to move, then white kingside, white queenside, black kingside and
+///   black queenside castling, from the lowest bit up
+/// - 1 byte en passant file, 1 for the a file, 0 for none
+/// - 2 bytes of move: origin, destination and promotion as in the board, 6, 6 and 4 bits
+/// - 2 bytes of score for the side to move, `i16::MIN` for none
+/// - 2 bytes of ply
+/// - 1 byte of result for the side to move, as in the plain format
+fn write_binary(
+    out: &mut impl Write,
+    record: &Record,
+    winner: Option<PlayerKind>,
+) -> std::io::Result<()> {
+    let core = &record.core;
+    let mut bytes = [0_u8; RECORD_SIZE];
+    for (square, piece) in core.board.pieces() {
+        let kind = u8::try_from(piece.kind.index())
+            .ok()
+            .filter(|kind| *kind < 6)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "only the standard pieces fit in a binary record",
+                )
+            })?;
+        let nibble = 1
+            + kind
+            + if piece.owner == PlayerKind::Black {
+                8
+            } else {
+                0
+            };
+        bytes[square.index() / 2] |= nibble << (4 * (square.index() % 2));
+    }
+    let rights = core.castling_rights;
+    bytes[32] = [
+        core.active_player == PlayerKind::Black,
+        rights.white_kingside,
+        rights.white_queenside,
+        rights.black_kingside,
+        rights.black_queenside,
+    ]
+    .iter()
+    .enumerate()
+    .map(|(bit, set)| u8::from(*set) << bit)
+    .sum();
+    #[allow(clippy::cast_possible_truncation)]
+    if let Some(target) = core.en_passant_target {
+        bytes[33] = (target.index() % 8) as u8 + 1;
+    }
+    let promotion = record
+        .mv
+        .kind
+        .promotion_replacement()
+        .map_or(0, |piece| piece.kind.index() + 1);
+    #[allow(clippy::cast_possible_truncation)]
+    let mv =
+        (record.mv.origin.index() | record.mv.destination.index() << 6 | promotion << 12) as u16;
+    bytes[34..36].copy_from_slice(&mv.to_le_bytes());
+    let score = record.score.map_or(i16::MIN, |score| {
+        #[allow(clippy::cast_possible_truncation)]
+        let score = score.0.clamp(-i32::from(i16::MAX), i32::from(i16::MAX)) as i16;
+        score
+    });
+    bytes[36..38].copy_from_slice(&score.to_le_bytes());
+    bytes[38..40].copy_from_slice(&u16::try_from(record.ply).unwrap_or(u16::MAX).to_le_bytes());
+    bytes[40] = result_for(record, winner).to_le_bytes()[0];
+    out.write_all(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use schach::notation::uci::parse_uci_move;
+
+    use super::*;
+
+    fn record(fen: &str, mv: &str, score: Option<Score>, ply: u32) -> Record {
+        let core = GameStateCore::try_from_fen(fen).unwrap();
+        let mv = parse_uci_move(&GameState::with_core(core), mv).unwrap();
+        Record {
+            core,
+            mv,
+            score,
+            ply,
+        }
+    }
+
+    #[test]
+    fn binary_record_bytes() {
+        let record = record("r3k3/1P6/8/3pP3/8/8/8/4K2R w Kq d6 0 2", "b7b8q", None, 3);
+        let mut bytes = vec![];
+        write_binary(&mut bytes, &record, Some(PlayerKind::White)).unwrap();
+
+        let mut expected = [0_u8; RECORD_SIZE];
+        expected[2] = 0x06; // white king on e1, the low nibble
+        expected[3] = 0x40; // white rook on h1, the high nibble
+        expected[17] = 0x90; // black pawn on d5
+        expected[18] = 0x01; // white pawn on e5
+        expected[24] = 0x10; // white pawn on b7
+        expected[28] = 0x0c; // black rook on a8
+        expected[30] = 0x0e; // black king on e8
+        expected[32] = 0b1_0010; // white kingside and black queenside
+        expected[33] = 4; // the d file
+        // b7 is 49, b8 is 57 << 6 and the queen is 5 << 12
+        expected[34..36].copy_from_slice(&0x5e71_u16.to_le_bytes());
+        expected[36..38].copy_from_slice(&i16::MIN.to_le_bytes());
+        expected[38..40].copy_from_slice(&3_u16.to_le_bytes());
+        expected[40] = 1;
+        assert_eq!(expected.as_slice(), bytes.as_slice());
+    }
+
+    #[test]
+    fn binary_record_for_black() {
+        let record = record(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "e7e5",
+            Some(Score(-35)),
+            1,
+        );
+        let mut bytes = vec![];
+        write_binary(&mut bytes, &record, Some(PlayerKind::White)).unwrap();
+
+        assert_eq!(RECORD_SIZE, bytes.len());
+        assert_eq!(0b1_1111, bytes[32]);
+        assert_eq!(0, bytes[33]);
+        // e7 is 52 and e5 is 36 << 6
+        assert_eq!(0x0934, u16::from_le_bytes([bytes[34], bytes[35]]));
+        assert_eq!((-35_i16).to_le_bytes(), bytes[36..38]);
+        assert_eq!(-1, i8::from_le_bytes([bytes[40]]));
+    }
+
+    fn read_openings_from(name: &str, text: &str) -> Result<Vec<GameStateCore>, String> {
+        let path =
+            std::env::temp_dir().join(format!("schach-selfplay-{name}-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, text).unwrap();
+        let openings = read_openings(path);
+        std::fs::remove_file(path).unwrap();
+        openings
+    }
+
+    #[test]
+    fn openings_from_epd_and_fen() {
+        let openings = read_openings_from(
+            "mixed",
+            "# an epd, then a fen\n\
+             rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - bm e5; id \"e4\";\n\
+             \n\
+             r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            [
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+                "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            ]
+            .as_slice(),
+            openings
+                .iter()
+                .map(|core| String::from(core.to_fen().as_str()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn openings_that_are_already_over_are_rejected() {
+        let mated = read_openings_from(
+            "mated",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -\n\
+             rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3\n",
+        );
+        assert!(mated.unwrap_err().contains(":2: the game is already over"));
+
+        assert!(read_openings_from("empty", "# nothing\n").is_err());
+        assert!(read_openings_from("short", "8/8/8 w\n").is_err());
+    }
+}
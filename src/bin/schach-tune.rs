@@ -0,0 +1,134 @@
+#![deny(clippy::pedantic, clippy::nursery)]
+#![forbid(unsafe_code)]
+
+//! tunes the evaluation weights on positions labelled with the results of their games, and
+//! writes them as a weights file for [`EvalWeights::from_file`]
+//!
+//! ```text
+//! schach-tune --positions games.epd --iterations 1000 --out tuned.weights
+//! ```
+//!
+//! the positions are one per line, a fen or epd with the result after it, like the `epd` format
+//! of `schach-selfplay`. the ones that aren't quiet are left out.
+
+use std::io::BufRead;
+use std::sync::Arc;
+
+use schach::eval::EvalWeights;
+use schach::search::Searcher;
+use schach::tune::Tuner;
+use schach::tune::is_quiet;
+use schach::tune::parse_labelled_position;
+
+fn main() {
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{error}");
+            eprintln!(
+                "usage: schach-tune --positions FILE [--weights FILE] [--out FILE] \
+                 [--iterations N] [--learning-rate X]"
+            );
+            std::process::exit(2);
+        }
+    };
+    if let Err(error) = run(&config) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
+
+#[derive(Debug)]
+struct Config {
+    positions: String,
+    /// the standard weights without one
+    weights: Option<String>,
+    /// stdout without one
+    out: Option<String>,
+    iterations: u64,
+    learning_rate: f64,
+}
+
+impl Config {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut positions = None;
+        let mut config = Self {
+            positions: String::new(),
+            weights: None,
+            out: None,
+            iterations: 500,
+            learning_rate: 1.0,
+        };
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("`{flag}` needs a value"))?;
+            match flag.as_str() {
+                "--positions" => positions = Some(value),
+                "--weights" => config.weights = Some(value),
+                "--out" => config.out = Some(value),
+                "--iterations" => {
+                    config.iterations = value
+                        .parse()
+                        .map_err(|_| format!("`{flag}` needs a number, not `{value}`"))?;
+                }
+                "--learning-rate" => {
+                    config.learning_rate = value
+                        .parse()
+                        .map_err(|_| format!("`{flag}` needs a number, not `{value}`"))?;
+                }
+                _ => return Err(format!("unknown option `{flag}`")),
+            }
+        }
+        config.positions = positions.ok_or("`--positions` is needed")?;
+        Ok(config)
+    }
+}
+
+fn run(config: &Config) -> Result<(), String> {
+    let weights = match &config.weights {
+        Some(path) => EvalWeights::from_file(path).map_err(|error| format!("{path}: {error:?}"))?,
+        None => EvalWeights::STANDARD,
+    };
+
+    let path = &config.positions;
+    let file = std::fs::File::open(path).map_err(|error| format!("{path}: {error}"))?;
+    let mut searcher = Searcher::new().with_weights(Arc::new(weights.clone()));
+    let mut positions = vec![];
+    let mut noisy = 0;
+    for (number, line) in (1..).zip(std::io::BufReader::new(file).lines()) {
+        let line = line.map_err(|error| format!("{path}: {error}"))?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (core, result) = parse_labelled_position(&line)
+            .map_err(|error| format!("{path}:{number}: {error:?}"))?;
+        if is_quiet(&mut searcher, &core) {
+            positions.push((core, result));
+        } else {
+            noisy += 1;
+        }
+    }
+    eprintln!(
+        "{} quiet positions, {noisy} left out that weren't",
+        positions.len()
+    );
+
+    let mut tuner = Tuner::new(&positions, &weights);
+    eprintln!("scaling {:.4}", tuner.fit_scaling());
+    for iteration in 0..config.iterations {
+        let loss = tuner.step(config.learning_rate);
+        if iteration % 50 == 0 {
+            eprintln!("iteration {iteration}: loss {loss:.6}");
+        }
+    }
+    eprintln!("final loss {:.6}", tuner.loss());
+
+    let text = tuner.weights().to_text();
+    if let Some(path) = &config.out {
+        std::fs::write(path, text).map_err(|error| format!("{path}: {error}"))
+    } else {
+        print!("{text}");
+        Ok(())
+    }
+}
@@ -0,0 +1,446 @@
+#![feature(ascii_char)]
+#![deny(clippy::pedantic, clippy::nursery)]
+#![forbid(unsafe_code)]
+
+use std::io::BufRead;
+use std::sync::Arc;
+use std::time::Duration;
+
+use schach::eval::EvalWeights;
+use schach::game::GameState;
+use schach::game::GameStateCore;
+use schach::game::Ongoing;
+use schach::game::StepResult;
+use schach::notation::uci::parse_uci_move;
+use schach::notation::uci::uci_notation;
+use schach::search::PvLine;
+use schach::search::SearchResult;
+use schach::search::Searcher;
+use schach::search_thread::SearchEvent;
+use schach::search_thread::SearchHandle;
+use schach::time::SearchLimits;
+use schach::tt::ReplacementScheme;
+use schach::tt::TranspositionTable;
+
+const MAX_HASH_MEGABYTES: usize = 4096;
+const MAX_MULTIPV: u32 = 256;
+const MAX_THREADS: usize = 256;
+
+fn main() {
+    let mut engine = Engine::new();
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if engine.handle(&line) == Flow::Quit {
+            break;
+        }
+    }
+    engine.stop_search();
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Flow {
+    Continue,
+    Quit,
+}
+
+struct Engine {
+    /// `None` if the moves of the last `position` command ended the game
+    game: Option<GameState<Ongoing>>,
+    /// lent to the search thread while it runs
+    searcher: Option<Searcher>,
+    search: Option<SearchHandle>,
+    hash_megabytes: usize,
+    multipv: u32,
+    threads: usize,
+    /// from the `EvalFile` option
+    weights: Arc<EvalWeights>,
+}
+
+impl Engine {
+    fn new() -> Self {
+        Self {
+            game: Some(GameState::new()),
+            searcher: Some(Searcher::new()),
+            search: None,
+            hash_megabytes: TranspositionTable::DEFAULT_MEGABYTES,
+            multipv: 1,
+            threads: 1,
+            weights: Arc::new(EvalWeights::STANDARD),
+        }
+    }
+
+    fn handle(&mut self, line: &str) -> Flow {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                println!("id name schach {}", env!("CARGO_PKG_VERSION"));
+                println!("id author the schach contributors");
+                println!(
+                    "option name Hash type spin default {} min 1 max {MAX_HASH_MEGABYTES}",
+                    TranspositionTable::DEFAULT_MEGABYTES
+                );
+                println!("option name Clear Hash type button");
+                println!("option name Ponder type check default false");
+                println!("option name MultiPV type spin default 1 min 1 max {MAX_MULTIPV}");
+                println!("option name Threads type spin default 1 min 1 max {MAX_THREADS}");
+                println!("option name EvalFile type string default <empty>");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("setoption") => self.set_option(tokens),
+            Some("ucinewgame") => {
+                self.stop_search();
+                self.replace_searcher();
+            }
+            Some("position") => {
+                self.stop_search();
+                match parse_position(tokens) {
+                    Ok(game) => self.game = game,
+                    Err(error) => println!("info string invalid position: {error}"),
+                }
+            }
+            Some("go") => self.go(tokens),
+            Some("stop") => self.stop_search(),
+            Some("ponderhit") => {
+                if let Some(search) = &self.search {
+                    search.ponderhit();
+                }
+            }
+            Some("quit") => return Flow::Quit,
+            // `debug`, `register` and anything unknown are ignored, like the protocol asks
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    fn set_option<'a>(&mut self, tokens: impl Iterator<Item = &'a str>) {
+        // `name <name> [value <value>]`, where both may contain spaces
+        let option = tokens.collect::<Vec<_>>().join(" ");
+        let (name, value) = option.split_once(" value ").unwrap_or((&option, ""));
+        let name = name.strip_prefix("name ").unwrap_or(name).to_lowercase();
+
+        self.stop_search();
+        match name.as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(megabytes) => {
+                    self.hash_megabytes = megabytes.clamp(1, MAX_HASH_MEGABYTES);
+                    self.replace_searcher();
+                }
+                Err(_) => println!("info string invalid hash size: {value}"),
+            },
+            "clear hash" => {
+                if let Some(searcher) = &self.searcher {
+                    searcher.table().clear();
+                }
+            }
+            "threads" => match value.parse::<usize>() {
+                Ok(threads) => self.threads = threads.clamp(1, MAX_THREADS),
+                Err(_) => println!("info string invalid thread count: {value}"),
+            },
+            "multipv" => match value.parse::<u32>() {
+                Ok(lines) => self.multipv = lines.clamp(1, MAX_MULTIPV),
+                Err(_) => println!("info string invalid multipv: {value}"),
+            },
+            "evalfile" => match EvalWeights::from_file(value) {
+                Ok(weights) => {
+                    self.weights = Arc::new(weights);
+                    self.replace_searcher();
+                }
+                Err(error) => println!("info string invalid eval file: {value}: {error:?}"),
+            },
+            // pondering is up to the gui, there's nothing to set up for it
+            "ponder" => {}
+            _ => println!("info string unknown option: {name}"),
+        }
+    }
+
+    fn go<'a>(&mut self, tokens: impl Iterator<Item = &'a str>) {
+        self.stop_search();
+        let Some(game) = self.game.clone() else {
+            println!("bestmove 0000");
+            return;
+        };
+        // mated or stalemated, there's nothing to search
+        if game.legal_moves().next().is_none() {
+            println!("bestmove 0000");
+            return;
+        }
+        let (mut limits, ponder) = parse_go(&game, tokens);
+        limits.multipv = Some(self.multipv);
+
+        let searcher = self
+            .searcher
+            .take()
+            .expect("no search to be running")
+            .with_threads(self.threads)
+            .with_weights(Arc::clone(&self.weights));
+        let table = Arc::clone(searcher.table());
+        let on_event = move |event: SearchEvent<'_>| match event {
+            SearchEvent::Iteration(result) => {
+                let hashfull = table.hashfull();
+                for (index, line) in result.lines.iter().enumerate() {
+                    println!("{}", info_line(result, index + 1, line, hashfull));
+                }
+            }
+            SearchEvent::Finished(result) => match (result.best_move, result.pv.get(1)) {
+                (Some(best_move), Some(ponder)) => println!(
+                    "bestmove {} ponder {}",
+                    uci_notation(best_move).as_str(),
+                    uci_notation(*ponder).as_str()
+                ),
+                (Some(best_move), None) => {
+                    println!("bestmove {}", uci_notation(best_move).as_str());
+                }
+                (None, _) => println!("bestmove 0000"),
+            },
+        };
+
+        self.search = Some(if ponder {
+            searcher.spawn_pondering(game, limits, on_event)
+        } else {
+            searcher.spawn(game, limits, on_event)
+        });
+    }
+
+    /// stops a running search and waits for it to send its `bestmove`
+    fn stop_search(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        search.stop();
+        let (searcher, _) = search.join();
+        self.searcher = Some(searcher);
+    }
+
+    /// a fresh searcher forgets everything from earlier games, and picks up a new hash size.
+    /// the scores in the old table don't hold for new weights either.
+    fn replace_searcher(&mut self) {
+        let table = TranspositionTable::new(self.hash_megabytes, ReplacementScheme::default());
+        self.searcher = Some(Searcher::with_table(Arc::new(table)));
+    }
+}
+
+fn parse_position<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+) -> Result<Option<GameState<Ongoing>>, String> {
+    let game = match tokens.next() {
+        Some("startpos") => GameState::new(),
+        Some("fen") => {
+            let fen = tokens
+                .by_ref()
+                .take_while(|token| *token != "moves")
+                .collect::<Vec<_>>()
+                .join(" ");
+            let core = GameStateCore::try_from_fen(&fen).map_err(|error| format!("{error:?}"))?;
+            return apply_moves(GameState::with_core(core), tokens);
+        }
+        other => return Err(format!("expected startpos or fen, got {other:?}")),
+    };
+
+    match tokens.next() {
+        None => Ok(Some(game)),
+        Some("moves") => apply_moves(game, tokens),
+        Some(other) => Err(format!("expected moves, got {other}")),
+    }
+}
+
+fn apply_moves<'a>(
+    mut game: GameState<Ongoing>,
+    moves: impl Iterator<Item = &'a str>,
+) -> Result<Option<GameState<Ongoing>>, String> {
+    for text in moves {
+        let mv = parse_uci_move(&game, text).map_err(|error| format!("{text}: {error:?}"))?;
+        match game.step(mv) {
+            StepResult::Ongoing(next) => game = next,
+            StepResult::Terminated(_) => return Ok(None),
+        }
+    }
+    Ok(Some(game))
+}
+
+/// the limits, and whether to start out pondering
+fn parse_go<'a>(
+    game: &GameState<Ongoing>,
+    tokens: impl Iterator<Item = &'a str>,
+) -> (SearchLimits, bool) {
+    fn millis(token: Option<&str>) -> Option<Duration> {
+        // some guis send negative times once the clock ran out
+        let millis = token?.parse::<i64>().ok()?;
+        Some(Duration::from_millis(millis.max(0).unsigned_abs()))
+    }
+
+    let mut limits = SearchLimits::default();
+    let mut ponder = false;
+    let mut tokens = tokens.peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            "wtime" => limits.wtime = millis(tokens.next()),
+            "btime" => limits.btime = millis(tokens.next()),
+            "winc" => limits.winc = millis(tokens.next()),
+            "binc" => limits.binc = millis(tokens.next()),
+            "movetime" => limits.movetime = millis(tokens.next()),
+            "movestogo" => limits.movestogo = tokens.next().and_then(|n| n.parse().ok()),
+            "depth" => limits.depth = tokens.next().and_then(|n| n.parse().ok()),
+            "nodes" => limits.nodes = tokens.next().and_then(|n| n.parse().ok()),
+            "mate" => limits.mate = tokens.next().and_then(|n| n.parse().ok()),
+            "infinite" => limits.infinite = true,
+            "ponder" => ponder = true,
+            // the moves run up to the next keyword, which doesn't parse as one
+            "searchmoves" => {
+                while let Some(mv) = tokens
+                    .peek()
+                    .and_then(|text| parse_uci_move(game, text).ok())
+                {
+                    limits.searchmoves.push(mv);
+                    tokens.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    (limits, ponder)
+}
+
+fn info_line(result: &SearchResult, multipv: usize, line: &PvLine, hashfull: usize) -> String {
+    let score = line.score.mate_moves().map_or_else(
+        || format!("cp {}", line.score.0),
+        |moves| format!("mate {moves}"),
+    );
+    let millis = result.elapsed.as_millis();
+    let nps = u128::from(result.nodes) * 1000 / millis.max(1);
+    let pv = line
+        .pv
+        .iter()
+        .map(|mv| String::from(uci_notation(*mv).as_str()))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "info depth {} multipv {multipv} score {score} nodes {} nps {nps} time {millis} \
+         hashfull {hashfull} pv {pv}",
+        result.depth, result.nodes
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fen(game: &GameState<Ongoing>) -> String {
+        String::from(game.core.to_fen().as_str())
+    }
+
+    #[test]
+    fn position_startpos_with_moves() {
+        let game = parse_position("startpos moves e2e4 c7c5 g1f3".split_whitespace())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            fen(&game)
+        );
+
+        let game = parse_position("startpos".split_whitespace())
+            .unwrap()
+            .unwrap();
+        assert_eq!(fen(&GameState::new()), fen(&game));
+
+        assert!(parse_position("startpos moves e2e5".split_whitespace()).is_err());
+        assert!(parse_position("startpos e2e4".split_whitespace()).is_err());
+        assert!(parse_position("".split_whitespace()).is_err());
+    }
+
+    #[test]
+    fn position_fen_with_moves() {
+        let game =
+            parse_position("fen 4k3/8/8/8/8/8/8/R3K3 w Q - 0 1 moves e1c1".split_whitespace())
+                .unwrap()
+                .unwrap();
+        assert_eq!("4k3/8/8/8/8/8/8/2KR4 b - - 1 1", fen(&game));
+
+        let game = parse_position("fen 4k3/8/8/8/8/8/8/R3K3 w Q - 0 1".split_whitespace())
+            .unwrap()
+            .unwrap();
+        assert_eq!("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1", fen(&game));
+
+        // the moves end the game, there's nothing left to search
+        let mated =
+            parse_position("fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1 moves a1a8".split_whitespace());
+        assert_eq!(None, mated.unwrap());
+
+        assert!(parse_position("fen 4k3/8 w - - 0 1".split_whitespace()).is_err());
+    }
+
+    #[test]
+    fn go_limits() {
+        let game = GameState::new();
+        let (limits, ponder) = parse_go(
+            &game,
+            "wtime 60000 btime 50000 winc 1000 binc 2000 movestogo 20 ponder".split_whitespace(),
+        );
+        assert_eq!(
+            SearchLimits {
+                wtime: Some(Duration::from_mins(1)),
+                btime: Some(Duration::from_secs(50)),
+                winc: Some(Duration::from_secs(1)),
+                binc: Some(Duration::from_secs(2)),
+                movestogo: Some(20),
+                ..SearchLimits::default()
+            },
+            limits
+        );
+        assert!(ponder);
+
+        let (limits, ponder) = parse_go(
+            &game,
+            "depth 7 nodes 100000 mate 3 movetime 1500 infinite".split_whitespace(),
+        );
+        assert_eq!(
+            SearchLimits {
+                depth: Some(7),
+                nodes: Some(100_000),
+                mate: Some(3),
+                movetime: Some(Duration::from_millis(1500)),
+                infinite: true,
+                ..SearchLimits::default()
+            },
+            limits
+        );
+        assert!(!ponder);
+
+        let (limits, _) = parse_go(&game, "".split_whitespace());
+        assert_eq!(SearchLimits::default(), limits);
+    }
+
+    #[test]
+    fn go_with_negative_times() {
+        let (limits, _) = parse_go(
+            &GameState::new(),
+            "wtime -350 btime -1 winc 0 binc 0".split_whitespace(),
+        );
+        assert_eq!(Some(Duration::ZERO), limits.wtime);
+        assert_eq!(Some(Duration::ZERO), limits.btime);
+        assert_eq!(Some(Duration::ZERO), limits.winc);
+    }
+
+    #[test]
+    fn go_searchmoves() {
+        let game = GameState::new();
+        let (limits, _) = parse_go(&game, "searchmoves e2e4 d2d4 depth 5".split_whitespace());
+        assert_eq!(
+            vec![
+                parse_uci_move(&game, "e2e4").unwrap(),
+                parse_uci_move(&game, "d2d4").unwrap()
+            ],
+            limits.searchmoves
+        );
+        assert_eq!(Some(5), limits.depth);
+
+        // an illegal move ends the list like a keyword
+        let (limits, _) = parse_go(&game, "searchmoves e2e4 e2e5 infinite".split_whitespace());
+        assert_eq!(1, limits.searchmoves.len());
+        assert!(limits.infinite);
+    }
+}
@@ -0,0 +1,554 @@
+#![feature(ascii_char)]
+#![deny(clippy::pedantic, clippy::nursery)]
+#![forbid(unsafe_code)]
+
+use std::io::BufRead;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use schach::eval::EvalWeights;
+use schach::game::DrawKind;
+use schach::game::GameResultKind;
+use schach::game::GameState;
+use schach::game::GameStateCore;
+use schach::game::Ongoing;
+use schach::game::StepResult;
+use schach::mv::Move;
+use schach::notation::uci::parse_uci_move;
+use schach::notation::uci::uci_notation;
+use schach::player::PlayerKind;
+use schach::search::SearchResult;
+use schach::search::Searcher;
+use schach::search_thread::SearchEvent;
+use schach::search_thread::SearchHandle;
+use schach::time::SearchLimits;
+
+const MAX_THREADS: usize = 256;
+
+fn main() {
+    let mut engine = Engine::new();
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if engine.handle(&line) == Flow::Quit {
+            break;
+        }
+    }
+    engine.finish_search(Finish::Abandon);
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Flow {
+    Continue,
+    Quit,
+}
+
+/// what to do about a search that may still be running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Finish {
+    /// let it run out on its own and play its move
+    Wait,
+    /// stop it and play the best move found so far
+    MoveNow,
+    /// stop it and forget about its move
+    Abandon,
+}
+
+/// the `level` command: `moves_per_session` moves in `base` time, 0 meaning all of them
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    moves_per_session: u32,
+    base: Duration,
+    increment: Duration,
+}
+
+impl Default for Level {
+    // what xboard itself starts out with
+    fn default() -> Self {
+        Self {
+            moves_per_session: 40,
+            base: Duration::from_mins(5),
+            increment: Duration::ZERO,
+        }
+    }
+}
+
+struct Engine {
+    /// every position of the game so far, for `undo` and `remove`. never empty.
+    positions: Vec<GameState<Ongoing>>,
+    /// the last move ended the game, there is no position after it
+    game_over: bool,
+    /// `None` in force mode, where moves are only recorded
+    plays: Option<PlayerKind>,
+    level: Level,
+    /// from `st`, replaces the level
+    move_time: Option<Duration>,
+    /// from `sd`
+    max_depth: Option<u32>,
+    /// from `time` and `otim`, the gui sends them right before it wants a move
+    own_time: Option<Duration>,
+    opponent_time: Option<Duration>,
+    post: bool,
+    /// from `cores`
+    threads: usize,
+    /// from the `EvalFile` option
+    weights: Arc<EvalWeights>,
+    /// lent to the search thread while it runs
+    searcher: Option<Searcher>,
+    search: Option<SearchHandle>,
+    /// whoever sets this first decides whether the searched move is sent: the search thread
+    /// once it's done, or an abandoning command before that
+    move_claimed: Arc<AtomicBool>,
+}
+
+impl Engine {
+    fn new() -> Self {
+        Self {
+            positions: vec![GameState::new()],
+            game_over: false,
+            plays: Some(PlayerKind::Black),
+            level: Level::default(),
+            move_time: None,
+            max_depth: None,
+            own_time: None,
+            opponent_time: None,
+            post: false,
+            threads: 1,
+            weights: Arc::new(EvalWeights::STANDARD),
+            searcher: Some(Searcher::new()),
+            search: None,
+            move_claimed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn handle(&mut self, line: &str) -> Flow {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else {
+            return Flow::Continue;
+        };
+        let argument = |index| line.split_whitespace().nth(index);
+
+        match command {
+            "protover" => println!(
+                "feature myname=\"schach {}\" ping=1 setboard=1 usermove=1 san=0 colors=0 \
+                 sigint=0 sigterm=0 reuse=1 analyze=0 smp=1 option=\"EvalFile -file \" done=1",
+                env!("CARGO_PKG_VERSION")
+            ),
+            "new" => {
+                self.finish_search(Finish::Abandon);
+                self.positions = vec![GameState::new()];
+                self.game_over = false;
+                self.plays = Some(PlayerKind::Black);
+                self.move_time = None;
+                self.max_depth = None;
+                self.searcher = Some(Searcher::new());
+            }
+            "setboard" => {
+                self.finish_search(Finish::Abandon);
+                let fen = tokens.collect::<Vec<_>>().join(" ");
+                match GameStateCore::try_from_fen(&fen) {
+                    Ok(core) => {
+                        self.positions = vec![GameState::with_core(core)];
+                        self.game_over = false;
+                        self.end_if_out_of_moves();
+                    }
+                    Err(error) => println!("tellusererror Illegal position: {error:?}"),
+                }
+            }
+            "usermove" => self.user_move(argument(1).unwrap_or_default()),
+            "go" => {
+                self.finish_search(Finish::Abandon);
+                self.plays = Some(self.position().core.active_player);
+                if !self.end_if_out_of_moves() {
+                    self.think();
+                }
+            }
+            "?" => self.finish_search(Finish::MoveNow),
+            "force" | "result" => {
+                self.finish_search(Finish::Abandon);
+                self.plays = None;
+            }
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+            "level" => match parse_level(tokens) {
+                Some(level) => {
+                    self.level = level;
+                    self.move_time = None;
+                }
+                None => println!("Error (malformed level): {line}"),
+            },
+            "st" => match argument(1).and_then(|seconds| seconds.parse().ok()) {
+                Some(seconds) => self.move_time = Some(Duration::from_secs(seconds)),
+                None => println!("Error (malformed st): {line}"),
+            },
+            "sd" => match argument(1).and_then(|depth| depth.parse().ok()) {
+                Some(depth) => self.max_depth = Some(depth),
+                None => println!("Error (malformed sd): {line}"),
+            },
+            "cores" => match argument(1).and_then(|cores| cores.parse::<usize>().ok()) {
+                Some(cores) => self.threads = cores.clamp(1, MAX_THREADS),
+                None => println!("Error (malformed cores): {line}"),
+            },
+            "option" => match line.trim_start().strip_prefix("option").map(str::trim) {
+                Some(option) => match option.split_once('=') {
+                    Some(("EvalFile", path)) => match EvalWeights::from_file(path) {
+                        Ok(weights) => {
+                            self.finish_search(Finish::Abandon);
+                            self.weights = Arc::new(weights);
+                            // what's in the table was evaluated with the old weights
+                            self.searcher = Some(Searcher::new());
+                        }
+                        Err(error) => {
+                            println!("tellusererror Invalid eval file: {path}: {error:?}");
+                        }
+                    },
+                    _ => println!("Error (unknown option): {line}"),
+                },
+                None => println!("Error (malformed option): {line}"),
+            },
+            "time" => self.own_time = argument(1).and_then(centiseconds),
+            "otim" => self.opponent_time = argument(1).and_then(centiseconds),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "ping" => {
+                // the pong has to come after a move that's being thought about
+                self.finish_search(Finish::Wait);
+                println!("pong {}", argument(1).unwrap_or_default());
+            }
+            "quit" => return Flow::Quit,
+            // pondering, draw offers and information about the opponent aren't acted on
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "draw" | "name"
+            | "rating" | "computer" | "ics" | "white" | "black" | "variant" => {}
+            _ => println!("Error (unknown command): {command}"),
+        }
+        Flow::Continue
+    }
+
+    fn position(&self) -> &GameState<Ongoing> {
+        self.positions
+            .last()
+            .expect("there to always be a current position")
+    }
+
+    fn user_move(&mut self, text: &str) {
+        self.finish_search(Finish::Abandon);
+        if self.game_over {
+            println!("Illegal move (the game is over): {text}");
+            return;
+        }
+        let Ok(mv) = parse_uci_move(self.position(), text) else {
+            println!("Illegal move: {text}");
+            return;
+        };
+        self.play(mv);
+        if self.plays == Some(self.position().core.active_player) {
+            self.think();
+        }
+    }
+
+    fn play(&mut self, mv: Move) {
+        match self.position().clone().step(mv) {
+            StepResult::Ongoing(next) => self.positions.push(next),
+            StepResult::Terminated(_) => self.game_over = true,
+        }
+    }
+
+    /// a position set up without legal moves is over before anything is played in it, which
+    /// is sent as its result
+    fn end_if_out_of_moves(&mut self) -> bool {
+        let game = self.position();
+        if game.legal_moves().next().is_some() {
+            return false;
+        }
+        let kind = game.rule_set.out_of_moves_result(&game.core);
+        println!("{}", result_line(kind, game.core.active_player.opponent()));
+        self.game_over = true;
+        true
+    }
+
+    fn take_back(&mut self, plies: usize) {
+        self.finish_search(Finish::Abandon);
+        for _ in 0..plies {
+            // the move that ended the game has no position of its own
+            if self.game_over {
+                self.game_over = false;
+            } else if self.positions.len() > 1 {
+                self.positions.pop();
+            }
+        }
+    }
+
+    fn limits(&self, player: PlayerKind) -> SearchLimits {
+        let mut limits = SearchLimits {
+            depth: self.max_depth,
+            ..SearchLimits::default()
+        };
+        if let Some(move_time) = self.move_time {
+            limits.movetime = Some(move_time);
+            return limits;
+        }
+
+        let own_time = self.own_time.unwrap_or(self.level.base);
+        let opponent_time = self.opponent_time.unwrap_or(self.level.base);
+        let (white, black) = match player {
+            PlayerKind::White => (own_time, opponent_time),
+            PlayerKind::Black => (opponent_time, own_time),
+        };
+        limits.wtime = Some(white);
+        limits.btime = Some(black);
+        limits.winc = Some(self.level.increment);
+        limits.binc = Some(self.level.increment);
+        if self.level.moves_per_session > 0 {
+            let moves_played = self.position().core.full_move_count.0.get() - 1;
+            let session = u64::from(self.level.moves_per_session);
+            limits.movestogo = u32::try_from(session - moves_played % session).ok();
+        }
+        limits
+    }
+
+    /// searches for a move for the side to move on a background thread, which also plays it
+    fn think(&mut self) {
+        if self.game_over {
+            return;
+        }
+        let game = self.position().clone();
+        let limits = self.limits(game.core.active_player);
+        let post = self.post;
+
+        let searcher = self
+            .searcher
+            .take()
+            .expect("no search to be running")
+            .with_threads(self.threads)
+            .with_weights(Arc::clone(&self.weights));
+        let move_claimed = Arc::new(AtomicBool::new(false));
+        self.move_claimed = Arc::clone(&move_claimed);
+
+        let mover = game.core.active_player;
+        self.search =
+            Some(searcher.spawn(
+                game.clone(),
+                limits,
+                move |event: SearchEvent<'_>| match event {
+                    SearchEvent::Iteration(result) => {
+                        if post {
+                            println!("{}", thinking_line(result));
+                        }
+                    }
+                    SearchEvent::Finished(result) => {
+                        let Some(best_move) = result.best_move else {
+                            return;
+                        };
+                        if move_claimed.swap(true, Ordering::Relaxed) {
+                            return;
+                        }
+                        println!("move {}", uci_notation(best_move).as_str());
+                        if let StepResult::Terminated(ended) = game.clone().step(best_move) {
+                            println!("{}", result_line(ended.kind, mover));
+                        }
+                    }
+                },
+            ));
+    }
+
+    fn finish_search(&mut self, finish: Finish) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        // a move that was already sent counts, even if the search was meant to be abandoned
+        let move_sent = match finish {
+            Finish::Wait => true,
+            Finish::MoveNow => {
+                search.stop();
+                true
+            }
+            Finish::Abandon => {
+                search.stop();
+                self.move_claimed.swap(true, Ordering::Relaxed)
+            }
+        };
+
+        let (searcher, result) = search.join();
+        self.searcher = Some(searcher);
+        if let Some(best_move) = result.best_move
+            && move_sent
+        {
+            self.play(best_move);
+        }
+    }
+}
+
+/// `level 40 5 0`, `level 0 2:30 1.5`: moves per session, base minutes[:seconds], increment seconds
+fn parse_level<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Option<Level> {
+    let moves_per_session = tokens.next()?.parse().ok()?;
+    let base = tokens.next()?;
+    let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+    let base =
+        Duration::from_secs(minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?);
+    let increment = Duration::try_from_secs_f64(tokens.next()?.parse().ok()?).ok()?;
+    Some(Level {
+        moves_per_session,
+        base,
+        increment,
+    })
+}
+
+fn centiseconds(text: &str) -> Option<Duration> {
+    // some guis send negative times once the clock ran out
+    let centiseconds = text.parse::<i64>().ok()?;
+    Some(Duration::from_millis(
+        centiseconds.max(0).unsigned_abs() * 10,
+    ))
+}
+
+/// `ply score time nodes pv`, with the time in centiseconds
+fn thinking_line(result: &SearchResult) -> String {
+    // xboard shows scores past 100000 as mates
+    let score = result.score.mate_moves().map_or(result.score.0, |moves| {
+        if moves > 0 {
+            100_000 + moves
+        } else {
+            -100_000 + moves
+        }
+    });
+    let pv = result
+        .pv
+        .iter()
+        .map(|mv| String::from(uci_notation(*mv).as_str()))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "{} {score} {} {} {pv}",
+        result.depth,
+        result.elapsed.as_millis() / 10,
+        result.nodes
+    )
+}
+
+fn result_line(kind: GameResultKind, mover: PlayerKind) -> String {
+    let winner = match kind {
+        GameResultKind::Win => mover,
+        GameResultKind::Loss => mover.opponent(),
+        GameResultKind::Draw(draw) => {
+            let reason = match draw {
+                DrawKind::Stalemate => "Stalemate",
+                DrawKind::ThreefoldRepetition => "Draw by repetition",
+                DrawKind::FiftyMove => "Draw by fifty move rule",
+                DrawKind::InsufficientMaterial => "Insufficient material",
+                DrawKind::BothKingsReachedGoal => "Both kings reached the goal",
+            };
+            return format!("1/2-1/2 {{{reason}}}");
+        }
+    };
+    match winner {
+        PlayerKind::White => String::from("1-0 {White wins}"),
+        PlayerKind::Black => String::from("0-1 {Black wins}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_with_whole_minutes() {
+        let level = parse_level("40 5 0".split_whitespace()).unwrap();
+        assert_eq!(40, level.moves_per_session);
+        assert_eq!(Duration::from_mins(5), level.base);
+        assert_eq!(Duration::ZERO, level.increment);
+    }
+
+    #[test]
+    fn level_with_seconds_and_a_fractional_increment() {
+        let level = parse_level("0 2:30 1.5".split_whitespace()).unwrap();
+        assert_eq!(0, level.moves_per_session);
+        assert_eq!(Duration::from_secs(150), level.base);
+        assert_eq!(Duration::from_millis(1500), level.increment);
+
+        assert!(parse_level("40 5".split_whitespace()).is_none());
+        assert!(parse_level("40 5:x 0".split_whitespace()).is_none());
+        assert!(parse_level("40 5 -1".split_whitespace()).is_none());
+    }
+
+    #[test]
+    fn level_limits() {
+        let mut engine = Engine::new();
+        engine.handle("level 40 5 2");
+        engine.handle("time 25000");
+        engine.handle("otim 30000");
+
+        let limits = engine.limits(PlayerKind::Black);
+        assert_eq!(Some(Duration::from_secs(250)), limits.btime);
+        assert_eq!(Some(Duration::from_mins(5)), limits.wtime);
+        assert_eq!(Some(Duration::from_secs(2)), limits.binc);
+        assert_eq!(Some(40), limits.movestogo);
+        assert_eq!(None, limits.movetime);
+    }
+
+    #[test]
+    fn st_and_sd() {
+        let mut engine = Engine::new();
+        engine.handle("st 5");
+        engine.handle("sd 4");
+
+        let limits = engine.limits(PlayerKind::White);
+        assert_eq!(Some(Duration::from_secs(5)), limits.movetime);
+        assert_eq!(Some(4), limits.depth);
+        assert_eq!(None, limits.wtime);
+
+        // a level replaces the time per move, but keeps the depth
+        engine.handle("level 0 1 0");
+        let limits = engine.limits(PlayerKind::White);
+        assert_eq!(None, limits.movetime);
+        assert_eq!(Some(Duration::from_mins(1)), limits.wtime);
+        assert_eq!(Some(4), limits.depth);
+
+        engine.handle("st x");
+        engine.handle("sd");
+        assert_eq!(None, engine.move_time);
+        assert_eq!(Some(4), engine.max_depth);
+    }
+
+    #[test]
+    fn usermove_in_force_mode_is_only_recorded() {
+        let mut engine = Engine::new();
+        engine.handle("force");
+        engine.handle("usermove e2e4");
+        engine.handle("usermove e7e5");
+        engine.handle("usermove e4e5");
+
+        assert_eq!(3, engine.positions.len());
+        assert!(engine.search.is_none());
+        assert_eq!(PlayerKind::White, engine.position().core.active_player);
+    }
+
+    #[test]
+    fn usermove_is_answered() {
+        let mut engine = Engine::new();
+        engine.handle("sd 1");
+        engine.handle("usermove e2e4");
+        assert!(engine.search.is_some());
+
+        engine.finish_search(Finish::Wait);
+        assert_eq!(3, engine.positions.len());
+        assert_eq!(PlayerKind::White, engine.position().core.active_player);
+    }
+
+    #[test]
+    fn usermove_after_the_game_ended_is_illegal() {
+        let mut engine = Engine::new();
+        engine.handle("force");
+        for mv in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            engine.handle(&format!("usermove {mv}"));
+        }
+        assert!(engine.game_over);
+
+        engine.handle("usermove e1f2");
+        assert_eq!(4, engine.positions.len());
+        assert!(engine.game_over);
+    }
+}
@@ -1,11 +1,12 @@
 use alloc::vec::Vec;
 
 use crate::coord::Col;
-use crate::coord::Row;
+use crate::coord::ColOf;
 use crate::coord::Square;
+use crate::coord::SquareOf;
 use crate::game::PieceCounts;
 use crate::game::attacked_squares;
-use crate::mv::Threat;
+use crate::mv::ThreatOf;
 use crate::piece::Piece;
 use crate::piece::PieceKind;
 use crate::player::PlayerKind;
@@ -13,54 +14,74 @@ use crate::player::PlayerKind;
 pub const COL_COUNT: usize = 8;
 pub const ROW_COUNT: usize = 8;
 
+pub type Board = BoardOf<COL_COUNT, ROW_COUNT>;
+
 #[derive(Copy, Clone, PartialEq, Eq)]
-pub struct Board(pub [[Option<Piece>; ROW_COUNT]; COL_COUNT]);
-impl Board {
+pub struct BoardOf<const COLS: usize, const ROWS: usize>(pub [[Option<Piece>; ROWS]; COLS]);
+impl<const COLS: usize, const ROWS: usize> BoardOf<COLS, ROWS> {
     #[must_use]
     pub const fn empty() -> Self {
-        Self([[None; ROW_COUNT]; COL_COUNT])
+        Self([[None; ROWS]; COLS])
     }
 
     pub(crate) fn threatening_moves_by(
         &self,
         threatened_by: PlayerKind,
-    ) -> impl Iterator<Item = Threat> {
-        Square::ALL
-            .iter()
-            .flat_map(move |square| crate::game::attacked_squares(self, *square, threatened_by))
+    ) -> impl Iterator<Item = ThreatOf<COLS, ROWS>> {
+        SquareOf::all()
+            .flat_map(move |square| crate::game::attacked_squares(self, square, threatened_by))
     }
 
-    pub fn threatened_squares_by(&self, threatened_by: PlayerKind) -> impl Iterator<Item = Square> {
-        Square::ALL.iter().flat_map(move |square| {
-            attacked_squares(self, *square, threatened_by).map(|threat| threat.destination)
+    pub fn threatened_squares_by(
+        &self,
+        threatened_by: PlayerKind,
+    ) -> impl Iterator<Item = SquareOf<COLS, ROWS>> {
+        SquareOf::all().flat_map(move |square| {
+            attacked_squares(self, square, threatened_by).map(|threat| threat.destination)
         })
     }
 
+    pub fn pieces(&self) -> impl Iterator<Item = (SquareOf<COLS, ROWS>, Piece)> {
+        SquareOf::all().filter_map(|square| self[square].map(|piece| (square, piece)))
+    }
+
     #[must_use]
-    pub fn king_position(&self, king_owner: PlayerKind) -> Square {
-        *Square::ALL
-            .iter()
-            .find(|square| {
-                self[**square]
-                    == Some(Piece {
-                        kind: PieceKind::King,
-                        owner: king_owner,
-                    })
-            })
-            .expect("where did the king go?")
+    pub fn king_position(&self, king_owner: PlayerKind) -> Option<SquareOf<COLS, ROWS>> {
+        SquareOf::all().find(|square| {
+            self[*square]
+                == Some(Piece {
+                    kind: PieceKind::King,
+                    owner: king_owner,
+                })
+        })
     }
 
     #[must_use]
     pub fn is_king_checked(&self, king_owner: PlayerKind) -> bool {
+        let Some(king_position) = self.king_position(king_owner) else {
+            return false; // no king, no check. (antichess, or a king that just got captured)
+        };
         self.threatened_squares_by(king_owner.opponent())
-            .any(|square| square == self.king_position(king_owner))
+            .any(|square| square == king_position)
     }
 
-    pub const fn mov(&mut self, start: Square, target: Square) {
+    pub const fn mov(&mut self, start: SquareOf<COLS, ROWS>, target: SquareOf<COLS, ROWS>) {
         self[target] = self[start];
         self[start] = None;
     }
 
+    #[must_use]
+    pub(crate) fn piece_counts(&self) -> PieceCounts {
+        let mut piece_counts = PieceCounts::default();
+        for square in SquareOf::all() {
+            if let Some(piece) = self[square] {
+                piece_counts[piece] += 1;
+            }
+        }
+        piece_counts
+    }
+}
+impl Board {
     #[must_use]
     pub const fn new() -> Self {
         #[allow(clippy::wildcard_imports)]
@@ -107,62 +128,55 @@ impl Board {
 
         board
     }
-
-    #[must_use]
-    pub fn piece_counts(&self) -> PieceCounts {
-        let mut piece_counts = PieceCounts::default();
-        for square in Square::ALL {
-            if let Some(piece) = self[square] {
-                piece_counts[piece] += 1;
-            }
-        }
-        piece_counts
-    }
 }
 impl const Default for Board {
     fn default() -> Self {
         Self::new()
     }
 }
-impl const core::ops::Index<Square> for Board {
+impl<const COLS: usize, const ROWS: usize> const core::ops::Index<SquareOf<COLS, ROWS>>
+    for BoardOf<COLS, ROWS>
+{
     type Output = Option<Piece>;
-    fn index(&self, index: Square) -> &Self::Output {
+    fn index(&self, index: SquareOf<COLS, ROWS>) -> &Self::Output {
         let col = usize::from(u8::from(index.col) - 1);
         let row = usize::from(u8::from(index.row) - 1);
         &self.0[col][row]
     }
 }
-impl const core::ops::IndexMut<Square> for Board {
-    fn index_mut(&mut self, index: Square) -> &mut Self::Output {
+impl<const COLS: usize, const ROWS: usize> const core::ops::IndexMut<SquareOf<COLS, ROWS>>
+    for BoardOf<COLS, ROWS>
+{
+    fn index_mut(&mut self, index: SquareOf<COLS, ROWS>) -> &mut Self::Output {
         let col = usize::from(u8::from(index.col) - 1);
         let row = usize::from(u8::from(index.row) - 1);
         &mut self.0[col][row]
     }
 }
-impl core::fmt::Debug for Board {
+impl<const COLS: usize, const ROWS: usize> core::fmt::Debug for BoardOf<COLS, ROWS> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         writeln!(f)?;
-        for square in Square::ALL {
-            if square.col == Col::_1 {
-                write!(f, "{}", i8::from(square.row))?;
+        for square in SquareOf::<COLS, ROWS>::all() {
+            if square.col == ColOf::FIRST {
+                write!(f, "{}", u8::from(square.row))?;
             }
             match self[square] {
                 Some(piece) => write!(f, "{piece} ")?,
                 None => {
                     if square.is_black() {
-                        write!(f, "□ ",)?;
+                        write!(f, "□ ")?;
                     } else {
-                        write!(f, "■ ",)?;
+                        write!(f, "■ ")?;
                     }
                 }
             }
-            if square.col == Col::_8 {
+            if square.col == ColOf::LAST {
                 writeln!(f)?;
             }
         }
         write!(f, "  ")?;
-        for col in Col::ALL {
-            write!(f, "{} ", i8::from(col))?;
+        for col in ColOf::<COLS>::ALL {
+            write!(f, "{} ", u8::from(col))?;
         }
         Ok(())
     }
@@ -178,23 +192,23 @@ impl core::fmt::Debug for DebugBoard {
         writeln!(f)?;
         for square in Square::ALL {
             if square.col == Col::_1 {
-                write!(f, "{}", i8::from(square.row))?;
+                write!(f, "{}", u8::from(square.row))?;
             }
             if self.highlighted_squares.contains(&square) {
-                write!(f, "\x1B[31m",)?;
+                write!(f, "\x1B[31m")?;
             }
             match self.inner[square] {
                 Some(piece) => write!(f, "{piece} ")?,
                 None => {
                     if square.is_black() {
-                        write!(f, "□ ",)?;
+                        write!(f, "□ ")?;
                     } else {
-                        write!(f, "■ ",)?;
+                        write!(f, "■ ")?;
                     }
                 }
             }
             if self.highlighted_squares.contains(&square) {
-                write!(f, "\x1B[0m",)?;
+                write!(f, "\x1B[0m")?;
             }
             if square.col == Col::_8 {
                 writeln!(f)?;
@@ -202,7 +216,7 @@ impl core::fmt::Debug for DebugBoard {
         }
         write!(f, "  ")?;
         for col in Col::ALL {
-            write!(f, "{} ", i8::from(col))?;
+            write!(f, "{} ", u8::from(col))?;
         }
         Ok(())
     }
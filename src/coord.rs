@@ -1,26 +1,29 @@
 use core::ops::Not;
 
-use crate::board::COL_COUNT;
-use crate::board::ROW_COUNT;
-use self::Square as S;
 use self::Col as C;
 use self::Row as R;
+use self::Square as S;
+use crate::board::COL_COUNT;
+use crate::board::ROW_COUNT;
+
+pub type Square = SquareOf<COL_COUNT, ROW_COUNT>;
+pub type Col = ColOf<COL_COUNT>;
+pub type Row = RowOf<ROW_COUNT>;
 
 #[derive_const(PartialEq, Eq)]
 #[derive(Debug, Copy, Clone, Hash)]
-pub struct Square {
-    pub col: Col,
-    pub row: Row,
+pub struct SquareOf<const COLS: usize, const ROWS: usize> {
+    pub col: ColOf<COLS>,
+    pub row: RowOf<ROWS>,
 }
 
-const fn s(col: Col, row: Row) -> Square{
+const fn s(col: Col, row: Row) -> Square {
     Square::new(col, row)
 }
 
-#[allow(clippy::use_self)]
-impl Square {
+impl<const COLS: usize, const ROWS: usize> SquareOf<COLS, ROWS> {
     #[must_use]
-    pub const fn new(col: Col, row: Row) -> Self {
+    pub const fn new(col: ColOf<COLS>, row: RowOf<ROWS>) -> Self {
         Self { col, row }
     }
     #[must_use]
@@ -32,6 +35,29 @@ impl Square {
         self.is_black().not()
     }
 
+    const GRID: [[Self; COLS]; ROWS] = {
+        let mut grid = [[Self::new(ColOf::FIRST, RowOf::FIRST); COLS]; ROWS];
+        let mut row = 0;
+        while row < ROWS {
+            let mut col = 0;
+            while col < COLS {
+                grid[row][col] =
+                    Self::new(ColOf::<COLS>::ALL[col], RowOf::<ROWS>::ALL[ROWS - 1 - row]);
+                col += 1;
+            }
+            row += 1;
+        }
+        grid
+    };
+
+    /// Same order as [`Square::ALL`], top row first. (`COLS * ROWS` can't be an array length yet)
+    pub fn all() -> impl Iterator<Item = Self> {
+        Self::GRID.into_iter().flatten()
+    }
+}
+
+#[allow(clippy::use_self)]
+impl Square {
     pub const A1: S = s(C::_1, R::_1);
     pub const A2: S = s(C::_1, R::_2);
     pub const A3: S = s(C::_1, R::_3);
@@ -103,7 +129,7 @@ impl Square {
     pub const H6: S = s(C::_8, R::_6);
     pub const H7: S = s(C::_8, R::_7);
     pub const H8: S = s(C::_8, R::_8);
-    
+
     #[rustfmt::skip]
     pub const ALL: [Self; 64] = [
         S::A8, S::B8, S::C8, S::D8, S::E8, S::F8, S::G8, S::H8,
@@ -115,65 +141,94 @@ impl Square {
         S::A2, S::B2, S::C2, S::D2, S::E2, S::F2, S::G2, S::H2,
         S::A1, S::B1, S::C1, S::D1, S::E1, S::F1, S::G1, S::H1,
     ];
+
+    /// dense index, a1 is 0, b1 is 1 and h8 is 63
+    #[must_use]
+    pub const fn index(self) -> usize {
+        usize::from(u8::from(self.row) - 1) * COL_COUNT + usize::from(u8::from(self.col) - 1)
+    }
+
+    #[must_use]
+    pub const fn from_index(index: usize) -> Option<Self> {
+        if index >= Self::ALL.len() {
+            return None;
+        }
+        Some(Self::new(
+            Col::ALL[index % COL_COUNT],
+            Row::ALL[index / COL_COUNT],
+        ))
+    }
 }
 
+/// 1-based column index, `COUNT` has to fit in a `u8`.
+#[derive_const(PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(Copy, Hash)]
+pub struct ColOf<const COUNT: usize>(u8);
+
+/// 1-based row index, `COUNT` has to fit in a `u8`.
 #[derive_const(PartialEq, Eq, PartialOrd, Ord, Clone)]
-#[derive(Debug, Copy, Hash)]
-pub enum Col {
-    _1,
-    _2,
-    _3,
-    _4,
-    _5,
-    _6,
-    _7,
-    _8,
+#[derive(Copy, Hash)]
+pub struct RowOf<const COUNT: usize>(u8);
+
+macro_rules! coord_impl {
+    ($($coord:ident)*) => {
+        $(
+            #[allow(clippy::cast_possible_truncation)]
+            impl<const COUNT: usize> $coord<COUNT> {
+                pub const FIRST: Self = Self(1);
+                pub const LAST: Self = Self(COUNT as u8);
+
+                pub const ALL: [Self; COUNT] = {
+                    let mut all = [Self(0); COUNT];
+                    let mut i = 0;
+                    while i < COUNT {
+                        all[i] = Self(i as u8 + 1);
+                        i += 1;
+                    }
+                    all
+                };
+            }
+            impl<const COUNT: usize> core::fmt::Debug for $coord<COUNT> {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    write!(f, "_{}", self.0)
+                }
+            }
+        )*
+    }
 }
+
+coord_impl!(ColOf RowOf);
+
 impl Col {
-    pub const ALL: [Self; COL_COUNT] = [
-        Self::_1,
-        Self::_2,
-        Self::_3,
-        Self::_4,
-        Self::_5,
-        Self::_6,
-        Self::_7,
-        Self::_8,
-    ];
+    pub const _1: Self = Self(1);
+    pub const _2: Self = Self(2);
+    pub const _3: Self = Self(3);
+    pub const _4: Self = Self(4);
+    pub const _5: Self = Self(5);
+    pub const _6: Self = Self(6);
+    pub const _7: Self = Self(7);
+    pub const _8: Self = Self(8);
 }
 
-#[derive_const(PartialEq, Eq, PartialOrd, Ord, Clone)]
-#[derive(Debug, Copy, Hash)]
-pub enum Row {
-    _1,
-    _2,
-    _3,
-    _4,
-    _5,
-    _6,
-    _7,
-    _8,
-}
 impl Row {
-    pub const ALL: [Self; ROW_COUNT] = [
-        Self::_1,
-        Self::_2,
-        Self::_3,
-        Self::_4,
-        Self::_5,
-        Self::_6,
-        Self::_7,
-        Self::_8,
-    ];
+    pub const _1: Self = Self(1);
+    pub const _2: Self = Self(2);
+    pub const _3: Self = Self(3);
+    pub const _4: Self = Self(4);
+    pub const _5: Self = Self(5);
+    pub const _6: Self = Self(6);
+    pub const _7: Self = Self(7);
+    pub const _8: Self = Self(8);
 }
-impl const core::ops::Add<i32> for Row {
+
+impl<const COUNT: usize> const core::ops::Add<i32> for RowOf<COUNT> {
     type Output = Result<Self, RowIndexOutOfRange>;
     fn add(self, rhs: i32) -> Self::Output {
         let row_number: i32 = self.into();
         (row_number + rhs).try_into()
     }
 }
-impl const core::ops::Add<i32> for Col {
+impl<const COUNT: usize> const core::ops::Add<i32> for ColOf<COUNT> {
     type Output = Result<Self, ColIndexOutOfRange>;
     fn add(self, rhs: i32) -> Self::Output {
         let column_number: i32 = self.into();
@@ -181,42 +236,12 @@ impl const core::ops::Add<i32> for Col {
     }
 }
 
-macro_rules! col_into_int_impl {
-    ($($ty:ty)*) => {
-        $(
-            impl const From<Col> for $ty {
-                fn from(value: Col) -> $ty {
-                    match value {
-                        Col::_1 => 1,
-                        Col::_2 => 2,
-                        Col::_3 => 3,
-                        Col::_4 => 4,
-                        Col::_5 => 5,
-                        Col::_6 => 6,
-                        Col::_7 => 7,
-                        Col::_8 => 8,
-                    }
-                }
-            }
-        )*
-    }
-}
-
-macro_rules! row_into_int_impl {
-    ($($ty:ty)*) => {
+macro_rules! coord_into_int_impl {
+    ($coord:ident: $($ty:ty)*) => {
         $(
-            impl const From<Row> for $ty {
-                fn from(value: Row) -> $ty {
-                    match value {
-                        Row::_1 => 1,
-                        Row::_2 => 2,
-                        Row::_3 => 3,
-                        Row::_4 => 4,
-                        Row::_5 => 5,
-                        Row::_6 => 6,
-                        Row::_7 => 7,
-                        Row::_8 => 8,
-                    }
+            impl<const COUNT: usize> const From<$coord<COUNT>> for $ty {
+                fn from(value: $coord<COUNT>) -> $ty {
+                    value.0.into()
                 }
             }
         )*
@@ -235,60 +260,53 @@ pub enum RowIndexOutOfRange {
     TooHigh,
 }
 
-macro_rules! col_try_from_int_impl {
-    ($($ty:ty)*) => {
+macro_rules! coord_try_from_int_impl {
+    ($coord:ident, $error:ident: $($ty:ty)*) => {
         $(
-            impl const TryFrom<$ty> for Col {
-                type Error = ColIndexOutOfRange;
+            impl<const COUNT: usize> const TryFrom<$ty> for $coord<COUNT> {
+                type Error = $error;
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    clippy::cast_sign_loss,
+                    clippy::cast_lossless,
+                    clippy::unnecessary_cast
+                )]
                 fn try_from(value: $ty) -> Result<Self, Self::Error> {
-                    match value {
-                        ..=0 => Err(ColIndexOutOfRange::TooLow),
-                         1 => Ok(Self::_1),
-                         2 => Ok(Self::_2),
-                         3 => Ok(Self::_3),
-                         4 => Ok(Self::_4),
-                         5 => Ok(Self::_5),
-                         6 => Ok(Self::_6),
-                         7 => Ok(Self::_7),
-                         8 => Ok(Self::_8),
-                         9.. =>  Err(ColIndexOutOfRange::TooHigh),
-
+                    if value < 1 {
+                        return Err($error::TooLow);
+                    }
+                    if value as u128 > COUNT as u128 {
+                        return Err($error::TooHigh);
                     }
+                    Ok(Self(value as u8))
                 }
             }
         )*
     }
 }
 
-macro_rules! row_try_from_int_impl {
-    ($($ty:ty)*) => {
+coord_into_int_impl!(ColOf: u8 u16 u32 u64 u128 usize i16 i32 i64 i128 isize);
+coord_into_int_impl!(RowOf: u8 u16 u32 u64 u128 usize i16 i32 i64 i128 isize);
+
+// an `i8` can't hold every `u8`, only the coordinates of boards up to 127 wide and long
+macro_rules! coord_into_i8_impl {
+    ($($coord:ident)*) => {
         $(
-            impl const TryFrom<$ty> for Row {
-                type Error = RowIndexOutOfRange;
-                fn try_from(value: $ty) -> Result<Self, Self::Error> {
-                    match value {
-                        ..=0 =>  Err(RowIndexOutOfRange::TooLow),
-                         1 => Ok(Self::_1),
-                         2 => Ok(Self::_2),
-                         3 => Ok(Self::_3),
-                         4 => Ok(Self::_4),
-                         5 => Ok(Self::_5),
-                         6 => Ok(Self::_6),
-                         7 => Ok(Self::_7),
-                         8 => Ok(Self::_8),
-                         9.. =>  Err(RowIndexOutOfRange::TooHigh),
-                    }
+            impl<const COUNT: usize> const From<$coord<COUNT>> for i8 {
+                #[allow(clippy::cast_possible_wrap)]
+                fn from(value: $coord<COUNT>) -> i8 {
+                    const { assert!(COUNT <= i8::MAX as usize, "the coordinates to fit in an i8") };
+                    value.0 as i8
                 }
             }
         )*
     }
 }
 
-col_into_int_impl!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
-row_into_int_impl!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+coord_into_i8_impl!(ColOf RowOf);
 
-col_try_from_int_impl!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
-row_try_from_int_impl!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+coord_try_from_int_impl!(ColOf, ColIndexOutOfRange: u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+coord_try_from_int_impl!(RowOf, RowIndexOutOfRange: u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) struct Offset {
@@ -331,6 +349,28 @@ impl Offset {
     pub(crate) const PAWN_UP_DIAGONAL: [Self; 2] = [Self::UL, Self::UR];
     pub(crate) const PAWN_DOWN_DIAGONAL: [Self; 2] = [Self::DL, Self::DR];
 
+    pub(crate) const CAMEL: [Self; 8] = [
+        Self::U * 3 + Self::L,
+        Self::U * 3 + Self::R,
+        Self::D * 3 + Self::L,
+        Self::D * 3 + Self::R,
+        Self::L * 3 + Self::U,
+        Self::L * 3 + Self::D,
+        Self::R * 3 + Self::U,
+        Self::R * 3 + Self::D,
+    ];
+
+    pub(crate) const ZEBRA: [Self; 8] = [
+        Self::U * 3 + Self::L * 2,
+        Self::U * 3 + Self::R * 2,
+        Self::D * 3 + Self::L * 2,
+        Self::D * 3 + Self::R * 2,
+        Self::L * 3 + Self::U * 2,
+        Self::L * 3 + Self::D * 2,
+        Self::R * 3 + Self::U * 2,
+        Self::R * 3 + Self::D * 2,
+    ];
+
     pub(crate) const KNIGHT: [Self; 8] = [
         Self::UUL,
         Self::UUR,
@@ -377,7 +417,7 @@ impl const From<RowIndexOutOfRange> for SquareOutOfRange {
         Self::Row(value)
     }
 }
-impl const core::ops::Add<Offset> for Square {
+impl<const COLS: usize, const ROWS: usize> const core::ops::Add<Offset> for SquareOf<COLS, ROWS> {
     type Output = Result<Self, SquareOutOfRange>;
     fn add(self, rhs: Offset) -> Self::Output {
         Ok(Self {
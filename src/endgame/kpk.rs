@@ -0,0 +1,229 @@
+//! which king and pawn versus king positions are won, worked out by retrograde analysis the
+//! first time one is looked up.
+//!
+//! squares are dense indices, a1 is 0 and h8 is 63. the pawn is white, on the files a to d.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicU8;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+/// both sides to move, the pawn on 24 squares, both kings anywhere
+const POSITIONS: usize = 2 * 24 * 64 * 64;
+
+static WINS: [AtomicU64; POSITIONS / 64] = [const { AtomicU64::new(0) }; POSITIONS / 64];
+static STATE: AtomicU8 = AtomicU8::new(EMPTY);
+const EMPTY: u8 = 0;
+const GENERATING: u8 = 1;
+const READY: u8 = 2;
+
+// what's known about a position, as flags so the results of all moves can be combined
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+/// whether white wins, with `pawn` on the files a to d
+pub(super) fn is_win(
+    white_king: usize,
+    pawn: usize,
+    black_king: usize,
+    white_to_move: bool,
+) -> bool {
+    generate_once();
+    let index = index(white_to_move, white_king, pawn, black_king);
+    WINS[index / 64].load(Ordering::Relaxed) >> (index % 64) & 1 == 1
+}
+
+fn generate_once() {
+    if STATE.load(Ordering::Acquire) == READY {
+        return;
+    }
+    if STATE
+        .compare_exchange(EMPTY, GENERATING, Ordering::Acquire, Ordering::Acquire)
+        .is_ok()
+    {
+        for (word, wins) in WINS.iter().zip(generate()) {
+            word.store(wins, Ordering::Relaxed);
+        }
+        STATE.store(READY, Ordering::Release);
+    } else {
+        // it only takes a moment, and happens once
+        while STATE.load(Ordering::Acquire) != READY {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+const fn index(white_to_move: bool, white_king: usize, pawn: usize, black_king: usize) -> usize {
+    let side = if white_to_move { 0 } else { 1 };
+    white_king | (black_king << 6) | (side << 12) | ((pawn % 8) << 13) | ((6 - pawn / 8) << 15)
+}
+
+struct Position {
+    white_to_move: bool,
+    white_king: usize,
+    black_king: usize,
+    pawn: usize,
+}
+
+impl Position {
+    const fn from_index(index: usize) -> Self {
+        Self {
+            white_to_move: (index >> 12) & 1 == 0,
+            white_king: index & 63,
+            black_king: (index >> 6) & 63,
+            pawn: (6 - (index >> 15)) * 8 + ((index >> 13) & 3),
+        }
+    }
+
+    /// what can be told without looking at any moves
+    const fn initial(&self) -> u8 {
+        let Self {
+            white_to_move,
+            white_king,
+            black_king,
+            pawn,
+        } = *self;
+        let promotion = pawn + 8;
+        if distance(white_king, black_king) <= 1
+            || white_king == pawn
+            || black_king == pawn
+            || (white_to_move && pawn_attacks(pawn) & bit(black_king) != 0)
+        {
+            INVALID
+        } else if white_to_move
+            && pawn / 8 == 6
+            && white_king != promotion
+            && (distance(black_king, promotion) > 1 || distance(white_king, promotion) == 1)
+        {
+            // promotes without being taken
+            WIN
+        } else if !white_to_move
+            && (king_attacks(black_king) & !(king_attacks(white_king) | pawn_attacks(pawn)) == 0
+                || king_attacks(black_king) & !king_attacks(white_king) & bit(pawn) != 0)
+        {
+            // stalemate, or the pawn is taken
+            DRAW
+        } else {
+            UNKNOWN
+        }
+    }
+
+    /// the best result of all moves, or still unknown
+    fn classify(&self, results: &[u8]) -> u8 {
+        let Self {
+            white_to_move,
+            white_king,
+            black_king,
+            pawn,
+        } = *self;
+        let mut combined = INVALID;
+        let mut king_moves = king_attacks(if white_to_move {
+            white_king
+        } else {
+            black_king
+        });
+        while king_moves != 0 {
+            let to = king_moves.trailing_zeros() as usize;
+            king_moves &= king_moves - 1;
+            combined |= if white_to_move {
+                results[index(false, to, pawn, black_king)]
+            } else {
+                results[index(true, white_king, pawn, to)]
+            };
+        }
+
+        if white_to_move {
+            if pawn / 8 < 6 {
+                combined |= results[index(false, white_king, pawn + 8, black_king)];
+            }
+            let double_step = pawn / 8 == 1 && pawn + 8 != white_king && pawn + 8 != black_king;
+            if double_step {
+                combined |= results[index(false, white_king, pawn + 16, black_king)];
+            }
+        }
+
+        let (good, bad) = if white_to_move {
+            (WIN, DRAW)
+        } else {
+            (DRAW, WIN)
+        };
+        if combined & good != 0 {
+            good
+        } else if combined & UNKNOWN != 0 {
+            UNKNOWN
+        } else {
+            bad
+        }
+    }
+}
+
+/// the won positions, a bit each
+fn generate() -> Vec<u64> {
+    let positions = (0..POSITIONS).map(Position::from_index).collect::<Vec<_>>();
+    let mut results = positions.iter().map(Position::initial).collect::<Vec<_>>();
+
+    // every pass settles the positions one move further away from a known result
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (index, position) in positions.iter().enumerate() {
+            if results[index] == UNKNOWN {
+                results[index] = position.classify(&results);
+                changed |= results[index] != UNKNOWN;
+            }
+        }
+    }
+
+    let mut wins = vec![0; POSITIONS / 64];
+    for (index, result) in results.iter().enumerate() {
+        if *result == WIN {
+            wins[index / 64] |= 1 << (index % 64);
+        }
+    }
+    wins
+}
+
+const fn bit(square: usize) -> u64 {
+    1 << square
+}
+
+const fn distance(a: usize, b: usize) -> usize {
+    let files = (a % 8).abs_diff(b % 8);
+    let ranks = (a / 8).abs_diff(b / 8);
+    if files > ranks { files } else { ranks }
+}
+
+const KING_ATTACKS: [u64; 64] = {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut other = 0;
+        while other < 64 {
+            if distance(square, other) == 1 {
+                attacks[square] |= bit(other);
+            }
+            other += 1;
+        }
+        square += 1;
+    }
+    attacks
+};
+
+const fn king_attacks(square: usize) -> u64 {
+    KING_ATTACKS[square]
+}
+
+const fn pawn_attacks(pawn: usize) -> u64 {
+    let mut attacks = 0;
+    let file = pawn % 8;
+    if file > 0 {
+        attacks |= bit(pawn + 7);
+    }
+    if file < 7 {
+        attacks |= bit(pawn + 9);
+    }
+    attacks
+}
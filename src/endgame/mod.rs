@@ -0,0 +1,257 @@
+//! what's known about endgames that the evaluation can't work out by itself: the exact result
+//! of king and pawn versus king, and material that can't win, or hardly ever does
+
+mod kpk;
+pub mod retrograde;
+
+use crate::coord::Square;
+use crate::game::GameStateCore;
+use crate::piece::Piece;
+use crate::piece::PieceKind;
+use crate::player::PlayerKind;
+
+/// how much of its evaluation the side that's ahead keeps, out of [`ScaleFactor::NORMAL`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScaleFactor(pub i32);
+
+impl ScaleFactor {
+    pub const DRAW: Self = Self(0);
+    pub const NORMAL: Self = Self(64);
+    /// bishops on opposite colors with nothing but pawns besides, the classic drawing margin
+    pub const OPPOSITE_BISHOPS: Self = Self(16);
+    /// bishops on opposite colors with other pieces still around, which give winning chances
+    pub const OPPOSITE_BISHOPS_WITH_PIECES: Self = Self(48);
+
+    #[must_use]
+    pub const fn apply(self, score: i32) -> i32 {
+        score * self.0 / Self::NORMAL.0
+    }
+}
+
+/// whether the side with the pawn wins, `None` for anything but a king and pawn versus a king
+#[must_use]
+pub fn probe_kpk(core: &GameStateCore) -> Option<bool> {
+    let mut pieces = core
+        .board
+        .pieces()
+        .filter(|(_, piece)| piece.kind != PieceKind::King);
+    let (
+        pawn,
+        Piece {
+            owner: strong,
+            kind: PieceKind::Pawn,
+        },
+    ) = pieces.next()?
+    else {
+        return None;
+    };
+    if pieces.next().is_some() {
+        return None;
+    }
+    let strong_king = core.board.king_position(strong)?;
+    let weak_king = core.board.king_position(strong.opponent())?;
+
+    // from the side of the pawn, with the pawn on the files a to d
+    let mirror_files = u8::from(pawn.col) > 4;
+    let normalize = |square: Square| {
+        let mut index = square.index();
+        if strong == PlayerKind::Black {
+            index ^= 0b11_1000;
+        }
+        if mirror_files {
+            index ^= 0b111;
+        }
+        index
+    };
+    let pawn = normalize(pawn);
+    if !(8..56).contains(&pawn) {
+        return None;
+    }
+    Some(kpk::is_win(
+        normalize(strong_king),
+        pawn,
+        normalize(weak_king),
+        core.active_player == strong,
+    ))
+}
+
+/// how much of the evaluation `strong` keeps when it's the one ahead. endgames with fairy
+/// pieces are left alone.
+#[must_use]
+pub fn scale_factor(core: &GameStateCore, strong: PlayerKind) -> ScaleFactor {
+    let board = &core.board;
+    let counts = board.piece_counts();
+    if PieceKind::FAIRY
+        .iter()
+        .any(|kind| counts[kind.to_white_piece()] + counts[kind.to_black_piece()] > 0)
+    {
+        return ScaleFactor::NORMAL;
+    }
+    let count = |player: PlayerKind, kind: PieceKind| counts[kind.to_piece(player)];
+    let pieces = |player| {
+        [
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Rook,
+            PieceKind::Queen,
+        ]
+        .map(|kind| count(player, kind))
+    };
+    let weak = strong.opponent();
+    let [knights, bishops, rooks, queens] = pieces(strong);
+    let pawns = count(strong, PieceKind::Pawn);
+    let weak_is_bare = pieces(weak) == [0; 4] && count(weak, PieceKind::Pawn) == 0;
+
+    // a single minor piece can't mate, and two knights can't force it
+    let at_most_a_minor = rooks + queens == 0 && knights + bishops <= 1;
+    let two_knights = rooks + queens + bishops == 0 && knights == 2;
+    if pawns == 0 && (at_most_a_minor || (two_knights && weak_is_bare)) {
+        return ScaleFactor::DRAW;
+    }
+
+    if pawns == 1 && pieces(strong) == [0; 4] && weak_is_bare {
+        return match probe_kpk(core) {
+            Some(false) => ScaleFactor::DRAW,
+            _ => ScaleFactor::NORMAL,
+        };
+    }
+
+    let bishop_squares = |player| {
+        board
+            .pieces()
+            .filter(move |(_, piece)| *piece == PieceKind::Bishop.to_piece(player))
+            .map(|(square, _)| square)
+    };
+
+    if [knights, bishops, rooks, queens] == [0, 1, 0, 0] && weak_is_bare {
+        // rook pawns the bishop can't help through the corner, once the king got there first
+        let pawn_cols = board
+            .pieces()
+            .filter(|(_, piece)| *piece == PieceKind::Pawn.to_piece(strong))
+            .map(|(square, _)| u8::from(square.col))
+            .collect::<alloc::vec::Vec<_>>();
+        let col = pawn_cols[0];
+        if (col == 1 || col == 8) && pawn_cols.iter().all(|other| *other == col) {
+            let corner_row = if strong == PlayerKind::White { 7 } else { 0 };
+            let corner = usize::from(col - 1) + corner_row * 8;
+            let corner_is_black = Square::from_index(corner).is_some_and(Square::is_black);
+            let wrong_bishop =
+                bishop_squares(strong).all(|bishop| bishop.is_black() != corner_is_black);
+            let weak_king = board.king_position(weak).map(Square::index);
+            if wrong_bishop && weak_king.is_some_and(|king| distance(king, corner) <= 1) {
+                return ScaleFactor::DRAW;
+            }
+        }
+    }
+
+    if bishops == 1 && count(weak, PieceKind::Bishop) == 1 {
+        let colors = [strong, weak].map(|player| bishop_squares(player).all(Square::is_black));
+        if colors[0] != colors[1] {
+            let only_bishops = [strong, weak].into_iter().all(|player| {
+                let [knights, _, rooks, queens] = pieces(player);
+                knights + rooks + queens == 0
+            });
+            return if only_bishops {
+                ScaleFactor::OPPOSITE_BISHOPS
+            } else {
+                ScaleFactor::OPPOSITE_BISHOPS_WITH_PIECES
+            };
+        }
+    }
+
+    ScaleFactor::NORMAL
+}
+
+/// neither side can win against sensible defence, which is enough to adjudicate a draw
+#[must_use]
+pub fn is_known_draw(core: &GameStateCore) -> bool {
+    PlayerKind::ALL
+        .into_iter()
+        .all(|player| scale_factor(core, player) == ScaleFactor::DRAW)
+}
+
+const fn distance(a: usize, b: usize) -> usize {
+    let cols = (a % 8).abs_diff(b % 8);
+    let rows = (a / 8).abs_diff(b / 8);
+    if cols > rows { cols } else { rows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::game_from_fen;
+
+    #[test]
+    fn king_and_pawn_versus_king() {
+        // the pawn in front of its king, whoever moves decides
+        let opposition = game_from_fen("4k3/4P3/4K3/8/8/8/8/8 w - - 0 1").core;
+        assert_eq!(Some(true), probe_kpk(&opposition));
+        assert_eq!(Some(false), probe_kpk(&opposition.with_opponent_active()));
+
+        assert_eq!(
+            Some(false),
+            probe_kpk(&game_from_fen("k7/8/8/8/8/8/P7/K7 w - - 0 1").core)
+        );
+        assert_eq!(
+            Some(false),
+            probe_kpk(&game_from_fen("7k/8/8/8/8/8/7P/7K w - - 0 1").core)
+        );
+        assert_eq!(
+            Some(true),
+            probe_kpk(&game_from_fen("8/8/8/8/8/7k/P7/K7 w - - 0 1").core)
+        );
+        assert_eq!(
+            Some(true),
+            probe_kpk(&game_from_fen("k7/p7/7K/8/8/8/8/8 b - - 0 1").core)
+        );
+        assert_eq!(
+            Some(false),
+            probe_kpk(&game_from_fen("k7/p7/8/8/8/8/8/2K5 w - - 0 1").core)
+        );
+        assert_eq!(
+            None,
+            probe_kpk(&game_from_fen("k7/8/8/8/8/8/PP6/K7 w - - 0 1").core)
+        );
+    }
+
+    #[test]
+    fn drawn_material() {
+        assert!(is_known_draw(
+            &game_from_fen("k7/8/8/8/8/8/8/KNN5 w - - 0 1").core
+        ));
+        assert!(is_known_draw(
+            &game_from_fen("k7/8/8/8/8/8/P7/2B1K3 w - - 0 1").core
+        ));
+        assert!(!is_known_draw(
+            &game_from_fen("8/8/8/8/8/8/P7/k1B1K3 w - - 0 1").core
+        ));
+        assert!(!is_known_draw(
+            &game_from_fen("k7/8/8/8/8/8/P7/1B2K3 w - - 0 1").core
+        ));
+        assert!(!is_known_draw(
+            &game_from_fen("k7/8/8/8/8/8/8/KNNN4 w - - 0 1").core
+        ));
+        assert!(!is_known_draw(
+            &game_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").core
+        ));
+    }
+
+    #[test]
+    fn opposite_colored_bishops() {
+        let bishops = game_from_fen("4k3/p7/2b5/8/8/2B5/PP6/4K3 w - - 0 1").core;
+        assert_eq!(
+            ScaleFactor::OPPOSITE_BISHOPS,
+            scale_factor(&bishops, PlayerKind::White)
+        );
+        let with_rooks = game_from_fen("r3k3/p7/2b5/8/8/2B5/PP6/R3K3 w - - 0 1").core;
+        assert_eq!(
+            ScaleFactor::OPPOSITE_BISHOPS_WITH_PIECES,
+            scale_factor(&with_rooks, PlayerKind::White)
+        );
+        let same_color = game_from_fen("4k3/p7/3b4/8/8/2B5/PP6/4K3 w - - 0 1").core;
+        assert_eq!(
+            ScaleFactor::NORMAL,
+            scale_factor(&same_color, PlayerKind::White)
+        );
+    }
+}
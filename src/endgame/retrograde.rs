@@ -0,0 +1,396 @@
+//! exact depth to mate for every placement of a few pieces, worked out backwards from the mates
+//! with [`GameStateCore::unmoves`]
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Not;
+
+use crate::board::Board;
+use crate::coord::Square;
+use crate::game::CastlingRights;
+use crate::game::FiftyMoveRuleClock;
+use crate::game::FullMoveCount;
+use crate::game::GameStateCore;
+use crate::game::PieceCounts;
+use crate::piece::Piece;
+use crate::piece::PieceKind;
+use crate::player::PlayerKind;
+
+/// the most pieces, kings included, that get a table. every one more multiplies its size by 64.
+pub const MAX_PIECES: usize = 4;
+
+/// the result for the side to move, with best play from both sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dtm {
+    /// mates in this many plies
+    Win(u16),
+    /// gets mated in this many plies, 0 when it already is
+    Loss(u16),
+    Draw,
+}
+
+impl Dtm {
+    /// the result for the side that moved into a position with this one
+    const fn before(self) -> Self {
+        match self {
+            Self::Win(plies) => Self::Loss(plies + 1),
+            Self::Loss(plies) => Self::Win(plies + 1),
+            Self::Draw => Self::Draw,
+        }
+    }
+
+    /// higher is better for the side to move
+    const fn preference(self) -> i32 {
+        match self {
+            Self::Win(plies) => i32::from(u16::MAX) - i32::from(plies),
+            Self::Loss(plies) => i32::from(plies) - i32::from(u16::MAX),
+            Self::Draw => 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RetrogradeError {
+    /// each side needs exactly one king
+    Kings,
+    /// pawns promote into material that isn't part of the table
+    Pawns,
+    /// the number of pieces asked for, more than [`MAX_PIECES`]
+    TooManyPieces(usize),
+}
+
+/// one material set, every legal placement with either side to move
+#[derive(Debug, Clone)]
+pub struct DtmTable {
+    /// white before black, identical pieces next to each other
+    material: Vec<Piece>,
+    counts: PieceCounts,
+    /// `None` for placements that can't happen, or are counted under another index
+    dtm: Vec<Option<Dtm>>,
+}
+
+impl DtmTable {
+    /// solves `material`, and everything that's left of it after captures along the way
+    pub fn solve(material: &[Piece]) -> Result<Self, RetrogradeError> {
+        let mut material = material.to_vec();
+        material.sort_by_key(|piece| (piece.owner == PlayerKind::Black, piece.kind.index()));
+        for player in PlayerKind::ALL {
+            let kings = material
+                .iter()
+                .filter(|piece| **piece == PieceKind::King.to_piece(player))
+                .count();
+            if kings != 1 {
+                return Err(RetrogradeError::Kings);
+            }
+        }
+        if material.iter().any(|piece| piece.kind == PieceKind::Pawn) {
+            return Err(RetrogradeError::Pawns);
+        }
+        if material.len() > MAX_PIECES {
+            return Err(RetrogradeError::TooManyPieces(material.len()));
+        }
+
+        let mut smaller = Vec::<Self>::new();
+        for (slot, piece) in material.iter().enumerate() {
+            let captured = piece.kind == PieceKind::King || material[..slot].contains(piece);
+            if captured.not() {
+                let mut rest = material.clone();
+                rest.remove(slot);
+                smaller.push(Self::solve(&rest)?);
+            }
+        }
+        Ok(Self::solve_with(material, &smaller))
+    }
+
+    /// `None` for other material, or with castling rights left
+    #[must_use]
+    pub fn probe(&self, core: &GameStateCore) -> Option<Dtm> {
+        if core.castling_rights != CastlingRights::none_available() {
+            return None;
+        }
+        self.dtm[self.index(&core.board, core.active_player)?]
+    }
+
+    /// every legal position of the table, each once
+    pub fn positions(&self) -> impl Iterator<Item = (GameStateCore, Dtm)> {
+        self.dtm.iter().enumerate().filter_map(|(index, dtm)| {
+            let dtm = (*dtm)?;
+            Some((self.core(index)?, dtm))
+        })
+    }
+
+    #[must_use]
+    pub const fn material(&self) -> &[Piece] {
+        &self.material
+    }
+
+    fn solve_with(material: Vec<Piece>, smaller: &[Self]) -> Self {
+        let mut board = Board::empty();
+        for (square, piece) in Square::ALL.into_iter().zip(&material) {
+            board[square] = Some(*piece);
+        }
+        let size = 2 << (6 * material.len());
+        let mut table = Self {
+            material,
+            counts: board.piece_counts(),
+            dtm: vec![None; size],
+        };
+
+        let mut solver = Solver {
+            remaining: vec![0; size],
+            longest_capture_loss: vec![0; size],
+            escapes: vec![false; size],
+            queue: vec![],
+        };
+        for index in 0..size {
+            if let Some(core) = table.core(index) {
+                solver.look_at_moves(&mut table, smaller, index, &core);
+            }
+        }
+
+        let mut plies = 0;
+        while plies < solver.queue.len() {
+            for (index, dtm) in core::mem::take(&mut solver.queue[plies]) {
+                if table.dtm[index].is_none() {
+                    table.dtm[index] = Some(dtm);
+                    solver.look_at_unmoves(&table, index, dtm);
+                }
+            }
+            plies += 1;
+        }
+
+        // nobody can force anything in whatever is left
+        for index in 0..size {
+            if table.dtm[index].is_none() && table.core(index).is_some() {
+                table.dtm[index] = Some(Dtm::Draw);
+            }
+        }
+        table
+    }
+
+    /// identical pieces are counted with their squares in order
+    fn index(&self, board: &Board, active_player: PlayerKind) -> Option<usize> {
+        if board.piece_counts() != self.counts {
+            return None;
+        }
+        let mut index = usize::from(active_player == PlayerKind::Black);
+        for (slot, piece) in self.material.iter().enumerate() {
+            let nth = self.material[..slot]
+                .iter()
+                .filter(|other| *other == piece)
+                .count();
+            let mut squares = board
+                .pieces()
+                .filter(|(_, other)| other == piece)
+                .map(|(square, _)| square.index())
+                .collect::<Vec<_>>();
+            squares.sort_unstable();
+            index |= squares[nth] << (1 + 6 * slot);
+        }
+        Some(index)
+    }
+
+    /// `None` for placements that can't happen, or are counted under another index
+    fn core(&self, index: usize) -> Option<GameStateCore> {
+        let mut board = Board::empty();
+        let mut previous_square = None;
+        for (slot, piece) in self.material.iter().enumerate() {
+            let square_index = (index >> (1 + 6 * slot)) & 63;
+            let square = Square::from_index(square_index)?;
+            let in_order = slot == 0
+                || self.material[slot - 1] != *piece
+                || previous_square.is_some_and(|previous| previous < square_index);
+            if in_order.not() || board[square].is_some() {
+                return None;
+            }
+            board[square] = Some(*piece);
+            previous_square = Some(square_index);
+        }
+        let active_player = if index & 1 == 0 {
+            PlayerKind::White
+        } else {
+            PlayerKind::Black
+        };
+        if board.is_king_checked(active_player.opponent()) {
+            return None;
+        }
+        Some(GameStateCore {
+            board,
+            fifty_move_rule_clock: FiftyMoveRuleClock::new(0),
+            castling_rights: CastlingRights::none_available(),
+            en_passant_target: None,
+            active_player,
+            full_move_count: FullMoveCount::new(),
+        })
+    }
+}
+
+/// what's known about the positions without a result yet
+struct Solver {
+    /// the moves staying in the table that aren't known to lose yet
+    remaining: Vec<u16>,
+    /// in plies, with all captures losing
+    longest_capture_loss: Vec<u16>,
+    /// a capture holds the draw, so there's no losing
+    escapes: Vec<bool>,
+    /// results by the number of plies they take, to be settled in that order
+    queue: Vec<Vec<(usize, Dtm)>>,
+}
+
+impl Solver {
+    fn push(&mut self, index: usize, dtm: Dtm) {
+        let (Dtm::Win(plies) | Dtm::Loss(plies)) = dtm else {
+            return;
+        };
+        let plies = usize::from(plies);
+        if self.queue.len() <= plies {
+            self.queue.resize_with(plies + 1, Vec::new);
+        }
+        self.queue[plies].push((index, dtm));
+    }
+
+    /// sorts out mates, stalemates and captures, and counts the other moves
+    fn look_at_moves(
+        &mut self,
+        table: &mut DtmTable,
+        smaller: &[DtmTable],
+        index: usize,
+        core: &GameStateCore,
+    ) {
+        let mut moves = 0_u16;
+        let mut best_capture = None::<Dtm>;
+        for mv in core.legal_moves() {
+            if mv.is_capture().not() {
+                moves += 1;
+                continue;
+            }
+            let mut next = core.with_opponent_active();
+            next.board.apply_move(mv);
+            let dtm = smaller
+                .iter()
+                .find_map(|table| table.probe(&next))
+                .expect("the tables for every capture to be solved")
+                .before();
+            if best_capture.is_none_or(|best| dtm.preference() > best.preference()) {
+                best_capture = Some(dtm);
+            }
+        }
+        self.remaining[index] = moves;
+
+        match best_capture {
+            None if moves == 0 && core.board.is_king_checked(core.active_player) => {
+                self.push(index, Dtm::Loss(0));
+            }
+            None if moves == 0 => table.dtm[index] = Some(Dtm::Draw),
+            None => {}
+            Some(Dtm::Draw) => self.escapes[index] = true,
+            Some(win @ Dtm::Win(_)) => self.push(index, win),
+            Some(Dtm::Loss(plies)) => {
+                self.longest_capture_loss[index] = plies;
+                if moves == 0 {
+                    self.push(index, Dtm::Loss(plies));
+                }
+            }
+        }
+    }
+
+    /// passes a newly settled result on to the positions one move before
+    fn look_at_unmoves(&mut self, table: &DtmTable, index: usize, dtm: Dtm) {
+        let core = table
+            .core(index)
+            .expect("only legal positions to get a result");
+        for unmove in core.unmoves() {
+            let previous = core.with_move_undone(unmove);
+            let previous = table
+                .index(&previous.board, previous.active_player)
+                .expect("unmoves to keep the material");
+            if table.dtm[previous].is_some() {
+                continue;
+            }
+            match dtm {
+                Dtm::Loss(plies) => self.push(previous, Dtm::Win(plies + 1)),
+                Dtm::Win(plies) => {
+                    self.remaining[previous] -= 1;
+                    if self.remaining[previous] == 0 && self.escapes[previous].not() {
+                        let plies = (plies + 1).max(self.longest_capture_loss[previous]);
+                        self.push(previous, Dtm::Loss(plies));
+                    }
+                }
+                Dtm::Draw => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::println;
+
+    use super::*;
+    use crate::game::GameState;
+    use crate::search::Searcher;
+    use crate::testing::skip_if_no_expensive_test_opt_in;
+    use crate::time::SearchLimits;
+
+    #[test]
+    fn material_that_cant_be_solved() {
+        let [king, queen, pawn] = [Piece::WHITE_KING, Piece::WHITE_QUEEN, Piece::WHITE_PAWN];
+        let black_king = Piece::BLACK_KING;
+        assert_eq!(
+            Some(RetrogradeError::Kings),
+            DtmTable::solve(&[king, queen]).err()
+        );
+        assert_eq!(
+            Some(RetrogradeError::Pawns),
+            DtmTable::solve(&[king, pawn, black_king]).err()
+        );
+        assert_eq!(
+            Some(RetrogradeError::TooManyPieces(5)),
+            DtmTable::solve(&[king, queen, queen, queen, black_king]).err()
+        );
+    }
+
+    #[test]
+    fn bare_kings() {
+        let table = DtmTable::solve(&[Piece::WHITE_KING, Piece::BLACK_KING]).unwrap();
+        // two kings anywhere but next to each other, and either side to move
+        assert_eq!(2 * (64 * 63 - 420), table.positions().count());
+        assert!(table.positions().all(|(_, dtm)| dtm == Dtm::Draw));
+    }
+
+    #[test]
+    fn king_and_queen_versus_king() {
+        skip_if_no_expensive_test_opt_in!();
+
+        let table =
+            DtmTable::solve(&[Piece::BLACK_KING, Piece::WHITE_QUEEN, Piece::WHITE_KING]).unwrap();
+        let probe = |fen| table.probe(&GameStateCore::try_from_fen(fen).unwrap());
+
+        assert_eq!(Some(Dtm::Win(1)), probe("k7/8/1K6/8/8/8/7Q/8 w - - 0 1"));
+        assert_eq!(Some(Dtm::Loss(0)), probe("k7/Q7/1K6/8/8/8/8/8 b - - 0 1"));
+        assert_eq!(Some(Dtm::Draw), probe("k7/8/1QK5/8/8/8/8/8 b - - 0 1"));
+        assert_eq!(Some(Dtm::Draw), probe("8/8/8/8/8/8/1k6/1Q2K3 b - - 0 1"));
+        assert_eq!(None, probe("k7/8/1K6/8/8/8/8/7R w - - 0 1"));
+
+        // the longest mate takes ten moves, and black never wins
+        let longest = table
+            .positions()
+            .filter_map(|(_, dtm)| match dtm {
+                Dtm::Win(plies) => Some(plies),
+                _ => None,
+            })
+            .max();
+        assert_eq!(Some(19), longest);
+        assert!(table.positions().all(|(core, dtm)| {
+            core.active_player == PlayerKind::White || matches!(dtm, Dtm::Win(_)).not()
+        }));
+
+        // and the engine finds mates as short as they get
+        let (core, _) = table
+            .positions()
+            .find(|(_, dtm)| *dtm == Dtm::Win(5))
+            .unwrap();
+        let result = Searcher::new().search(&GameState::with_core(core), &SearchLimits::depth(6));
+        assert_eq!(Some(3), result.score.mate_moves());
+    }
+}
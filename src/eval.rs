@@ -0,0 +1,892 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+use core::ops::Add;
+use core::ops::AddAssign;
+use core::ops::Mul;
+use core::ops::Neg;
+use core::ops::Not;
+use core::ops::Sub;
+
+use crate::board::Board;
+use crate::coord::Offset;
+use crate::coord::Square;
+use crate::endgame;
+use crate::endgame::ScaleFactor;
+use crate::game::GameStateCore;
+use crate::piece::PieceKind;
+use crate::player::PlayerKind;
+
+pub const MAX_PHASE: i32 = 24;
+
+/// centipawns, from the point of view of whoever is to move
+#[derive_const(PartialEq, Eq, PartialOrd, Ord, Clone, Default)]
+#[derive(Debug, Copy, Hash)]
+pub struct Score(pub i32);
+impl Score {
+    pub const ZERO: Self = Self(0);
+    pub const MATE: Self = Self(100_000);
+    /// strictly better than any mate, so it can be used as an open search window bound
+    pub const INFINITY: Self = Self(Self::MATE.0 + 1);
+
+    /// anything closer to [`Score::MATE`] than this is a forced mate, not an evaluation
+    const MATE_THRESHOLD: i32 = Self::MATE.0 - 1_000;
+
+    /// `plies` counted from the root of the search to the mating move
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub const fn mate_in(plies: u32) -> Self {
+        Self(Self::MATE.0 - plies as i32)
+    }
+
+    #[must_use]
+    pub const fn mated_in(plies: u32) -> Self {
+        Self(-Self::mate_in(plies).0)
+    }
+
+    #[must_use]
+    pub const fn is_mate(self) -> bool {
+        self.0.abs() > Self::MATE_THRESHOLD
+    }
+
+    /// "mate in N" in full moves, negative if it's the side to move getting mated
+    #[must_use]
+    pub const fn mate_moves(self) -> Option<i32> {
+        if self.is_mate().not() {
+            return None;
+        }
+        let plies = Self::MATE.0 - self.0.abs();
+        let moves = (plies + 1) / 2;
+        Some(if self.0 > 0 { moves } else { -moves })
+    }
+}
+impl const Neg for Score {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+impl const Add for Score {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+impl const Sub for Score {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+#[derive_const(PartialEq, Eq, Clone, Default)]
+#[derive(Debug, Copy, Hash)]
+pub struct TaperedScore {
+    pub middlegame: i32,
+    pub endgame: i32,
+}
+impl TaperedScore {
+    pub const ZERO: Self = Self::new(0, 0);
+
+    #[must_use]
+    pub const fn new(middlegame: i32, endgame: i32) -> Self {
+        Self {
+            middlegame,
+            endgame,
+        }
+    }
+
+    /// `phase` goes from [`MAX_PHASE`] (all pieces on the board) down to 0 (bare kings & pawns)
+    #[must_use]
+    pub const fn taper(self, phase: i32) -> i32 {
+        (self.middlegame * phase + self.endgame * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+impl const Add for TaperedScore {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.middlegame + rhs.middlegame, self.endgame + rhs.endgame)
+    }
+}
+impl const Sub for TaperedScore {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.middlegame - rhs.middlegame, self.endgame - rhs.endgame)
+    }
+}
+impl const Mul<i32> for TaperedScore {
+    type Output = Self;
+    fn mul(self, rhs: i32) -> Self::Output {
+        Self::new(self.middlegame * rhs, self.endgame * rhs)
+    }
+}
+impl const AddAssign for TaperedScore {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+/// Everything [`evaluate_with`] knows about chess, all indexed by [`PieceKind::index`].
+/// Piece-square tables are written from whites point of view, `a8` first, just like [`Square::ALL`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalWeights {
+    pub material: [TaperedScore; PieceKind::COUNT],
+    pub piece_square_tables: [[TaperedScore; 64]; PieceKind::COUNT],
+    pub mobility: [TaperedScore; PieceKind::COUNT],
+    pub phase: [i32; PieceKind::COUNT],
+    pub king_shelter_pawn: TaperedScore,
+    pub king_zone_attack: TaperedScore,
+    pub doubled_pawn: TaperedScore,
+    pub isolated_pawn: TaperedScore,
+    /// by how many rows the pawn has advanced from its owners back row
+    pub passed_pawn: [TaperedScore; 8],
+}
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+const fn tapered_table(middlegame: [i32; 64], endgame: [i32; 64]) -> [TaperedScore; 64] {
+    let mut table = [TaperedScore::ZERO; 64];
+    let mut i = 0;
+    while i < 64 {
+        table[i] = TaperedScore::new(middlegame[i], endgame[i]);
+        i += 1;
+    }
+    table
+}
+
+const fn t(middlegame: i32, endgame: i32) -> TaperedScore {
+    TaperedScore::new(middlegame, endgame)
+}
+
+#[rustfmt::skip]
+const PAWN_MIDDLEGAME: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_ENDGAME: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    80,  80,  80,  80,  80,  80,  80,  80,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    30,  30,  30,  30,  30,  30,  30,  30,
+    15,  15,  15,  15,  15,  15,  15,  15,
+     5,   5,   5,   5,   5,   5,   5,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+const NO_TABLE: [TaperedScore; 64] = [TaperedScore::ZERO; 64];
+
+impl EvalWeights {
+    // order: pawn, knight, bishop, rook, queen, king, archbishop, chancellor, amazon, camel, zebra
+    pub const STANDARD: Self = Self {
+        material: [
+            t(100, 120),
+            t(320, 300),
+            t(330, 320),
+            t(500, 520),
+            t(900, 950),
+            t(0, 0),
+            t(800, 800),
+            t(850, 870),
+            t(1200, 1250),
+            t(250, 230),
+            t(250, 230),
+        ],
+        piece_square_tables: [
+            tapered_table(PAWN_MIDDLEGAME, PAWN_ENDGAME),
+            tapered_table(KNIGHT, KNIGHT),
+            tapered_table(BISHOP, BISHOP),
+            tapered_table(ROOK, ROOK),
+            tapered_table(QUEEN, QUEEN),
+            tapered_table(KING_MIDDLEGAME, KING_ENDGAME),
+            NO_TABLE,
+            NO_TABLE,
+            NO_TABLE,
+            NO_TABLE,
+            NO_TABLE,
+        ],
+        mobility: [
+            t(0, 0),
+            t(4, 4),
+            t(5, 5),
+            t(2, 4),
+            t(1, 2),
+            t(0, 0),
+            t(3, 3),
+            t(2, 3),
+            t(1, 2),
+            t(3, 3),
+            t(3, 3),
+        ],
+        phase: [0, 1, 1, 2, 4, 0, 3, 4, 6, 1, 1],
+        king_shelter_pawn: t(12, 0),
+        king_zone_attack: t(-8, -2),
+        doubled_pawn: t(-10, -20),
+        isolated_pawn: t(-10, -15),
+        passed_pawn: [
+            t(0, 0),
+            t(0, 5),
+            t(5, 10),
+            t(10, 20),
+            t(20, 40),
+            t(35, 70),
+            t(60, 110),
+            t(0, 0),
+        ],
+    };
+}
+
+/// in the order of [`PieceKind::index`]
+const PIECE_KINDS: [PieceKind; PieceKind::COUNT] = [
+    PieceKind::Pawn,
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::Queen,
+    PieceKind::King,
+    PieceKind::Archbishop,
+    PieceKind::Chancellor,
+    PieceKind::Amazon,
+    PieceKind::Camel,
+    PieceKind::Zebra,
+];
+
+const PIECE_NAMES: [&str; PieceKind::COUNT] = [
+    "pawn",
+    "knight",
+    "bishop",
+    "rook",
+    "queen",
+    "king",
+    "archbishop",
+    "chancellor",
+    "amazon",
+    "camel",
+    "zebra",
+];
+
+/// one of the tapered [`EvalWeights`], everything but the phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term {
+    Material(PieceKind),
+    /// with the index into the piece-square table
+    PieceSquare(PieceKind, usize),
+    Mobility(PieceKind),
+    KingShelterPawn,
+    KingZoneAttack,
+    DoubledPawn,
+    IsolatedPawn,
+    /// with the rows advanced
+    PassedPawn(usize),
+}
+impl Term {
+    const PIECE_SQUARE_START: usize = PieceKind::COUNT;
+    const MOBILITY_START: usize = Self::PIECE_SQUARE_START + PieceKind::COUNT * 64;
+    const KINGS_AND_PAWNS_START: usize = Self::MOBILITY_START + PieceKind::COUNT;
+    const PASSED_PAWN_START: usize = Self::KINGS_AND_PAWNS_START + 4;
+    pub const COUNT: usize = Self::PASSED_PAWN_START + 8;
+
+    /// dense index, `0..Term::COUNT`
+    #[must_use]
+    pub const fn index(self) -> usize {
+        match self {
+            Self::Material(kind) => kind.index(),
+            Self::PieceSquare(kind, square) => {
+                Self::PIECE_SQUARE_START + kind.index() * 64 + square
+            }
+            Self::Mobility(kind) => Self::MOBILITY_START + kind.index(),
+            Self::KingShelterPawn => Self::KINGS_AND_PAWNS_START,
+            Self::KingZoneAttack => Self::KINGS_AND_PAWNS_START + 1,
+            Self::DoubledPawn => Self::KINGS_AND_PAWNS_START + 2,
+            Self::IsolatedPawn => Self::KINGS_AND_PAWNS_START + 3,
+            Self::PassedPawn(rows) => Self::PASSED_PAWN_START + rows,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_index(index: usize) -> Option<Self> {
+        Some(if index < Self::PIECE_SQUARE_START {
+            Self::Material(PIECE_KINDS[index])
+        } else if index < Self::MOBILITY_START {
+            let index = index - Self::PIECE_SQUARE_START;
+            Self::PieceSquare(PIECE_KINDS[index / 64], index % 64)
+        } else if index < Self::KINGS_AND_PAWNS_START {
+            Self::Mobility(PIECE_KINDS[index - Self::MOBILITY_START])
+        } else if index < Self::PASSED_PAWN_START {
+            [
+                Self::KingShelterPawn,
+                Self::KingZoneAttack,
+                Self::DoubledPawn,
+                Self::IsolatedPawn,
+            ][index - Self::KINGS_AND_PAWNS_START]
+        } else if index < Self::COUNT {
+            Self::PassedPawn(index - Self::PASSED_PAWN_START)
+        } else {
+            return None;
+        })
+    }
+
+    /// all of them, in the order of their index
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..Self::COUNT).filter_map(Self::from_index)
+    }
+
+    /// what it's called in a weights file, like `material.knight` or `piece_square.pawn.e4`
+    #[must_use]
+    pub fn name(self) -> String {
+        match self {
+            Self::Material(kind) => format!("material.{}", PIECE_NAMES[kind.index()]),
+            Self::PieceSquare(kind, square) => format!(
+                "piece_square.{}.{}",
+                PIECE_NAMES[kind.index()],
+                Square::ALL[square].to_fen_repr().as_str()
+            ),
+            Self::Mobility(kind) => format!("mobility.{}", PIECE_NAMES[kind.index()]),
+            Self::KingShelterPawn => "king_shelter_pawn".to_owned(),
+            Self::KingZoneAttack => "king_zone_attack".to_owned(),
+            Self::DoubledPawn => "doubled_pawn".to_owned(),
+            Self::IsolatedPawn => "isolated_pawn".to_owned(),
+            Self::PassedPawn(rows) => format!("passed_pawn.{rows}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WeightsFromTextError {
+    /// with the line number, counting from 1
+    UnknownTerm(usize),
+    MalformedLine(usize),
+}
+
+#[cfg(any(test, feature = "std"))]
+#[derive(Debug)]
+pub enum WeightsFromFileError {
+    Io(std::io::Error),
+    Text(WeightsFromTextError),
+}
+
+impl EvalWeights {
+    #[must_use]
+    pub const fn term(&self, term: Term) -> TaperedScore {
+        match term {
+            Term::Material(kind) => self.material[kind.index()],
+            Term::PieceSquare(kind, square) => self.piece_square_tables[kind.index()][square],
+            Term::Mobility(kind) => self.mobility[kind.index()],
+            Term::KingShelterPawn => self.king_shelter_pawn,
+            Term::KingZoneAttack => self.king_zone_attack,
+            Term::DoubledPawn => self.doubled_pawn,
+            Term::IsolatedPawn => self.isolated_pawn,
+            Term::PassedPawn(rows) => self.passed_pawn[rows],
+        }
+    }
+
+    pub const fn term_mut(&mut self, term: Term) -> &mut TaperedScore {
+        match term {
+            Term::Material(kind) => &mut self.material[kind.index()],
+            Term::PieceSquare(kind, square) => &mut self.piece_square_tables[kind.index()][square],
+            Term::Mobility(kind) => &mut self.mobility[kind.index()],
+            Term::KingShelterPawn => &mut self.king_shelter_pawn,
+            Term::KingZoneAttack => &mut self.king_zone_attack,
+            Term::DoubledPawn => &mut self.doubled_pawn,
+            Term::IsolatedPawn => &mut self.isolated_pawn,
+            Term::PassedPawn(rows) => &mut self.passed_pawn[rows],
+        }
+    }
+
+    /// a weights file: one `name middlegame endgame` line for every [`Term`]
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for term in Term::all() {
+            let TaperedScore {
+                middlegame,
+                endgame,
+            } = self.term(term);
+            writeln!(text, "{} {middlegame} {endgame}", term.name())
+                .expect("writing to a String to never fail");
+        }
+        text
+    }
+
+    /// the [`EvalWeights::STANDARD`] with what's in a weights file instead. blank lines and
+    /// lines starting with `#` are skipped. the phase can't be changed this way.
+    pub fn from_text(text: &str) -> Result<Self, WeightsFromTextError> {
+        let mut weights = Self::STANDARD;
+        for (number, line) in (1..).zip(text.lines()) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let [name, middlegame, endgame] = line.split_whitespace().collect::<Vec<_>>()[..]
+            else {
+                return Err(WeightsFromTextError::MalformedLine(number));
+            };
+            let term = Term::all()
+                .find(|term| term.name() == name)
+                .ok_or(WeightsFromTextError::UnknownTerm(number))?;
+            let (Ok(middlegame), Ok(endgame)) = (middlegame.parse(), endgame.parse()) else {
+                return Err(WeightsFromTextError::MalformedLine(number));
+            };
+            *weights.term_mut(term) = TaperedScore::new(middlegame, endgame);
+        }
+        Ok(weights)
+    }
+
+    /// [`EvalWeights::from_text`] for a weights file, like the ones `schach-tune` writes. an empty
+    /// path gives the [`EvalWeights::STANDARD`], and so does `<empty>`, which is how uci guis
+    /// show a file option that isn't set.
+    #[cfg(any(test, feature = "std"))]
+    pub fn from_file(path: &str) -> Result<Self, WeightsFromFileError> {
+        if path.is_empty() || path == "<empty>" {
+            return Ok(Self::STANDARD);
+        }
+        let text = std::fs::read_to_string(path).map_err(WeightsFromFileError::Io)?;
+        Self::from_text(&text).map_err(WeightsFromFileError::Text)
+    }
+}
+
+/// how likely a side `score` centipawns ahead is to win, on the usual logistic curve where a
+/// pawn up wins about two games out of three
+#[cfg(any(test, feature = "std"))]
+#[must_use]
+pub fn win_chance(score: f64) -> f64 {
+    1.0 / (1.0 + 10_f64.powf(-score / 400.0))
+}
+
+/// the score in centipawns [`win_chance`] turns into `win_rate`, which is infinite for 0 and 1
+#[cfg(any(test, feature = "std"))]
+#[must_use]
+pub fn score_from_win_rate(win_rate: f64) -> f64 {
+    -400.0 * (1.0 / win_rate - 1.0).log10()
+}
+
+#[must_use]
+pub fn evaluate(core: &GameStateCore) -> Score {
+    evaluate_with(core, &EvalWeights::STANDARD)
+}
+
+#[must_use]
+pub fn evaluate_with(core: &GameStateCore, weights: &EvalWeights) -> Score {
+    let phase = game_phase(&core.board, weights);
+    let white_point_of_view =
+        side_terms(core, PlayerKind::White, weights) - side_terms(core, PlayerKind::Black, weights);
+    let white_point_of_view = white_point_of_view.taper(phase);
+    let ahead = if white_point_of_view >= 0 {
+        PlayerKind::White
+    } else {
+        PlayerKind::Black
+    };
+    let score = Score(endgame::scale_factor(core, ahead).apply(white_point_of_view));
+    match core.active_player {
+        PlayerKind::White => score,
+        PlayerKind::Black => -score,
+    }
+}
+
+/// what [`evaluate_with`] adds up, before the weights come in. the evaluation is linear in the
+/// terms, up to the phase and the scale factor, which is what makes it tunable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalTrace {
+    /// how often every term that counts at all counts for white, minus for black
+    pub terms: Vec<(Term, i32)>,
+    pub phase: i32,
+    /// for the weights the trace was made with, it depends on who they think is ahead
+    pub scale: ScaleFactor,
+}
+impl EvalTrace {
+    /// the same as [`evaluate_with`], but from whites point of view
+    #[must_use]
+    pub fn white_score(&self, weights: &EvalWeights) -> i32 {
+        let mut score = TaperedScore::ZERO;
+        for (term, count) in &self.terms {
+            score += weights.term(*term) * *count;
+        }
+        self.scale.apply(score.taper(self.phase))
+    }
+}
+
+#[must_use]
+pub fn trace(core: &GameStateCore, weights: &EvalWeights) -> EvalTrace {
+    let mut counts = [0; Term::COUNT];
+    count_side_terms(core, PlayerKind::White, &mut |term, count| {
+        counts[term.index()] += count;
+    });
+    count_side_terms(core, PlayerKind::Black, &mut |term, count| {
+        counts[term.index()] -= count;
+    });
+    let terms = Term::all()
+        .zip(counts)
+        .filter(|(_, count)| *count != 0)
+        .collect();
+
+    let white_point_of_view = (side_terms(core, PlayerKind::White, weights)
+        - side_terms(core, PlayerKind::Black, weights))
+    .taper(game_phase(&core.board, weights));
+    let ahead = if white_point_of_view >= 0 {
+        PlayerKind::White
+    } else {
+        PlayerKind::Black
+    };
+    EvalTrace {
+        terms,
+        phase: game_phase(&core.board, weights),
+        scale: endgame::scale_factor(core, ahead),
+    }
+}
+
+#[must_use]
+pub fn game_phase(board: &Board, weights: &EvalWeights) -> i32 {
+    board
+        .pieces()
+        .map(|(_, piece)| weights.phase[piece.kind.index()])
+        .sum::<i32>()
+        .min(MAX_PHASE)
+}
+
+fn side_terms(core: &GameStateCore, player: PlayerKind, weights: &EvalWeights) -> TaperedScore {
+    let mut score = TaperedScore::ZERO;
+    count_side_terms(core, player, &mut |term, count| {
+        score += weights.term(term) * count;
+    });
+    score
+}
+
+/// every term of `player`, as often as it counts for them
+fn count_side_terms(core: &GameStateCore, player: PlayerKind, add: &mut impl FnMut(Term, i32)) {
+    material_and_placement(&core.board, player, add);
+    mobility(core, player, add);
+    king_safety(&core.board, player, add);
+    pawn_structure(&core.board, player, add);
+}
+
+/// index into the piece-square tables, which are written from whites point of view
+const fn table_index(square: Square, owner: PlayerKind) -> usize {
+    let col = usize::from(u8::from(square.col) - 1);
+    let row = usize::from(u8::from(square.row) - 1);
+    match owner {
+        PlayerKind::White => (7 - row) * 8 + col,
+        PlayerKind::Black => row * 8 + col,
+    }
+}
+
+/// how many rows this square is away from the owners back row
+const fn rows_advanced(square: Square, owner: PlayerKind) -> usize {
+    let row = usize::from(u8::from(square.row) - 1);
+    match owner {
+        PlayerKind::White => row,
+        PlayerKind::Black => 7 - row,
+    }
+}
+
+fn material_and_placement(board: &Board, player: PlayerKind, add: &mut impl FnMut(Term, i32)) {
+    for (square, piece) in board.pieces().filter(|(_, piece)| piece.owner == player) {
+        add(Term::Material(piece.kind), 1);
+        add(
+            Term::PieceSquare(piece.kind, table_index(square, player)),
+            1,
+        );
+    }
+}
+
+fn mobility(core: &GameStateCore, player: PlayerKind, add: &mut impl FnMut(Term, i32)) {
+    let mut core = *core;
+    if core.active_player != player {
+        core.active_player = player;
+        core.en_passant_target = None; // that one only ever belongs to whoever is to move
+    }
+
+    for mv in core.legal_moves() {
+        add(Term::Mobility(mv.kind.piece_kind()), 1);
+    }
+}
+
+fn king_safety(board: &Board, player: PlayerKind, add: &mut impl FnMut(Term, i32)) {
+    let Some(king) = board.king_position(player) else {
+        return;
+    };
+
+    let shelter_pawns = (-1..=1)
+        .flat_map(|col| {
+            (1..=2).map(move |rows| player.forwards_one_row() * rows + Offset { col, row: 0 })
+        })
+        .filter_map(|offset| (king + offset).ok())
+        .filter(|square| board[*square] == Some(PieceKind::Pawn.to_piece(player)))
+        .count();
+
+    let king_zone = Offset::KING_DIRECT
+        .iter()
+        .filter_map(|offset| (king + *offset).ok())
+        .collect::<Vec<_>>();
+    let king_zone_attacks = board
+        .threatened_squares_by(player.opponent())
+        .filter(|square| king_zone.contains(square))
+        .count();
+
+    add(Term::KingShelterPawn, count(shelter_pawns));
+    add(Term::KingZoneAttack, count(king_zone_attacks));
+}
+
+fn pawn_structure(board: &Board, player: PlayerKind, add: &mut impl FnMut(Term, i32)) {
+    let own_pawn = Some(PieceKind::Pawn.to_piece(player));
+    let enemy_pawn = Some(PieceKind::Pawn.to_piece(player.opponent()));
+
+    let pawns_on_col = |col: i32, pawn| {
+        Square::ALL
+            .iter()
+            .filter(move |square| i32::from(square.col) == col && board[**square] == pawn)
+    };
+
+    for square in Square::ALL
+        .iter()
+        .filter(|square| board[**square] == own_pawn)
+    {
+        let col = i32::from(square.col);
+
+        if pawns_on_col(col - 1, own_pawn)
+            .chain(pawns_on_col(col + 1, own_pawn))
+            .next()
+            .is_none()
+        {
+            add(Term::IsolatedPawn, 1);
+        }
+
+        // the one further back pays for being doubled, so two pawns on a col only count once
+        if pawns_on_col(col, own_pawn)
+            .any(|other| rows_advanced(*other, player) > rows_advanced(*square, player))
+        {
+            add(Term::DoubledPawn, 1);
+        }
+
+        let is_passed = (col - 1..=col + 1)
+            .flat_map(|col| pawns_on_col(col, enemy_pawn))
+            .all(|enemy| rows_advanced(*enemy, player) <= rows_advanced(*square, player));
+        if is_passed {
+            add(Term::PassedPawn(rows_advanced(*square, player)), 1);
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const fn count(count: usize) -> i32 {
+    count as i32 // there are only 64 squares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starting_position_is_balanced() {
+        assert_eq!(Score::ZERO, evaluate(&GameStateCore::default()));
+    }
+
+    #[test]
+    fn win_chances_go_both_ways() {
+        assert!((win_chance(0.0) - 0.5).abs() < 1e-9);
+        assert!((win_chance(100.0) + win_chance(-100.0) - 1.0).abs() < 1e-9);
+        for score in [-350.0, -20.0, 0.0, 75.0, 900.0] {
+            assert!((score_from_win_rate(win_chance(score)) - score).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn score_is_from_the_side_to_move() {
+        let white_to_move =
+            GameStateCore::try_from_fen("4k3/8/8/8/8/8/PPP5/1K1Q4 w - - 0 1").unwrap();
+        let black_to_move = white_to_move.with_opponent_active();
+
+        assert!(evaluate(&white_to_move) > Score(800));
+        assert_eq!(evaluate(&white_to_move), -evaluate(&black_to_move));
+    }
+
+    #[test]
+    fn passed_pawns_are_worth_more_further_up() {
+        let far = GameStateCore::try_from_fen("4k3/8/1P6/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let near = GameStateCore::try_from_fen("4k3/8/8/8/8/1P6/8/4K3 w - - 0 1").unwrap();
+        assert!(evaluate(&far) > evaluate(&near));
+    }
+
+    #[test]
+    fn traces_add_up_to_the_evaluation() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R b KQ - 3 8",
+            "8/5k2/1p6/1P1b4/8/3B4/5K2/8 w - - 0 1",
+            "4k3/8/1P6/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            let core = GameStateCore::try_from_fen(fen).unwrap();
+            let white_score =
+                trace(&core, &EvalWeights::STANDARD).white_score(&EvalWeights::STANDARD);
+            let score = match core.active_player {
+                PlayerKind::White => evaluate(&core),
+                PlayerKind::Black => -evaluate(&core),
+            };
+            assert_eq!(score, Score(white_score), "{fen}");
+        }
+    }
+
+    #[test]
+    fn terms_round_trip_through_their_index() {
+        assert_eq!(Term::COUNT, Term::all().count());
+        for (index, term) in Term::all().enumerate() {
+            assert_eq!(index, term.index());
+        }
+        assert_eq!(None, Term::from_index(Term::COUNT));
+    }
+
+    #[test]
+    fn weights_round_trip_through_text() {
+        let mut weights = EvalWeights::STANDARD;
+        *weights.term_mut(Term::PieceSquare(PieceKind::Knight, 27)) = TaperedScore::new(-7, 13);
+        weights.passed_pawn[5] = TaperedScore::new(1, 2);
+        assert_eq!(
+            Ok(weights.clone()),
+            EvalWeights::from_text(&weights.to_text())
+        );
+
+        let partial = EvalWeights::from_text("# just the one\nmaterial.queen 1 2\n").unwrap();
+        assert_eq!(
+            TaperedScore::new(1, 2),
+            partial.material[PieceKind::Queen.index()]
+        );
+        assert_eq!(
+            EvalWeights::STANDARD.material[PieceKind::Rook.index()],
+            partial.material[PieceKind::Rook.index()]
+        );
+
+        assert_eq!(
+            Err(WeightsFromTextError::UnknownTerm(2)),
+            EvalWeights::from_text("\nmaterial.dragon 1 2")
+        );
+        assert_eq!(
+            Err(WeightsFromTextError::MalformedLine(1)),
+            EvalWeights::from_text("material.queen 900")
+        );
+    }
+
+    #[test]
+    fn weights_from_file() {
+        let path = std::env::temp_dir().join(format!("schach-weights-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "material.queen 1 2\n").unwrap();
+        let weights = EvalWeights::from_file(path);
+        std::fs::write(path, "material.dragon 1 2\n").unwrap();
+        let unknown = EvalWeights::from_file(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            TaperedScore::new(1, 2),
+            weights.unwrap().material[PieceKind::Queen.index()]
+        );
+        assert!(matches!(
+            unknown,
+            Err(WeightsFromFileError::Text(
+                WeightsFromTextError::UnknownTerm(1)
+            ))
+        ));
+        assert!(matches!(
+            EvalWeights::from_file(path),
+            Err(WeightsFromFileError::Io(_))
+        ));
+        for unset in ["", "<empty>"] {
+            assert_eq!(
+                EvalWeights::STANDARD,
+                EvalWeights::from_file(unset).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn custom_weights_are_used() {
+        let mut weights = EvalWeights::default();
+        weights.material[PieceKind::Queen.index()] = TaperedScore::new(1, 1);
+
+        let core = GameStateCore::try_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        assert!(evaluate_with(&core, &weights) < evaluate(&core));
+    }
+}
@@ -5,15 +5,24 @@ use core::ops::IndexMut;
 use core::ops::Not;
 
 use crate::board::Board;
+use crate::board::BoardOf;
+use crate::coord::Row;
 use crate::coord::Square;
+use crate::coord::SquareOf;
 use crate::mv::Move;
 use crate::mv::MoveKind;
-use crate::mv::Threat;
+use crate::mv::ThreatOf;
 use crate::piece::Piece;
+use crate::piece::PieceKind;
 use crate::player::PlayerKind;
 
 pub(crate) static REPETITIONS_TO_FORCED_DRAW_COUNT: usize = 5;
 pub(crate) static FIFTY_MOVE_RULE_COUNT: FiftyMoveRuleClock = FiftyMoveRuleClock(100);
+pub(crate) const RACING_KINGS_GOAL_ROW: Row = Row::_8;
+
+pub const HORDE_START_FEN: &str =
+    "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+pub const RACING_KINGS_START_FEN: &str = "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1";
 
 #[derive_const(PartialEq, Eq)]
 #[derive(Debug, Copy, Clone)]
@@ -32,6 +41,7 @@ pub enum DrawKind {
     ThreefoldRepetition,
     FiftyMove,
     InsufficientMaterial,
+    BothKingsReachedGoal,
 }
 
 #[derive_const(Clone, PartialEq, Eq)]
@@ -39,12 +49,18 @@ pub enum DrawKind {
 pub enum GameResultKind {
     Draw(DrawKind),
     Win,
+    Loss, // the player who made the final move lost, only reachable in variants like antichess
 }
 impl GameResultKind {
     #[must_use]
     pub const fn is_win(&self) -> bool {
         self == &Self::Win
     }
+
+    #[must_use]
+    pub const fn is_loss(&self) -> bool {
+        self == &Self::Loss
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -74,6 +90,16 @@ pub(crate) struct PieceCounts {
     pub(crate) black_rook: u8,
     pub(crate) black_queen: u8,
     pub(crate) black_king: u8,
+    pub(crate) white_archbishop: u8,
+    pub(crate) white_chancellor: u8,
+    pub(crate) white_amazon: u8,
+    pub(crate) white_camel: u8,
+    pub(crate) white_zebra: u8,
+    pub(crate) black_archbishop: u8,
+    pub(crate) black_chancellor: u8,
+    pub(crate) black_amazon: u8,
+    pub(crate) black_camel: u8,
+    pub(crate) black_zebra: u8,
 }
 impl PieceCounts {
     const KINGS_ONLY: Self = Self {
@@ -81,20 +107,6 @@ impl PieceCounts {
         black_king: 1,
         ..Default::default()
     };
-
-    const WHITE_KING_AND_TWO_KNIGHTS: Self = Self {
-        white_king: 1,
-        white_knight: 2,
-        black_king: 1,
-        ..Default::default()
-    };
-
-    const BLACK_KING_AND_TWO_KNIGHTS: Self = Self {
-        black_king: 1,
-        black_knight: 2,
-        white_king: 1,
-        ..Default::default()
-    };
 }
 impl Index<Piece> for PieceCounts {
     type Output = u8;
@@ -115,6 +127,18 @@ impl Index<Piece> for PieceCounts {
             Piece::BLACK_ROOK   => &self.black_rook,
             Piece::BLACK_QUEEN  => &self.black_queen,
             Piece::BLACK_KING   => &self.black_king,
+
+            Piece::WHITE_ARCHBISHOP => &self.white_archbishop,
+            Piece::WHITE_CHANCELLOR => &self.white_chancellor,
+            Piece::WHITE_AMAZON     => &self.white_amazon,
+            Piece::WHITE_CAMEL      => &self.white_camel,
+            Piece::WHITE_ZEBRA      => &self.white_zebra,
+
+            Piece::BLACK_ARCHBISHOP => &self.black_archbishop,
+            Piece::BLACK_CHANCELLOR => &self.black_chancellor,
+            Piece::BLACK_AMAZON     => &self.black_amazon,
+            Piece::BLACK_CAMEL      => &self.black_camel,
+            Piece::BLACK_ZEBRA      => &self.black_zebra,
         }
     }
 }
@@ -135,6 +159,18 @@ impl IndexMut<Piece> for PieceCounts {
             Piece::BLACK_ROOK   => &mut self.black_rook,
             Piece::BLACK_QUEEN  => &mut self.black_queen,
             Piece::BLACK_KING   => &mut self.black_king,
+
+            Piece::WHITE_ARCHBISHOP => &mut self.white_archbishop,
+            Piece::WHITE_CHANCELLOR => &mut self.white_chancellor,
+            Piece::WHITE_AMAZON     => &mut self.white_amazon,
+            Piece::WHITE_CAMEL      => &mut self.white_camel,
+            Piece::WHITE_ZEBRA      => &mut self.white_zebra,
+
+            Piece::BLACK_ARCHBISHOP => &mut self.black_archbishop,
+            Piece::BLACK_CHANCELLOR => &mut self.black_chancellor,
+            Piece::BLACK_AMAZON     => &mut self.black_amazon,
+            Piece::BLACK_CAMEL      => &mut self.black_camel,
+            Piece::BLACK_ZEBRA      => &mut self.black_zebra,
         }
     }
 }
@@ -180,6 +216,134 @@ pub enum RuleSet {
     #[default]
     Standard,
     Perft,
+    Antichess,
+    Horde,
+    RacingKings,
+}
+impl RuleSet {
+    #[must_use]
+    pub const fn has_royal_king(self) -> bool {
+        match self {
+            Self::Standard | Self::Perft | Self::Horde | Self::RacingKings => true,
+            Self::Antichess => false,
+        }
+    }
+
+    #[must_use]
+    pub const fn has_castling(self) -> bool {
+        match self {
+            Self::Standard | Self::Perft | Self::Horde => true,
+            Self::Antichess | Self::RacingKings => false,
+        }
+    }
+
+    #[must_use]
+    pub const fn has_forced_captures(self) -> bool {
+        match self {
+            Self::Standard | Self::Perft | Self::Horde | Self::RacingKings => false,
+            Self::Antichess => true,
+        }
+    }
+
+    #[must_use]
+    pub const fn allows_giving_check(self) -> bool {
+        match self {
+            Self::Standard | Self::Perft | Self::Antichess | Self::Horde => true,
+            Self::RacingKings => false,
+        }
+    }
+
+    #[must_use]
+    pub const fn has_insufficient_material_draw(self) -> bool {
+        match self {
+            Self::Standard | Self::Perft | Self::Horde => true,
+            Self::Antichess | Self::RacingKings => false, // bare kings can still win those
+        }
+    }
+
+    #[must_use]
+    pub(crate) const fn is_pawn_double_step_row(self, pawn_owner: PlayerKind, row: Row) -> bool {
+        match (self, pawn_owner) {
+            (Self::Horde, PlayerKind::White) => row == Row::_1 || row == Row::_2,
+            _ => row == pawn_owner.pawn_starting_row(),
+        }
+    }
+
+    /// `stuck` is the position of the player who has no legal moves left
+    #[must_use]
+    pub fn out_of_moves_result(self, stuck: &GameStateCore) -> GameResultKind {
+        match self {
+            Self::Antichess => GameResultKind::Loss, // running out of moves (or pieces) is how you win
+            _ if stuck.board.is_king_checked(stuck.active_player) => GameResultKind::Win,
+            Self::Horde
+                if stuck
+                    .board
+                    .pieces()
+                    .all(|(_, piece)| piece.owner != stuck.active_player) =>
+            {
+                GameResultKind::Win // the horde got wiped out
+            }
+            Self::Standard | Self::Perft | Self::Horde | Self::RacingKings => {
+                GameResultKind::Draw(DrawKind::Stalemate)
+            }
+        }
+    }
+
+    /// `moved` is the position right after a move, with the mover still active
+    #[must_use]
+    pub(crate) fn goal_result(self, moved: &GameStateCore) -> Option<GameResultKind> {
+        match self {
+            Self::Standard | Self::Perft | Self::Antichess | Self::Horde => None,
+            Self::RacingKings => {
+                let has_reached_goal = |player| {
+                    moved
+                        .board
+                        .king_position(player)
+                        .is_some_and(|king| king.row == RACING_KINGS_GOAL_ROW)
+                };
+
+                match moved.active_player {
+                    // black moves second, so it gets one last chance to also reach the goal
+                    PlayerKind::White => {
+                        if has_reached_goal(PlayerKind::White).not() {
+                            return None;
+                        }
+                        let black_can_catch_up = moved
+                            .with_opponent_active()
+                            .legal_moves_for(self)
+                            .any(|mv| {
+                                mv.kind.piece_kind() == PieceKind::King
+                                    && mv.destination.row == RACING_KINGS_GOAL_ROW
+                            });
+                        black_can_catch_up.not().then_some(GameResultKind::Win)
+                    }
+                    PlayerKind::Black => {
+                        match (
+                            has_reached_goal(PlayerKind::White),
+                            has_reached_goal(PlayerKind::Black),
+                        ) {
+                            (true, true) => {
+                                Some(GameResultKind::Draw(DrawKind::BothKingsReachedGoal))
+                            }
+                            (true, false) => Some(GameResultKind::Loss),
+                            (false, true) => Some(GameResultKind::Win),
+                            (false, false) => None,
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn promotion_options(self) -> &'static [PieceKind] {
+        match self {
+            Self::Standard | Self::Perft | Self::Horde | Self::RacingKings => {
+                &PieceKind::PROMOTION_OPTIONS
+            }
+            Self::Antichess => &PieceKind::ANTICHESS_PROMOTION_OPTIONS,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -308,6 +472,37 @@ impl GameState<Ongoing> {
         }
     }
 
+    #[must_use]
+    pub fn antichess() -> Self {
+        Self {
+            core: GameStateCore {
+                castling_rights: CastlingRights::none_available(),
+                ..Default::default()
+            },
+            rule_set: RuleSet::Antichess,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn horde() -> Self {
+        Self {
+            core: GameStateCore::try_from_fen(HORDE_START_FEN).expect("this to be a valid fen"),
+            rule_set: RuleSet::Horde,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn racing_kings() -> Self {
+        Self {
+            core: GameStateCore::try_from_fen(RACING_KINGS_START_FEN)
+                .expect("this to be a valid fen"),
+            rule_set: RuleSet::RacingKings,
+            ..Default::default()
+        }
+    }
+
     #[must_use]
     pub fn step(mut self, mv: Move) -> StepResult {
         self.core.board.apply_move(mv);
@@ -352,7 +547,7 @@ impl GameState<Ongoing> {
             future.en_passant_target = Some(possible_en_passant_target);
 
             future
-                .legal_moves()
+                .legal_moves_for(game.rule_set)
                 .any(|mv| mv.kind.is_pawn_en_passant())
                 .then_some(possible_en_passant_target)
         } else {
@@ -376,19 +571,19 @@ impl GameState<Ongoing> {
             }
         }
 
+        if let Some(kind) = game.rule_set.goal_result(&game.core) {
+            return StepResult::Terminated(GameResult {
+                kind,
+                final_game_state: game.terminated(),
+            });
+        }
+
         let future = game.core.with_opponent_active();
-        if future.legal_moves().count() == 0 {
-            return if future.board.is_king_checked(future.active_player) {
-                StepResult::Terminated(GameResult {
-                    kind: GameResultKind::Win,
-                    final_game_state: game.terminated(),
-                })
-            } else {
-                StepResult::Terminated(GameResult {
-                    kind: GameResultKind::Draw(DrawKind::Stalemate),
-                    final_game_state: game.terminated(),
-                })
-            };
+        if future.legal_moves_for(game.rule_set).count() == 0 {
+            return StepResult::Terminated(GameResult {
+                kind: game.rule_set.out_of_moves_result(&future),
+                final_game_state: game.terminated(),
+            });
         }
 
         if game.rule_set != RuleSet::Perft {
@@ -415,7 +610,8 @@ impl GameState<Ongoing> {
 
         let piece_counts = game.core.board.piece_counts();
 
-        if piece_counts == PieceCounts::KINGS_ONLY {
+        if game.rule_set.has_insufficient_material_draw() && piece_counts == PieceCounts::KINGS_ONLY
+        {
             return StepResult::Terminated(GameResult {
                 kind: GameResultKind::Draw(DrawKind::InsufficientMaterial),
                 final_game_state: game.terminated(),
@@ -500,11 +696,11 @@ pub struct Position {
     pub en_passant_target: Option<Square>,
 }
 
-pub(crate) gen fn attacked_squares(
-    board: &Board,
-    origin: Square,
+pub(crate) gen fn attacked_squares<const COLS: usize, const ROWS: usize>(
+    board: &BoardOf<COLS, ROWS>,
+    origin: SquareOf<COLS, ROWS>,
     active_player: PlayerKind,
-) -> Threat {
+) -> ThreatOf<COLS, ROWS> {
     let Some(piece) = board[origin] else {
         return;
     };
@@ -512,21 +708,24 @@ pub(crate) gen fn attacked_squares(
         return;
     }
 
-    let (directions, range_upper_bound) = piece.threat_directions();
-    let range_upper_bound = i32::from(range_upper_bound);
-
-    let rays = directions.iter().map(move |direction| {
-        (1..=range_upper_bound)
-            .map(move |range| origin + (*direction * range))
-            .take_while(Result::is_ok) // ugly but right, once this is Err(_) once, it'll _always_ be out of bounds!
-            .map(Result::unwrap)
-    });
+    let rays = piece
+        .threat_directions()
+        .iter()
+        .flat_map(move |(directions, range_upper_bound)| {
+            let range_upper_bound = i32::from(*range_upper_bound);
+            directions.iter().map(move |direction| {
+                (1..=range_upper_bound)
+                    .map(move |range| origin + (*direction * range))
+                    .take_while(Result::is_ok) // ugly but right, once this is Err(_) once, it'll _always_ be out of bounds!
+                    .map(Result::unwrap)
+            })
+        });
 
     for ray in rays {
         for destination in ray {
             match board[destination] {
                 None => {
-                    yield Threat {
+                    yield ThreatOf {
                         piece,
                         origin,
                         destination,
@@ -536,7 +735,7 @@ pub(crate) gen fn attacked_squares(
                     break;
                 }
                 Some(attacked_piece) if attacked_piece.owner != active_player => {
-                    yield Threat {
+                    yield ThreatOf {
                         piece,
                         origin,
                         destination,
@@ -13,15 +13,12 @@
     const_default,
     const_clone,
     derive_const,
-    iter_collect_into,
     ascii_char,
     ascii_char_variants,
     result_option_map_or_default,
     stmt_expr_attributes,
     coroutines,
-    gen_blocks,
-    //maybe not my brightest idea
-    core_intrinsics,
+    gen_blocks
 )]
 #![forbid(unsafe_code)]
 #![no_std]
@@ -32,12 +29,30 @@ extern crate std;
 
 pub mod board;
 pub mod coord;
+pub mod endgame;
+pub mod eval;
 pub mod game;
+#[cfg(all(feature = "rand", any(test, feature = "std")))]
+pub mod mcts;
 pub mod move_gen;
+pub mod move_picker;
 pub mod mv;
 pub mod notation;
 pub mod piece;
 pub mod player;
+#[cfg(feature = "rand")]
+pub mod playout;
+pub mod polyglot;
+pub mod search;
+#[cfg(any(test, feature = "std"))]
+pub mod search_thread;
+#[cfg(any(test, feature = "std"))]
+pub mod syzygy;
+pub mod time;
+pub mod tt;
+#[cfg(any(test, feature = "std"))]
+pub mod tune;
+pub mod zobrist;
 
 #[cfg(test)]
 mod testing;
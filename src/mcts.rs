@@ -0,0 +1,517 @@
+//! monte carlo tree search: UCT over random playouts from [`GameState::random_walk`]. far weaker
+//! than [`crate::search::Searcher`], but every step of it is easy to follow.
+
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::eval::evaluate;
+use crate::eval::win_chance;
+use crate::game::GameResult;
+use crate::game::GameResultKind;
+use crate::game::GameState;
+use crate::game::Ongoing;
+use crate::game::StepResult;
+use crate::mv::Move;
+use crate::time::Clock;
+use crate::time::StdClock;
+
+/// how a new node finds out roughly how good it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Playout {
+    /// random moves until the game is over, a draw if it isn't after `max_plies`
+    Random { max_plies: u32 },
+    /// random moves for `plies`, then the evaluation guesses how it ends
+    RandomThenEvaluate { plies: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsResult {
+    /// `None` when the budget didn't allow a single iteration
+    pub best_move: Option<Move>,
+    /// added up over all trees
+    pub iterations: u64,
+    /// how often the best move was tried
+    pub visits: u64,
+    /// the share of the best move's playouts the side to move won, draws count half
+    pub win_rate: f64,
+    /// the visits the root had from searching the moves before, before this search started
+    pub reused_visits: u64,
+}
+
+/// per tree, when neither the iterations nor the movetime are limited
+const DEFAULT_ITERATIONS: u64 = 1_000;
+
+#[derive(Debug)]
+pub struct MctsPlayer {
+    exploration: f64,
+    playout: Playout,
+    /// per tree
+    iterations: Option<u64>,
+    movetime: Option<Duration>,
+    threads: usize,
+    /// the trees take the seeds from this one up, `None` for fresh randomness
+    seed: Option<u64>,
+    clock: Arc<dyn Clock>,
+    /// one per thread, kept from the last search for whatever gets played next
+    trees: Vec<Tree>,
+}
+
+impl Default for MctsPlayer {
+    fn default() -> Self {
+        Self {
+            exploration: core::f64::consts::SQRT_2,
+            // whole random games would take seconds a move
+            playout: Playout::RandomThenEvaluate { plies: 2 },
+            iterations: Some(DEFAULT_ITERATIONS),
+            movetime: None,
+            threads: 1,
+            seed: None,
+            clock: Arc::new(StdClock::new()),
+            trees: vec![],
+        }
+    }
+}
+
+impl MctsPlayer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// how much UCT favors moves that haven't been tried much, the square root of 2 by default
+    #[must_use]
+    pub const fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_playout(mut self, playout: Playout) -> Self {
+        self.playout = playout;
+        self
+    }
+
+    /// iterations for every tree, `None` for as many as the movetime allows. without a movetime
+    /// either, it's the default 1000.
+    #[must_use]
+    pub const fn with_iterations(mut self, iterations: Option<u64>) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_movetime(mut self, movetime: Option<Duration>) -> Self {
+        self.movetime = movetime;
+        self
+    }
+
+    /// root parallelism: a tree of its own for every thread, with the visits of the root moves
+    /// added up in the end. without `rayon` the trees are searched one after the other.
+    #[must_use]
+    pub const fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = if threads > 1 { threads } else { 1 };
+        self
+    }
+
+    /// the same seed with an iteration budget plays the same moves, only the movetime can make
+    /// it differ
+    #[must_use]
+    pub const fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// forgets the trees kept from the last search, for a new game
+    pub fn clear(&mut self) {
+        self.trees.clear();
+    }
+
+    /// keeps the trees for the next search, which starts from what they already know about
+    /// its position if it's this one or up to two plies further
+    pub fn search(&mut self, game: &GameState<Ongoing>) -> MctsResult {
+        let mut trees = core::mem::take(&mut self.trees);
+        for thread in trees.len()..self.threads {
+            let rng = self.seed.map_or_else(
+                || StdRng::from_rng(&mut rand::rng()),
+                |seed| StdRng::seed_from_u64(seed.wrapping_add(thread as u64)),
+            );
+            trees.push(Tree::new(game.clone(), rng));
+        }
+        let mut trees = trees
+            .into_iter()
+            .take(self.threads)
+            .map(|tree| tree.reused_for(game))
+            .collect::<Vec<_>>();
+        let reused_visits = trees.iter().map(|tree| tree.nodes[0].visits).sum();
+
+        let budget = Budget {
+            exploration: self.exploration,
+            playout: self.playout,
+            iterations: self
+                .iterations
+                .or_else(|| self.movetime.is_none().then_some(DEFAULT_ITERATIONS)),
+            movetime: self.movetime,
+            clock: &*self.clock,
+            started_at: self.clock.now(),
+        };
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            trees.par_iter_mut().for_each(|tree| tree.grow(&budget));
+        }
+        #[cfg(not(feature = "rayon"))]
+        for tree in &mut trees {
+            tree.grow(&budget);
+        }
+
+        let result = best_root_move(&trees, reused_visits);
+        self.trees = trees;
+        result
+    }
+}
+
+/// what every tree gets to spend
+struct Budget<'clock> {
+    exploration: f64,
+    playout: Playout,
+    iterations: Option<u64>,
+    movetime: Option<Duration>,
+    clock: &'clock dyn Clock,
+    started_at: Duration,
+}
+
+impl Budget<'_> {
+    fn allows(&self, iterations: u64) -> bool {
+        self.iterations.is_none_or(|limit| iterations < limit)
+            && self
+                .movetime
+                .is_none_or(|movetime| self.clock.now().saturating_sub(self.started_at) < movetime)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    /// the move that led here, `None` at the root
+    mv: Option<Move>,
+    children: Vec<usize>,
+    /// the moves that don't have a child yet
+    untried: Vec<Move>,
+    visits: u64,
+    /// the results for the player who moved here added up, 1 for a win and 0.5 for a draw
+    reward: f64,
+    /// the result for the player who moved here, when that move ended the game
+    terminal: Option<f64>,
+}
+
+impl Node {
+    fn new(mv: Option<Move>, game: &GameState<Ongoing>) -> Self {
+        Self {
+            mv,
+            children: vec![],
+            untried: game.legal_moves().collect(),
+            visits: 0,
+            reward: 0.0,
+            terminal: None,
+        }
+    }
+
+    const fn terminal(mv: Move, result: f64) -> Self {
+        Self {
+            mv: Some(mv),
+            children: vec![],
+            untried: vec![],
+            visits: 0,
+            reward: 0.0,
+            terminal: Some(result),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Tree {
+    root: GameState<Ongoing>,
+    /// the root first
+    nodes: Vec<Node>,
+    iterations: u64,
+    rng: StdRng,
+}
+
+impl Tree {
+    fn new(root: GameState<Ongoing>, rng: StdRng) -> Self {
+        Self {
+            nodes: vec![Node::new(None, &root)],
+            root,
+            iterations: 0,
+            rng,
+        }
+    }
+
+    /// the part of this tree that starts at `game`, or a new one
+    fn reused_for(self, game: &GameState<Ongoing>) -> Self {
+        let nodes = &self.nodes;
+        let mut found = None;
+        let mut frontier = vec![(0, self.root.clone())];
+        for _ in 0..=2 {
+            if let Some((node, _)) = frontier.iter().find(|(_, state)| state.core == game.core) {
+                found = Some(*node);
+                break;
+            }
+            frontier = frontier
+                .into_iter()
+                .flat_map(|(node, state)| {
+                    nodes[node].children.iter().filter_map(move |child| {
+                        let mv = nodes[*child].mv?;
+                        match state.clone().step(mv) {
+                            StepResult::Ongoing(next) => Some((*child, next)),
+                            StepResult::Terminated(_) => None,
+                        }
+                    })
+                })
+                .collect();
+        }
+        let Some(found) = found else {
+            return Self::new(game.clone(), self.rng);
+        };
+
+        // copied over breadth first, so the children of a node come after it
+        let mut nodes = vec![];
+        let mut queue = vec![found];
+        let mut next = 0;
+        while let Some(old) = queue.get(next).copied() {
+            let mut node = self.nodes[old].clone();
+            node.children = node
+                .children
+                .iter()
+                .map(|child| {
+                    queue.push(*child);
+                    queue.len() - 1
+                })
+                .collect();
+            nodes.push(node);
+            next += 1;
+        }
+        nodes[0].mv = None;
+        Self {
+            root: game.clone(),
+            nodes,
+            iterations: 0,
+            rng: self.rng,
+        }
+    }
+
+    fn grow(&mut self, budget: &Budget) {
+        while budget.allows(self.iterations) {
+            self.iterate(budget);
+            self.iterations += 1;
+        }
+    }
+
+    /// down the tree by UCT, one new node, a playout from it, and its result back up
+    fn iterate(&mut self, budget: &Budget) {
+        let mut game = self.root.clone();
+        let mut path = vec![0];
+        let mut node = 0;
+        let result = loop {
+            if let Some(result) = self.nodes[node].terminal {
+                break result;
+            }
+            if let Some(mv) = self.nodes[node].untried.pop() {
+                let (child, result) = match game.step(mv) {
+                    StepResult::Ongoing(next) => {
+                        let result = playout(next.clone(), budget.playout, &mut self.rng);
+                        (Node::new(Some(mv), &next), result)
+                    }
+                    StepResult::Terminated(GameResult { kind, .. }) => {
+                        let result = mover_result(kind);
+                        (Node::terminal(mv, result), result)
+                    }
+                };
+                self.nodes.push(child);
+                let index = self.nodes.len() - 1;
+                self.nodes[node].children.push(index);
+                path.push(index);
+                break result;
+            }
+            let Some(child) = self.select(node, budget.exploration) else {
+                break 0.5; // nothing to move, can't happen in an ongoing game
+            };
+            path.push(child);
+            node = child;
+            if self.nodes[child].terminal.is_none() {
+                let mv = self.nodes[child].mv.expect("only the root to have no move");
+                let StepResult::Ongoing(next) = game.step(mv) else {
+                    unreachable!("children that end the game to be terminal");
+                };
+                game = next;
+            }
+        };
+
+        // the result alternates between the players on the way up
+        let mut result = result;
+        for node in path.into_iter().rev() {
+            self.nodes[node].visits += 1;
+            self.nodes[node].reward += result;
+            result = 1.0 - result;
+        }
+    }
+
+    fn select(&self, parent: usize, exploration: f64) -> Option<usize> {
+        #[allow(clippy::cast_precision_loss)]
+        let parent_visits = (self.nodes[parent].visits as f64).ln();
+        self.nodes[parent].children.iter().copied().max_by(|a, b| {
+            let uct = |node: &Node| {
+                #[allow(clippy::cast_precision_loss)]
+                let visits = node.visits as f64;
+                node.reward / visits + exploration * (parent_visits / visits).sqrt()
+            };
+            uct(&self.nodes[*a]).total_cmp(&uct(&self.nodes[*b]))
+        })
+    }
+}
+
+/// for the player who moved into `game`
+fn playout(game: GameState<Ongoing>, playout: Playout, rng: &mut StdRng) -> f64 {
+    let mover = game.core.active_player.opponent();
+    let plies = match playout {
+        Playout::Random { max_plies } => max_plies,
+        Playout::RandomThenEvaluate { plies } => plies,
+    };
+    match game.random_walk(plies, rng, |_, _| ()) {
+        StepResult::Terminated(GameResult {
+            kind,
+            final_game_state,
+        }) => {
+            let result = mover_result(kind);
+            if final_game_state.core.active_player == mover {
+                result
+            } else {
+                1.0 - result
+            }
+        }
+        StepResult::Ongoing(end) => match playout {
+            Playout::Random { .. } => 0.5,
+            Playout::RandomThenEvaluate { .. } => {
+                let result = win_chance(f64::from(evaluate(&end.core).0));
+                if end.core.active_player == mover {
+                    result
+                } else {
+                    1.0 - result
+                }
+            }
+        },
+    }
+}
+
+/// for the player whose move ended the game
+const fn mover_result(kind: GameResultKind) -> f64 {
+    match kind {
+        GameResultKind::Win => 1.0,
+        GameResultKind::Loss => 0.0,
+        GameResultKind::Draw(_) => 0.5,
+    }
+}
+
+/// the most visited root move, with the visits of all trees added up
+fn best_root_move(trees: &[Tree], reused_visits: u64) -> MctsResult {
+    let mut moves = Vec::<(Move, u64, f64)>::new();
+    for tree in trees {
+        for child in &tree.nodes[0].children {
+            let node = &tree.nodes[*child];
+            let Some(mv) = node.mv else { continue };
+            match moves.iter_mut().find(|(other, ..)| *other == mv) {
+                Some((_, visits, reward)) => {
+                    *visits += node.visits;
+                    *reward += node.reward;
+                }
+                None => moves.push((mv, node.visits, node.reward)),
+            }
+        }
+    }
+    let best = moves.iter().max_by_key(|(_, visits, _)| *visits);
+    #[allow(clippy::cast_precision_loss)]
+    MctsResult {
+        best_move: best.map(|(mv, ..)| *mv),
+        iterations: trees.iter().map(|tree| tree.iterations).sum(),
+        visits: best.map_or(0, |(_, visits, _)| *visits),
+        win_rate: best.map_or(0.5, |(_, visits, reward)| reward / *visits as f64),
+        reused_visits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::game_from_fen;
+
+    #[test]
+    fn finds_mate_in_one() {
+        let game = game_from_fen("6k1/5ppp/8/8/8/8/r4PPP/3R2K1 w - - 0 1");
+        let result = MctsPlayer::new()
+            .with_playout(Playout::RandomThenEvaluate { plies: 2 })
+            .with_iterations(Some(300))
+            .search(&game);
+
+        let mate = game.legal_moves().find(|mv| {
+            matches!(
+                game.clone().step(*mv),
+                StepResult::Terminated(GameResult {
+                    kind: GameResultKind::Win,
+                    ..
+                })
+            )
+        });
+        assert_eq!(mate, result.best_move);
+        assert_eq!(300, result.iterations);
+        assert!(result.win_rate > 0.9, "{result:?}");
+    }
+
+    #[test]
+    fn trees_are_reused_and_added_up() {
+        let game = GameState::new();
+        let mut player = MctsPlayer::new()
+            .with_playout(Playout::RandomThenEvaluate { plies: 2 })
+            .with_iterations(Some(200))
+            .with_threads(2);
+        let first = player.search(&game);
+        assert_eq!(400, first.iterations);
+        assert_eq!(0, first.reused_visits);
+
+        let StepResult::Ongoing(next) = game.step(first.best_move.unwrap()) else {
+            panic!("the first move to not end the game");
+        };
+        let second = player.search(&next);
+        assert!(second.reused_visits > 0, "{second:?}");
+    }
+
+    #[test]
+    fn same_seed_same_search() {
+        let search = |seed| {
+            MctsPlayer::new()
+                .with_playout(Playout::Random { max_plies: 20 })
+                .with_iterations(Some(100))
+                .with_seed(Some(seed))
+                .search(&GameState::new())
+        };
+        assert_eq!(search(3), search(3));
+    }
+
+    #[test]
+    fn no_budget_falls_back_to_the_default() {
+        let result = MctsPlayer::new()
+            .with_playout(Playout::RandomThenEvaluate { plies: 0 })
+            .with_iterations(None)
+            .search(&GameState::new());
+        assert_eq!(DEFAULT_ITERATIONS, result.iterations);
+    }
+}
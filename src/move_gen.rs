@@ -10,8 +10,10 @@ use crate::game::GameResultKind;
 use crate::game::GameState;
 use crate::game::GameStateCore;
 use crate::game::Ongoing;
+use crate::game::RuleSet;
 use crate::game::StepResult;
 use crate::game::Terminated;
+use crate::game::attacked_squares;
 use crate::mv::KingMove;
 use crate::mv::Move;
 use crate::mv::MoveKind;
@@ -20,17 +22,26 @@ use crate::mv::Threat;
 use crate::piece::PieceKind;
 
 impl GameState<Ongoing> {
+    pub fn legal_moves(&self) -> impl Iterator<Item = Move> {
+        self.core.legal_moves_for(self.rule_set)
+    }
+
+    pub fn tactical_moves(&self) -> impl Iterator<Item = Move> {
+        self.core.tactical_moves_for(self.rule_set)
+    }
+
     #[must_use]
     pub fn search(self, max_depth: u32, checker: impl Fn(&Self)) -> SearchStats {
         let mut terminated_games_checkmate: Vec<GameState<Terminated>> = vec![];
         let mut terminated_games_draw: Vec<GameState<Terminated>> = vec![];
+        let mut terminated_games_loss: Vec<GameState<Terminated>> = vec![];
         let mut continued_games: Vec<Self> = vec![self];
         let mut new_continued_games: Vec<Self> = vec![];
 
         for _ in 0..=max_depth {
             continued_games.clone().into_iter().for_each(|game| {
                 checker(&game);
-                let legal_moves: Vec<Move> = game.core.legal_moves().collect();
+                let legal_moves: Vec<Move> = game.legal_moves().collect();
 
                 for mv in legal_moves {
                     match game.clone().step(mv) {
@@ -42,6 +53,10 @@ impl GameState<Ongoing> {
                             kind: GameResultKind::Draw(_),
                             final_game_state,
                         }) => terminated_games_draw.push(final_game_state),
+                        StepResult::Terminated(GameResult {
+                            kind: GameResultKind::Loss,
+                            final_game_state,
+                        }) => terminated_games_loss.push(final_game_state),
                         StepResult::Ongoing(game_state) => {
                             new_continued_games.push(game_state);
                         }
@@ -57,26 +72,35 @@ impl GameState<Ongoing> {
             continued_games: continued_games.len(),
             checkmated_games: terminated_games_checkmate.len(),
             drawn_games: terminated_games_draw.len(),
+            lost_games: terminated_games_loss.len(),
         }
     }
 
+    /// plays random moves from `rng`, so the same seed always takes the same walk. `checker` sees
+    /// every position on the way together with the moves that led there from `self`.
     #[cfg(feature = "rand")]
-    pub fn random_walk(self, max_depth: u32, checker: impl Fn(&Self)) -> StepResult {
+    pub fn random_walk(
+        self,
+        max_depth: u32,
+        rng: &mut impl rand::Rng,
+        checker: impl Fn(&Self, &[Move]),
+    ) -> StepResult {
         use crate::alloc::borrow::ToOwned as _;
 
-        let mut rng = rand::rng();
         let mut game = self;
+        let mut moves = vec![];
 
         for _ in 0..max_depth {
             use rand::seq::IndexedRandom;
 
-            checker(&game);
-            let legal_moves: Vec<Move> = game.core.legal_moves().collect();
+            checker(&game, &moves);
+            let legal_moves: Vec<Move> = game.legal_moves().collect();
 
             let random_move = legal_moves
-                .choose(&mut rng)
+                .choose(rng)
                 .expect("a GameState<Ongoing> to always have legal moves")
                 .to_owned();
+            moves.push(random_move);
 
             match game.clone().step(random_move) {
                 StepResult::Ongoing(game_state) => {
@@ -93,15 +117,147 @@ impl GameState<Ongoing> {
 
 impl GameStateCore {
     pub fn legal_moves(&self) -> impl Iterator<Item = Move> {
-        self.threatening_move_candidates()
-            .chain(self.pawn_step_candidates())
-            .chain(self.castle_candidates())
-            .filter(move |mov| {
-                self.board
-                    .with_move_applied(*mov)
-                    .is_king_checked(self.active_player)
-                    .not()
+        self.legal_moves_for(RuleSet::Standard)
+    }
+
+    pub gen fn legal_moves_for(&self, rule_set: RuleSet) -> Move {
+        let candidates = self
+            .threatening_move_candidates(rule_set)
+            .chain(self.pawn_step_candidates(rule_set))
+            .chain(
+                rule_set
+                    .has_castling()
+                    .then(|| self.castle_candidates())
+                    .into_iter()
+                    .flatten(),
+            )
+            .filter(move |mov| self.is_legal_candidate(*mov, rule_set));
+
+        if rule_set.has_forced_captures().not() {
+            for mv in candidates {
+                yield mv;
+            }
+            return;
+        }
+
+        // captures are compulsory, so we have to see all candidates before yielding any of them
+        let candidates = candidates.collect::<Vec<_>>();
+        let has_capture = candidates.iter().any(Move::is_capture);
+        for mv in candidates {
+            if has_capture.not() || mv.is_capture() {
+                yield mv;
+            }
+        }
+    }
+
+    /// the subset of `legal_moves_for` that captures or promotes, without generating any quiet moves
+    pub gen fn tactical_moves_for(&self, rule_set: RuleSet) -> Move {
+        let captures = self
+            .board
+            .threatening_moves_by(self.active_player)
+            .filter(|threat| {
+                self.board[threat.destination].is_some()
+                    || (threat.piece.kind == PieceKind::Pawn
+                        && Some(threat.destination) == self.en_passant_target)
             })
+            .flat_map(move |threat| self.threat_to_move_candidates(threat, rule_set));
+        let promotions = self
+            .pawn_step_candidates(rule_set)
+            .filter(|mov| mov.kind.is_promotion());
+        let candidates = captures
+            .chain(promotions)
+            .filter(move |mov| self.is_legal_candidate(*mov, rule_set));
+
+        if rule_set.has_forced_captures().not() {
+            for mv in candidates {
+                yield mv;
+            }
+            return;
+        }
+
+        // a quiet promotion is only legal if there is nothing to capture
+        let candidates = candidates.collect::<Vec<_>>();
+        let has_capture = candidates.iter().any(Move::is_capture);
+        for mv in candidates {
+            if has_capture.not() || mv.is_capture() {
+                yield mv;
+            }
+        }
+    }
+
+    /// the subset of `legal_moves_for` that neither captures nor promotes
+    pub gen fn quiet_moves_for(&self, rule_set: RuleSet) -> Move {
+        if rule_set.has_forced_captures()
+            && self.tactical_moves_for(rule_set).any(|mv| mv.is_capture())
+        {
+            return;
+        }
+
+        let candidates = self
+            .board
+            .threatening_moves_by(self.active_player)
+            .filter(|threat| {
+                threat.piece.kind != PieceKind::Pawn && self.board[threat.destination].is_none()
+            })
+            .flat_map(move |threat| self.threat_to_move_candidates(threat, rule_set))
+            .chain(
+                self.pawn_step_candidates(rule_set)
+                    .filter(|mov| mov.kind.is_promotion().not()),
+            )
+            .chain(
+                rule_set
+                    .has_castling()
+                    .then(|| self.castle_candidates())
+                    .into_iter()
+                    .flatten(),
+            )
+            .filter(move |mov| self.is_legal_candidate(*mov, rule_set));
+
+        for mv in candidates {
+            yield mv;
+        }
+    }
+
+    /// candidates for moves starting on `origin`, without checking whether they are legal
+    pub(crate) fn move_candidates_from(
+        &self,
+        origin: Square,
+        rule_set: RuleSet,
+    ) -> impl Iterator<Item = Move> {
+        attacked_squares(&self.board, origin, self.active_player)
+            .flat_map(move |threat| self.threat_to_move_candidates(threat, rule_set))
+            .chain(
+                self.pawn_step_candidates(rule_set)
+                    .filter(move |mov| mov.origin == origin),
+            )
+            .chain(
+                (rule_set.has_castling() && origin == self.active_player.king_start())
+                    .then(|| self.castle_candidates())
+                    .into_iter()
+                    .flatten(),
+            )
+    }
+
+    /// same as `legal_moves_for(rule_set).any(|legal| legal == mv)`, but only generates the
+    /// moves starting where `mv` does. for checking moves remembered from other positions.
+    #[must_use]
+    pub fn is_legal_for(&self, mv: Move, rule_set: RuleSet) -> bool {
+        self.move_candidates_from(mv.origin, rule_set)
+            .any(|candidate| candidate == mv)
+            && self.is_legal_candidate(mv, rule_set)
+            && (rule_set.has_forced_captures().not()
+                || mv.is_capture()
+                || self
+                    .tactical_moves_for(rule_set)
+                    .any(|mv| mv.is_capture())
+                    .not())
+    }
+
+    fn is_legal_candidate(&self, mov: Move, rule_set: RuleSet) -> bool {
+        let board = self.board.with_move_applied(mov);
+        (rule_set.has_royal_king().not() || board.is_king_checked(self.active_player).not())
+            && (rule_set.allows_giving_check()
+                || board.is_king_checked(self.active_player.opponent()).not())
     }
 
     gen fn castle_candidates(&self) -> Move {
@@ -129,13 +285,13 @@ impl GameStateCore {
         }
     }
 
-    fn threatening_move_candidates(&self) -> impl Iterator<Item = Move> {
+    fn threatening_move_candidates(&self, rule_set: RuleSet) -> impl Iterator<Item = Move> {
         self.board
             .threatening_moves_by(self.active_player)
-            .flat_map(|threat| self.threat_to_move_candidates(threat))
+            .flat_map(move |threat| self.threat_to_move_candidates(threat, rule_set))
     }
 
-    gen fn pawn_step_candidates(&self) -> Move {
+    gen fn pawn_step_candidates(&self, rule_set: RuleSet) -> Move {
         for square in Square::ALL {
             if self.board[square] != Some(PieceKind::Pawn.to_piece(self.active_player)) {
                 continue;
@@ -149,7 +305,7 @@ impl GameStateCore {
             }
 
             if one_in_front.row == self.active_player.pawn_promotion_row() {
-                for promotion_option in PieceKind::PROMOTION_OPTIONS {
+                for promotion_option in rule_set.promotion_options() {
                     yield Move {
                         kind: MoveKind::Pawn(PawnMove::SingleStep {
                             promotion_replacement: Some(
@@ -174,7 +330,10 @@ impl GameStateCore {
                 continue; // this one can def be out of range.
             };
 
-            if square.row != self.active_player.pawn_starting_row() {
+            if rule_set
+                .is_pawn_double_step_row(self.active_player, square.row)
+                .not()
+            {
                 continue; // pawns can only double-move when they haven't moved yet!
             }
 
@@ -191,7 +350,7 @@ impl GameStateCore {
     }
 
     #[must_use]
-    fn threat_to_move_candidates(&self, threat: Threat) -> Vec<Move> {
+    fn threat_to_move_candidates(&self, threat: Threat, rule_set: RuleSet) -> Vec<Move> {
         let is_capture = self.board[threat.destination].is_some();
         let origin = threat.origin;
         let destination = threat.destination;
@@ -221,9 +380,35 @@ impl GameStateCore {
                 origin,
                 destination,
             }],
+            PieceKind::Archbishop => vec![Move {
+                kind: MoveKind::Archbishop { is_capture },
+                origin,
+                destination,
+            }],
+            PieceKind::Chancellor => vec![Move {
+                kind: MoveKind::Chancellor { is_capture },
+                origin,
+                destination,
+            }],
+            PieceKind::Amazon => vec![Move {
+                kind: MoveKind::Amazon { is_capture },
+                origin,
+                destination,
+            }],
+            PieceKind::Camel => vec![Move {
+                kind: MoveKind::Camel { is_capture },
+                origin,
+                destination,
+            }],
+            PieceKind::Zebra => vec![Move {
+                kind: MoveKind::Zebra { is_capture },
+                origin,
+                destination,
+            }],
             PieceKind::Pawn if is_capture => {
                 if threat.destination.row == self.active_player.pawn_promotion_row() {
-                    PieceKind::PROMOTION_OPTIONS
+                    rule_set
+                        .promotion_options()
                         .iter()
                         .map(|promotion_option| Move {
                             kind: MoveKind::Pawn(PawnMove::Capture {
@@ -262,6 +447,120 @@ impl GameStateCore {
             }
         }
     }
+
+    /// the moves that could have led here, as played in the position before, see
+    /// [`Self::with_move_undone`]. captures, promotions and castling can't be taken back, there's
+    /// no telling what was captured or which rights were given up.
+    pub gen fn unmoves(&self) -> Move {
+        let mover = self.active_player.opponent();
+        for (square, piece) in self.board.pieces() {
+            if piece.owner != mover || self.kept_castling_rights_on(square) {
+                continue;
+            }
+            if piece.kind == PieceKind::Pawn {
+                for mv in self.pawn_unmoves(square) {
+                    yield mv;
+                }
+                continue;
+            }
+            if self.en_passant_target.is_some() {
+                continue; // only a double step leaves one of those
+            }
+
+            // everything but pawns can go back the way it came
+            let unmoves = attacked_squares(&self.board, square, mover)
+                .filter(|threat| self.board[threat.destination].is_none())
+                .flat_map(|threat| self.threat_to_move_candidates(threat, RuleSet::Standard))
+                .map(|mv| Move {
+                    origin: mv.destination,
+                    destination: mv.origin,
+                    ..mv
+                })
+                .filter(|mv| self.is_legal_unmove(*mv));
+            for mv in unmoves {
+                yield mv;
+            }
+        }
+    }
+
+    /// the position before `mv`, one of [`Self::unmoves`]. the clocks are left alone, and the
+    /// position before never has an en passant target.
+    #[must_use]
+    pub const fn with_move_undone(mut self, mv: Move) -> Self {
+        self.board.mov(mv.destination, mv.origin);
+        self.active_player = self.active_player.opponent();
+        self.en_passant_target = None;
+        self
+    }
+
+    gen fn pawn_unmoves(&self, square: Square) -> Move {
+        let mover = self.active_player.opponent();
+        let Ok(one_behind) = square + mover.backwards_one_row() else {
+            return;
+        };
+        // the back row is where the opponent promotes
+        if self.board[one_behind].is_some()
+            || one_behind.row == mover.opponent().pawn_promotion_row()
+        {
+            return;
+        }
+
+        let single_step = Move {
+            kind: MoveKind::Pawn(PawnMove::SingleStep {
+                promotion_replacement: None,
+            }),
+            origin: one_behind,
+            destination: square,
+        };
+        if self.en_passant_target.is_none() && self.is_legal_unmove(single_step) {
+            yield single_step;
+        }
+
+        let Ok(two_behind) = one_behind + mover.backwards_one_row() else {
+            return;
+        };
+        if self.board[two_behind].is_some()
+            || RuleSet::Standard
+                .is_pawn_double_step_row(mover, two_behind.row)
+                .not()
+        {
+            return;
+        }
+        // the target is there exactly when the double step can be taken en passant
+        let mut with_target = *self;
+        with_target.en_passant_target = Some(one_behind);
+        let capturable = with_target
+            .legal_moves()
+            .any(|mv| mv.kind.is_pawn_en_passant());
+        let double_step = Move {
+            kind: MoveKind::Pawn(PawnMove::DoubleStep),
+            origin: two_behind,
+            destination: square,
+        };
+        if capturable == (self.en_passant_target == Some(one_behind))
+            && self.is_legal_unmove(double_step)
+        {
+            yield double_step;
+        }
+    }
+
+    /// the player to move can't have been left in check
+    fn is_legal_unmove(&self, mv: Move) -> bool {
+        self.with_move_undone(mv)
+            .board
+            .is_king_checked(self.active_player)
+            .not()
+    }
+
+    /// kings and rooks that still have castling rights haven't moved yet
+    fn kept_castling_rights_on(&self, square: Square) -> bool {
+        let mover = self.with_opponent_active();
+        CastlingSide::ALL.into_iter().any(|castling_side| {
+            mover.has_castling_right(castling_side)
+                && (square == mover.active_player.king_start()
+                    || square == mover.active_player.rook_start(castling_side))
+        })
+    }
 }
 
 impl Board {
@@ -286,7 +585,12 @@ impl Board {
             | MoveKind::Bishop { .. }
             | MoveKind::Rook { .. }
             | MoveKind::Queen { .. }
-            | MoveKind::King(KingMove::Normal { .. }) => { /*nothing */ }
+            | MoveKind::King(KingMove::Normal { .. })
+            | MoveKind::Archbishop { .. }
+            | MoveKind::Chancellor { .. }
+            | MoveKind::Amazon { .. }
+            | MoveKind::Camel { .. }
+            | MoveKind::Zebra { .. } => { /*nothing */ }
 
             MoveKind::Pawn(PawnMove::EnPassant { affected }) => {
                 self[affected] = None;
@@ -320,6 +624,7 @@ pub struct SearchStats {
     pub continued_games: usize,
     pub checkmated_games: usize,
     pub drawn_games: usize,
+    pub lost_games: usize,
 }
 
 #[cfg(test)]
@@ -328,6 +633,8 @@ mod tests {
 
     use super::*;
     use crate::notation::san::standard_algebraic_notation;
+    #[cfg(feature = "rand")]
+    use crate::notation::uci::uci_notation;
     use crate::testing::skip_if_no_expensive_test_opt_in;
 
     #[test]
@@ -366,19 +673,274 @@ mod tests {
         println!("---------------------------");
     }
 
+    #[test]
+    fn fairy_piece_move_counts() {
+        // 5 king moves + whatever the fairy piece on d4 can do on an otherwise empty board
+        for (fen, expected) in [
+            ("8/8/7k/8/3A4/8/7K/8 w - - 0 1", 5 + 8 + 13),
+            ("8/8/7k/8/3C4/8/7K/8 w - - 0 1", 5 + 8 + 14),
+            ("8/8/7k/8/3M4/8/7K/8 w - - 0 1", 5 + 8 + 27),
+            ("8/8/7k/8/3L4/8/7K/8 w - - 0 1", 5 + 8),
+            ("8/8/7k/8/3Z4/8/7K/8 w - - 0 1", 5 + 8),
+        ] {
+            let core = GameStateCore::try_from_fen(fen).unwrap();
+            assert_eq!(expected, core.legal_moves().count(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn antichess_perft() {
+        let game = GameState::antichess();
+
+        let stats = game.clone().search(1, |_| ());
+        assert_eq!(400, stats.continued_games);
+
+        let stats = game.search(2, |_| ());
+        assert_eq!(8067, stats.continued_games);
+    }
+
+    #[test]
+    fn horde_perft() {
+        let game = GameState::horde();
+        assert_eq!(8, game.legal_moves().count());
+
+        let stats = game.clone().search(1, |_| ());
+        assert_eq!(128, stats.continued_games);
+
+        let stats = game.search(2, |_| ());
+        assert_eq!(1274, stats.continued_games);
+    }
+
+    #[test]
+    fn racing_kings_perft() {
+        let game = GameState::racing_kings();
+        assert_eq!(21, game.legal_moves().count());
+
+        let stats = game.clone().search(1, |_| ());
+        assert_eq!(421, stats.continued_games);
+
+        skip_if_no_expensive_test_opt_in!();
+
+        let stats = game.search(2, |_| ());
+        assert_eq!(11264, stats.continued_games);
+    }
+
+    #[test]
+    fn racing_kings_goal() {
+        let core = GameStateCore::try_from_fen("8/6K1/8/8/8/8/k7/8 w - - 0 1").unwrap();
+        let mut game = GameState::with_core(core);
+        game.rule_set = RuleSet::RacingKings;
+
+        let to_goal = |game: &GameState<Ongoing>| {
+            game.legal_moves()
+                .find(|mv| mv.destination.row == crate::coord::Row::_8)
+                .unwrap()
+        };
+
+        let StepResult::Terminated(GameResult { kind, .. }) = game.clone().step(to_goal(&game))
+        else {
+            panic!("black can't catch up, so white should have won");
+        };
+        assert_eq!(GameResultKind::Win, kind);
+
+        let core = GameStateCore::try_from_fen("8/1k4K1/8/8/8/8/8/8 w - - 0 1").unwrap();
+        let mut game = GameState::with_core(core);
+        game.rule_set = RuleSet::RacingKings;
+
+        let StepResult::Ongoing(game) = game.clone().step(to_goal(&game)) else {
+            panic!("black can still catch up");
+        };
+        let StepResult::Terminated(GameResult { kind, .. }) = game.clone().step(to_goal(&game))
+        else {
+            panic!("both kings reached the goal");
+        };
+        assert_eq!(
+            GameResultKind::Draw(crate::game::DrawKind::BothKingsReachedGoal),
+            kind
+        );
+    }
+
+    #[test]
+    fn tactical_moves_are_the_legal_captures_and_promotions() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        ] {
+            let core = GameStateCore::try_from_fen(fen).unwrap();
+            let mut expected = core
+                .legal_moves()
+                .filter(|mv| mv.is_capture() || mv.kind.is_promotion())
+                .map(|mv| std::format!("{mv:?}"))
+                .collect::<Vec<_>>();
+            let mut tactical = core
+                .tactical_moves_for(RuleSet::Standard)
+                .map(|mv| std::format!("{mv:?}"))
+                .collect::<Vec<_>>();
+            expected.sort();
+            tactical.sort();
+
+            assert_eq!(expected, tactical, "{fen}");
+        }
+    }
+
+    #[test]
+    fn tactical_and_quiet_moves_split_the_legal_moves() {
+        let fens_and_rule_sets = [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                RuleSet::Standard,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+                RuleSet::Standard,
+            ),
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/3P4/8/PPP1PPPP/RNBQKBNR w - - 0 2",
+                RuleSet::Antichess,
+            ),
+            ("8/P7/8/8/8/8/8/k7 w - - 0 1", RuleSet::Antichess),
+        ];
+        for (fen, rule_set) in fens_and_rule_sets {
+            let core = GameStateCore::try_from_fen(fen).unwrap();
+            let mut legal = core
+                .legal_moves_for(rule_set)
+                .map(|mv| std::format!("{mv:?}"))
+                .collect::<Vec<_>>();
+            let mut split = core
+                .tactical_moves_for(rule_set)
+                .chain(core.quiet_moves_for(rule_set))
+                .map(|mv| std::format!("{mv:?}"))
+                .collect::<Vec<_>>();
+            legal.sort();
+            split.sort();
+
+            assert_eq!(legal, split, "{fen}");
+            for mv in core.legal_moves_for(rule_set) {
+                assert!(core.is_legal_for(mv, rule_set), "{fen} {mv:?}");
+            }
+        }
+
+        // a legal move from somewhere else isn't legal here
+        let core = GameStateCore::default();
+        let elsewhere = GameStateCore::try_from_fen(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+        )
+        .unwrap();
+        let king_move = elsewhere
+            .legal_moves()
+            .find(|mv| mv.kind.piece_kind() == PieceKind::King)
+            .unwrap();
+        assert!(core.is_legal_for(king_move, RuleSet::Standard).not());
+    }
+
+    #[test]
+    fn unmoves_take_back_the_quiet_moves() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/ppp1pppp/8/4P3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+        ] {
+            let core = GameStateCore::try_from_fen(fen).unwrap();
+            let quiet_moves = core.legal_moves().filter(|mv| {
+                mv.is_capture().not()
+                    && mv.kind.is_promotion().not()
+                    && matches!(mv.kind, MoveKind::King(KingMove::Castle { .. })).not()
+            });
+            for mv in quiet_moves {
+                let StepResult::Ongoing(next) = GameState::with_core(core).step(mv) else {
+                    continue;
+                };
+                let unmoves = next.core.unmoves().collect::<Vec<_>>();
+                assert!(unmoves.contains(&mv), "{fen} {mv:?}");
+                assert_eq!(next.core.with_move_undone(mv).board, core.board);
+
+                for unmove in unmoves {
+                    let previous = next.core.with_move_undone(unmove);
+                    assert!(
+                        previous.legal_moves().any(|mv| mv == unmove),
+                        "{fen} {unmove:?}"
+                    );
+                }
+            }
+        }
+
+        // the king and rooks with castling rights stay put, and black's double step is the only
+        // way to get an en passant target
+        let core = GameStateCore::try_from_fen(
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        )
+        .unwrap();
+        let unmoves = core.unmoves().collect::<Vec<_>>();
+        assert_eq!(1, unmoves.len());
+        assert!(unmoves[0].kind.is_pawn_double_step());
+        let corners = GameStateCore::try_from_fen("r3k2r/8/8/8/8/8/8/4K3 w kq - 0 1").unwrap();
+        assert!(corners.unmoves().next().is_none());
+    }
+
+    #[test]
+    fn antichess_captures_are_forced() {
+        let core = GameStateCore::try_from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let mut game = GameState::with_core(core);
+        game.rule_set = RuleSet::Antichess;
+
+        let legal_moves = game.legal_moves().collect::<Vec<_>>();
+        assert_eq!(1, legal_moves.len());
+        assert!(legal_moves[0].is_capture());
+    }
+
+    #[test]
+    fn antichess_losing_all_pieces_wins() {
+        let core = GameStateCore::try_from_fen("8/8/8/p7/8/8/8/R7 w - - 0 1").unwrap();
+        let mut game = GameState::with_core(core);
+        game.rule_set = RuleSet::Antichess;
+
+        let mv = game.legal_moves().next().unwrap();
+        let StepResult::Terminated(GameResult { kind, .. }) = game.step(mv) else {
+            panic!("black has no pieces left and should have won");
+        };
+        assert_eq!(GameResultKind::Loss, kind);
+    }
+
     #[cfg(feature = "rand")]
     #[cfg(feature = "rayon")]
     #[test]
     fn many_random_walks() {
+        use rand::Rng;
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
         use rayon::prelude::*;
         skip_if_no_expensive_test_opt_in!();
 
         let max_depth = 1_000;
         let walk_count = 1_000;
         let game = GameState::new();
+        // a failing walk's seed given here replays it as walk 0
+        let first_seed = std::env::var("SCHACH_SEED").map_or_else(
+            |_| rand::rng().random(),
+            |seed| seed.parse::<u64>().expect("SCHACH_SEED to be a number"),
+        );
 
         (0..walk_count).into_par_iter().panic_fuse().for_each(|i| {
-            match game.clone().random_walk(max_depth, owl_checker_depth_1) {
+            let seed = first_seed.wrapping_add(i);
+            let checker = |game: &GameState<Ongoing>, moves: &[Move]| {
+                let checked = std::panic::catch_unwind(|| owl_checker_depth_1(game));
+                if let Err(panic) = checked {
+                    let moves = moves
+                        .iter()
+                        .map(|mv| std::string::String::from(uci_notation(*mv).as_str()))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    println!("walk {i} failed, replay it with SCHACH_SEED={seed}");
+                    println!("position startpos moves {moves}");
+                    std::panic::resume_unwind(panic);
+                }
+            };
+            match game
+                .clone()
+                .random_walk(max_depth, &mut StdRng::seed_from_u64(seed), checker)
+            {
                 StepResult::Ongoing(GameState { core, .. })
                 | StepResult::Terminated(GameResult {
                     final_game_state: GameState { core, .. },
@@ -388,6 +950,32 @@ mod tests {
         });
     }
 
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_walks_replay_from_their_seed() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let walk = |seed| {
+            let moves = core::cell::RefCell::new(vec![]);
+            let result = GameState::new().random_walk(
+                100,
+                &mut StdRng::seed_from_u64(seed),
+                |_, played: &[Move]| {
+                    moves.replace(played.to_vec());
+                },
+            );
+            let (StepResult::Ongoing(GameState { core, .. })
+            | StepResult::Terminated(GameResult {
+                final_game_state: GameState { core, .. },
+                ..
+            })) = result;
+            (core, moves.into_inner())
+        };
+        assert_eq!(walk(7), walk(7));
+        assert_ne!(walk(7), walk(8));
+    }
+
     #[allow(dead_code)]
     fn owl_checker_move_count(game: &GameState<Ongoing>) {
         let schach_move_count = game.core.legal_moves().count();
@@ -0,0 +1,381 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Not;
+
+use crate::eval::Score;
+use crate::game::GameStateCore;
+use crate::game::RuleSet;
+use crate::mv::Move;
+use crate::piece::PieceKind;
+use crate::player::PlayerKind;
+use crate::search::MAX_PLY;
+use crate::search::see_value;
+use crate::search::static_exchange_evaluation;
+use crate::tt::PackedMove;
+
+/// what the search learned about which quiet moves cause beta cutoffs
+#[derive(Debug, Clone)]
+pub struct Heuristics {
+    /// the last two quiet moves that caused a cutoff at each ply
+    killers: Vec<[Option<Move>; 2]>,
+    /// by mover, origin and destination
+    history: Vec<[[i32; 64]; 64]>,
+    /// the quiet move that refuted the previous move, by the previous moves piece and destination
+    countermoves: Box<[[Option<Move>; 64]; PieceKind::COUNT]>,
+}
+
+impl Heuristics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            killers: vec![[None; 2]; MAX_PLY as usize],
+            history: vec![[[0; 64]; 64]; 2],
+            countermoves: Box::new([[None; 64]; PieceKind::COUNT]),
+        }
+    }
+
+    /// killers only make sense within one search, history is only trusted half as much
+    pub fn new_search(&mut self) {
+        self.killers.fill([None; 2]);
+        for score in self.history.iter_mut().flatten().flatten() {
+            *score /= 2;
+        }
+    }
+
+    #[must_use]
+    pub fn killers(&self, ply: u32) -> [Option<Move>; 2] {
+        self.killers.get(ply as usize).copied().unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn countermove(&self, previous_move: Move) -> Option<Move> {
+        self.countermoves[previous_move.kind.piece_kind().index()]
+            [previous_move.destination.index()]
+    }
+
+    #[must_use]
+    pub const fn history(&self, player: PlayerKind, mv: Move) -> i32 {
+        self.history[player_index(player)][mv.origin.index()][mv.destination.index()]
+    }
+
+    /// captures and promotions are already ordered well without any of this, so they're ignored
+    pub fn record_cutoff(
+        &mut self,
+        player: PlayerKind,
+        mv: Move,
+        ply: u32,
+        depth: u32,
+        previous_move: Option<Move>,
+    ) {
+        if is_tactical(mv) {
+            return;
+        }
+
+        if let Some(killers) = self.killers.get_mut(ply as usize)
+            && killers[0] != Some(mv)
+        {
+            *killers = [Some(mv), killers[0]];
+        }
+
+        let bonus = i32::try_from(depth * depth).unwrap_or(i32::MAX);
+        let score =
+            &mut self.history[player_index(player)][mv.origin.index()][mv.destination.index()];
+        *score = score.saturating_add(bonus);
+
+        if let Some(previous_move) = previous_move {
+            self.countermoves[previous_move.kind.piece_kind().index()]
+                [previous_move.destination.index()] = Some(mv);
+        }
+    }
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const fn player_index(player: PlayerKind) -> usize {
+    match player {
+        PlayerKind::White => 0,
+        PlayerKind::Black => 1,
+    }
+}
+
+const fn is_tactical(mv: Move) -> bool {
+    mv.is_capture() || mv.kind.is_promotion()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Stage {
+    HashMove,
+    GenerateTactical,
+    GoodCaptures,
+    Promotions,
+    Killers,
+    Countermove,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// hands out the legal moves of a position best guess first, only generating each kind of move
+/// once the ones before it are used up. a cutoff on an early move never generates the quiet moves.
+#[derive(Debug)]
+pub struct MovePicker<'a> {
+    core: &'a GameStateCore,
+    rule_set: RuleSet,
+    stage: Stage,
+    hash_move: Option<PackedMove>,
+    ply: u32,
+    previous_move: Option<Move>,
+    /// hash move, killers and countermove, the generated stages skip these
+    picked_early: Vec<Move>,
+    killer_index: usize,
+    // all of these are sorted worst first, so the next one is popped off the end
+    good_captures: Vec<Move>,
+    promotions: Vec<Move>,
+    quiets: Vec<Move>,
+    bad_captures: Vec<Move>,
+}
+
+impl<'a> MovePicker<'a> {
+    /// `previous_move` is the one that led to `core`, for looking up its countermove
+    #[must_use]
+    pub const fn new(
+        core: &'a GameStateCore,
+        rule_set: RuleSet,
+        hash_move: Option<PackedMove>,
+        ply: u32,
+        previous_move: Option<Move>,
+    ) -> Self {
+        Self {
+            core,
+            rule_set,
+            stage: Stage::HashMove,
+            hash_move,
+            ply,
+            previous_move,
+            picked_early: vec![],
+            killer_index: 0,
+            good_captures: vec![],
+            promotions: vec![],
+            quiets: vec![],
+            bad_captures: vec![],
+        }
+    }
+
+    pub fn next(&mut self, heuristics: &Heuristics) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateTactical;
+                    let hash_move = self
+                        .hash_move
+                        .and_then(|packed| packed.unpack(self.core, self.rule_set));
+                    if let Some(mv) = hash_move
+                        && self.pick_early(mv)
+                    {
+                        return Some(mv);
+                    }
+                }
+                Stage::GenerateTactical => {
+                    self.generate_tactical();
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => match self.good_captures.pop() {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::Promotions,
+                },
+                Stage::Promotions => match self.promotions.pop() {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::Killers,
+                },
+                Stage::Killers => {
+                    let killers = heuristics.killers(self.ply);
+                    let Some(killer) = killers.get(self.killer_index) else {
+                        self.stage = Stage::Countermove;
+                        continue;
+                    };
+                    self.killer_index += 1;
+                    if let Some(mv) = *killer
+                        && self.pick_early(mv)
+                    {
+                        return Some(mv);
+                    }
+                }
+                Stage::Countermove => {
+                    self.stage = Stage::GenerateQuiets;
+                    let countermove = self
+                        .previous_move
+                        .and_then(|previous_move| heuristics.countermove(previous_move));
+                    if let Some(mv) = countermove
+                        && self.pick_early(mv)
+                    {
+                        return Some(mv);
+                    }
+                }
+                Stage::GenerateQuiets => {
+                    self.generate_quiets(heuristics);
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => match self.quiets.pop() {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::BadCaptures,
+                },
+                Stage::BadCaptures => match self.bad_captures.pop() {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    /// remembered moves come from other positions, so they have to be checked before use
+    fn pick_early(&mut self, mv: Move) -> bool {
+        if self.picked_early.contains(&mv) || self.core.is_legal_for(mv, self.rule_set).not() {
+            return false;
+        }
+        self.picked_early.push(mv);
+        true
+    }
+
+    fn generate_tactical(&mut self) {
+        for mv in self.core.tactical_moves_for(self.rule_set) {
+            if self.picked_early.contains(&mv) {
+                continue;
+            }
+            if mv.is_capture().not() {
+                self.promotions.push(mv);
+            } else if static_exchange_evaluation(&self.core.board, mv) >= Score::ZERO {
+                self.good_captures.push(mv);
+            } else {
+                self.bad_captures.push(mv);
+            }
+        }
+
+        // most valuable victim first, least valuable attacker to break ties
+        let board = &self.core.board;
+        self.good_captures.sort_by_cached_key(|mv| {
+            let victim = board[mv.destination].map_or(PieceKind::Pawn, |piece| piece.kind);
+            (see_value(victim), -see_value(mv.kind.piece_kind()))
+        });
+        self.promotions.sort_by_cached_key(|mv| {
+            let promoted_to = mv.kind.promotion_replacement().map(|piece| piece.kind);
+            promoted_to.map_or(0, see_value)
+        });
+        self.bad_captures
+            .sort_by_cached_key(|mv| static_exchange_evaluation(board, *mv));
+    }
+
+    fn generate_quiets(&mut self, heuristics: &Heuristics) {
+        let player = self.core.active_player;
+        self.quiets = self
+            .core
+            .quiet_moves_for(self.rule_set)
+            .filter(|mv| self.picked_early.contains(mv).not())
+            .collect();
+        self.quiets
+            .sort_by_cached_key(|mv| heuristics.history(player, *mv));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::Square;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn pick_all(picker: &mut MovePicker, heuristics: &Heuristics) -> Vec<Move> {
+        let mut moves = vec![];
+        while let Some(mv) = picker.next(heuristics) {
+            moves.push(mv);
+        }
+        moves
+    }
+
+    fn find(core: &GameStateCore, origin: Square, destination: Square) -> Move {
+        core.legal_moves()
+            .find(|mv| mv.origin == origin && mv.destination == destination)
+            .unwrap()
+    }
+
+    #[test]
+    fn picks_every_legal_move_once() {
+        let core = GameStateCore::try_from_fen(KIWIPETE).unwrap();
+        let quiet = find(&core, Square::A2, Square::A3);
+        let mut heuristics = Heuristics::new();
+        // the second killer is also the hash move, and the first one isn't legal here at all
+        heuristics.record_cutoff(core.active_player, quiet, 2, 3, None);
+        let foreign = Move {
+            origin: Square::H1,
+            destination: Square::H8,
+            ..quiet
+        };
+        heuristics.record_cutoff(core.active_player, foreign, 2, 3, None);
+
+        let mut picker = MovePicker::new(
+            &core,
+            RuleSet::Standard,
+            Some(PackedMove::new(quiet)),
+            2,
+            None,
+        );
+        let mut moves = pick_all(&mut picker, &heuristics)
+            .into_iter()
+            .map(|mv| std::format!("{mv:?}"))
+            .collect::<Vec<_>>();
+        let mut legal = core
+            .legal_moves()
+            .map(|mv| std::format!("{mv:?}"))
+            .collect::<Vec<_>>();
+        moves.sort();
+        legal.sort();
+
+        assert_eq!(legal, moves);
+    }
+
+    #[test]
+    fn order() {
+        let core = GameStateCore::try_from_fen(KIWIPETE).unwrap();
+        let hash_move = find(&core, Square::E2, Square::A6); // bishop takes bishop
+        let killer = find(&core, Square::G2, Square::G3);
+        let mut heuristics = Heuristics::new();
+        heuristics.record_cutoff(core.active_player, killer, 0, 1, None);
+
+        let mut picker = MovePicker::new(
+            &core,
+            RuleSet::Standard,
+            Some(PackedMove::new(hash_move)),
+            0,
+            None,
+        );
+        let moves = pick_all(&mut picker, &heuristics);
+        let position = |mv: Move| moves.iter().position(|picked| *picked == mv).unwrap();
+
+        assert_eq!(hash_move, moves[0]);
+        let first_quiet = moves.iter().position(|mv| is_tactical(*mv).not()).unwrap();
+        assert_eq!(killer, moves[first_quiet]);
+        // the rook on h8 takes back either way, but a pawn is worth giving up for it
+        assert!(position(find(&core, Square::G2, Square::H3)) < first_quiet);
+        assert!(
+            position(find(&core, Square::F3, Square::H3))
+                > position(find(&core, Square::A2, Square::A3))
+        );
+    }
+
+    #[test]
+    fn cutoff_on_a_capture_never_generates_quiets() {
+        let core = GameStateCore::try_from_fen(KIWIPETE).unwrap();
+        let heuristics = Heuristics::new();
+        let mut picker = MovePicker::new(&core, RuleSet::Standard, None, 0, None);
+
+        assert!(picker.next(&heuristics).unwrap().is_capture());
+        assert!(picker.quiets.is_empty());
+        assert!(matches!(picker.stage, Stage::GoodCaptures));
+    }
+}
@@ -1,16 +1,21 @@
+use crate::board::COL_COUNT;
+use crate::board::ROW_COUNT;
 use crate::coord::Square;
+use crate::coord::SquareOf;
 use crate::game::CastlingSide;
 use crate::piece::Piece;
 use crate::piece::PieceKind;
 
+pub(crate) type Threat = ThreatOf<COL_COUNT, ROW_COUNT>;
+
 #[derive(Clone, Copy)]
-pub(crate) struct Threat {
+pub(crate) struct ThreatOf<const COLS: usize, const ROWS: usize> {
     pub(crate) piece: Piece,
-    pub(crate) origin: Square,
-    pub(crate) destination: Square,
+    pub(crate) origin: SquareOf<COLS, ROWS>,
+    pub(crate) destination: SquareOf<COLS, ROWS>,
 }
 
-impl core::fmt::Debug for Threat {
+impl<const COLS: usize, const ROWS: usize> core::fmt::Debug for ThreatOf<COLS, ROWS> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
@@ -29,6 +34,11 @@ pub enum MoveKind {
     Rook { is_capture: bool },
     Queen { is_capture: bool },
     King(KingMove),
+    Archbishop { is_capture: bool },
+    Chancellor { is_capture: bool },
+    Amazon { is_capture: bool },
+    Camel { is_capture: bool },
+    Zebra { is_capture: bool },
 }
 
 impl MoveKind {
@@ -41,6 +51,11 @@ impl MoveKind {
             | Self::Rook { .. } => PieceKind::Rook,
             | Self::Queen { .. } => PieceKind::Queen,
             | Self::King(_) => PieceKind::King,
+            | Self::Archbishop { .. } => PieceKind::Archbishop,
+            | Self::Chancellor { .. } => PieceKind::Chancellor,
+            | Self::Amazon { .. } => PieceKind::Amazon,
+            | Self::Camel { .. } => PieceKind::Camel,
+            | Self::Zebra { .. } => PieceKind::Zebra,
         }
     }
 
@@ -56,16 +71,22 @@ impl MoveKind {
 
     #[must_use]
     pub const fn is_promotion(&self) -> bool {
-        matches!(
-            self,
+        self.promotion_replacement().is_some()
+    }
+
+    #[must_use]
+    pub const fn promotion_replacement(&self) -> Option<Piece> {
+        match self {
             Self::Pawn(
                 PawnMove::SingleStep {
-                    promotion_replacement: Some(_)
-                } | PawnMove::Capture {
-                    promotion_replacement: Some(_)
+                    promotion_replacement,
                 }
-            )
-        )
+                | PawnMove::Capture {
+                    promotion_replacement,
+                },
+            ) => *promotion_replacement,
+            _ => None,
+        }
     }
 }
 
@@ -83,7 +104,12 @@ impl Move {
             | MoveKind::Bishop { is_capture, .. }
             | MoveKind::Rook { is_capture, .. }
             | MoveKind::Queen { is_capture, .. }
-            | MoveKind::King(KingMove::Normal { is_capture, .. }) => is_capture,
+            | MoveKind::King(KingMove::Normal { is_capture, .. })
+            | MoveKind::Archbishop { is_capture, .. }
+            | MoveKind::Chancellor { is_capture, .. }
+            | MoveKind::Amazon { is_capture, .. }
+            | MoveKind::Camel { is_capture, .. }
+            | MoveKind::Zebra { is_capture, .. } => is_capture,
             | MoveKind::Pawn(PawnMove::Capture { .. } | PawnMove::EnPassant { .. }) => true,
             | MoveKind::Pawn(PawnMove::SingleStep { .. } | PawnMove::DoubleStep)
             | MoveKind::King(KingMove::Castle { .. }) => false,
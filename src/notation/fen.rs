@@ -5,11 +5,15 @@ use alloc::vec::Vec;
 use core::ascii::Char as AsciiChar;
 
 use crate::board::Board;
+use crate::board::BoardOf;
 use crate::coord::Col;
 use crate::coord::ColIndexOutOfRange;
+use crate::coord::ColOf;
 use crate::coord::Row;
 use crate::coord::RowIndexOutOfRange;
+use crate::coord::RowOf;
 use crate::coord::Square;
+use crate::coord::SquareOf;
 use crate::coord::SquareOutOfRange;
 use crate::game::CastlingRights;
 use crate::game::FiftyMoveRuleClock;
@@ -101,7 +105,7 @@ impl GameStateCore {
         } = self;
 
         let fen = FenStrings {
-            piece_placements: Board::to_fen_repr(board),
+            piece_placements: Board::to_fen_repr(*board),
             active_player: vec![PlayerKind::to_fen_repr(*active_player)],
             castling_availability: CastlingRights::to_fen_repr(*castling_rights),
             en_passant_target_square: Square::option_to_fen_repr(*en_passant_target),
@@ -203,6 +207,11 @@ impl PieceKind {
             Self::Rook => AsciiChar::SmallR,
             Self::Queen => AsciiChar::SmallQ,
             Self::King => AsciiChar::SmallK,
+            Self::Archbishop => AsciiChar::SmallA,
+            Self::Chancellor => AsciiChar::SmallC,
+            Self::Amazon => AsciiChar::SmallM,
+            Self::Camel => AsciiChar::SmallL,
+            Self::Zebra => AsciiChar::SmallZ,
         }
     }
 
@@ -215,6 +224,11 @@ impl PieceKind {
             Self::Rook => AsciiChar::CapitalR,
             Self::Queen => AsciiChar::CapitalQ,
             Self::King => AsciiChar::CapitalK,
+            Self::Archbishop => AsciiChar::CapitalA,
+            Self::Chancellor => AsciiChar::CapitalC,
+            Self::Amazon => AsciiChar::CapitalM,
+            Self::Camel => AsciiChar::CapitalL,
+            Self::Zebra => AsciiChar::CapitalZ,
         }
     }
 }
@@ -238,6 +252,18 @@ impl Piece {
             b'q' => Ok(Self::BLACK_QUEEN),
             b'k' => Ok(Self::BLACK_KING),
 
+            b'A' => Ok(Self::WHITE_ARCHBISHOP),
+            b'C' => Ok(Self::WHITE_CHANCELLOR),
+            b'M' => Ok(Self::WHITE_AMAZON),
+            b'L' => Ok(Self::WHITE_CAMEL),
+            b'Z' => Ok(Self::WHITE_ZEBRA),
+
+            b'a' => Ok(Self::BLACK_ARCHBISHOP),
+            b'c' => Ok(Self::BLACK_CHANCELLOR),
+            b'm' => Ok(Self::BLACK_AMAZON),
+            b'l' => Ok(Self::BLACK_CAMEL),
+            b'z' => Ok(Self::BLACK_ZEBRA),
+
             _ => Err(MalformedPieceError),
         }
     }
@@ -285,61 +311,61 @@ pub enum BoardFromFenError {
     IllegalColDimensions,
 }
 
-impl Board {
-    fn try_from_fen_repr(value: &[AsciiChar]) -> Result<Self, BoardFromFenError> {
-        fn fen_row_to_board_row(
-            row: &[AsciiChar],
-        ) -> Result<[Option<Piece>; 8], BoardFromFenError> {
-            let mut out_row: Vec<Option<Piece>> = vec![];
-
-            for c in row {
-                match *c as u8 {
-                    b'1'..=b'8' => out_row.extend(vec![None; usize::from(u8::from(*c) - b'0')]),
-                    b'P' | b'N' | b'B' | b'R' | b'Q' | b'K' | b'p' | b'n' | b'b' | b'r' | b'q'
-                    | b'k' => {
-                        out_row.push(Some(
-                            Piece::try_from_fen_repr(*c)
-                                .expect("these chars to be valid Piece reprs"),
-                        ));
-                    }
+impl<const COLS: usize, const ROWS: usize> BoardOf<COLS, ROWS> {
+    pub fn try_from_fen_repr(value: &[AsciiChar]) -> Result<Self, BoardFromFenError> {
+        let fen_rows = value
+            .split(|c| *c == AsciiChar::Solidus)
+            .collect::<Vec<_>>();
+        if fen_rows.len() != ROWS {
+            return Err(BoardFromFenError::IllegalColDimensions);
+        }
 
-                    _ => return Err(BoardFromFenError::IllegalCharacter(*c)),
+        let mut board = Self::empty();
+
+        // fen lists the rows top to bottom
+        for (fen_row, row) in fen_rows
+            .into_iter()
+            .zip(RowOf::<ROWS>::ALL.into_iter().rev())
+        {
+            let mut cols = ColOf::<COLS>::ALL.into_iter();
+            let mut skip_count: usize = 0; // wide boards can have multi-digit counts, like `10`
+
+            for c in fen_row {
+                if c.to_u8().is_ascii_digit() {
+                    skip_count = skip_count
+                        .checked_mul(10)
+                        .and_then(|count| count.checked_add(usize::from(c.to_u8() - b'0')))
+                        .ok_or(BoardFromFenError::IllegalRowDimensions)?;
+                    continue;
                 }
-            }
 
-            out_row
-                .try_into()
-                .map_err(|_| BoardFromFenError::IllegalRowDimensions)
-        }
+                for _ in 0..core::mem::take(&mut skip_count) {
+                    cols.next().ok_or(BoardFromFenError::IllegalRowDimensions)?;
+                }
 
-        let piece_placements_chunked: Self = value
-            .split(|c| *c == AsciiChar::Solidus)
-            .map(fen_row_to_board_row)
-            .collect::<Result<Vec<[Option<Piece>; 8]>, BoardFromFenError>>()?
-            .try_into()
-            .map(Board)
-            .map_err(|_| BoardFromFenError::IllegalColDimensions)?;
+                let piece = Piece::try_from_fen_repr(*c)
+                    .map_err(|_| BoardFromFenError::IllegalCharacter(*c))?;
+                let col = cols.next().ok_or(BoardFromFenError::IllegalRowDimensions)?;
+                board[SquareOf::new(col, row)] = Some(piece);
+            }
 
-        let mut new_board = Self::empty();
+            for _ in 0..skip_count {
+                cols.next().ok_or(BoardFromFenError::IllegalRowDimensions)?;
+            }
 
-        //TODO: un-fuck this
-        for square in Square::ALL {
-            new_board[Square {
-                col: Col::try_from(u8::from(square.row))
-                    .expect("valid row numbers to be valid col numbers"),
-                row: Row::try_from(9 - u8::from(square.col))
-                    .expect("9 - 1..=8 to be in range 1..=8"),
-            }] = piece_placements_chunked[square];
+            if cols.next().is_some() {
+                return Err(BoardFromFenError::IllegalRowDimensions);
+            }
         }
 
-        Ok(new_board)
+        Ok(board)
     }
 
     #[must_use]
-    fn to_fen_repr(&self) -> Vec<AsciiChar> {
+    pub fn to_fen_repr(self) -> Vec<AsciiChar> {
         let mut running_square_count: u32 = 0;
         let mut out: Vec<AsciiChar> = vec![];
-        for square in Square::ALL {
+        for square in SquareOf::<COLS, ROWS>::all() {
             match self[square] {
                 None => running_square_count += 1,
                 Some(piece) => {
@@ -350,13 +376,13 @@ impl Board {
                                 .as_ascii()
                                 .expect("digits to be ascii")
                                 .to_owned(),
-                        ); //as the running count should never exceed 8, this should always be a single digit
+                        ); // on boards wider than 9 cols this can be more than one digit
                     }
                     running_square_count = 0;
                     out.push(Piece::to_fen_repr(piece));
                 }
             }
-            if square.col == Col::_8 {
+            if square.col == ColOf::LAST {
                 if running_square_count != 0 {
                     out.extend(
                         running_square_count
@@ -364,10 +390,10 @@ impl Board {
                             .as_ascii()
                             .expect("digits to be ascii")
                             .to_owned(),
-                    ); //as the running count should never exceed 8, this should always be a single digit
+                    ); // on boards wider than 9 cols this can be more than one digit
                 }
                 running_square_count = 0;
-                if square != Square::H1 {
+                if square.row != RowOf::FIRST {
                     out.push(AsciiChar::Solidus);
                 }
             }
@@ -423,7 +449,7 @@ impl Square {
     }
 
     #[must_use]
-    pub(super) const fn to_fen_repr(self) -> [AsciiChar; 2] {
+    pub(crate) const fn to_fen_repr(self) -> [AsciiChar; 2] {
         [Col::to_fen_repr(self.col), Row::to_fen_repr(self.row)]
     }
 
@@ -472,7 +498,7 @@ mod tests {
 
     #[test]
     fn test_pieces_fen_round_trip() {
-        for piece in Piece::ALL {
+        for piece in Piece::ALL.into_iter().chain(Piece::FAIRY) {
             println!("{piece}: {}", Piece::to_fen_repr(piece).as_str());
             assert_eq!(
                 piece,
@@ -514,6 +540,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_wide_board_fen_round_trip() {
+        let capablanca_like = "rnbqkbnr2/pppppppppp/10/10/10/10/PPPPPPPPPP/RNBQKBNR2";
+        let board =
+            BoardOf::<10, 8>::try_from_fen_repr(capablanca_like.as_ascii().unwrap()).unwrap();
+        println!("{board:?}");
+        assert_eq!(capablanca_like, board.to_fen_repr().as_str());
+    }
+
+    #[test]
+    fn test_small_board_fen_round_trip() {
+        let mini = "rnbqk/ppppp/5/5/PPPPP/RNBQK";
+        let board = BoardOf::<5, 6>::try_from_fen_repr(mini.as_ascii().unwrap()).unwrap();
+        println!("{board:?}");
+        assert_eq!(mini, board.to_fen_repr().as_str());
+    }
+
+    #[test]
+    fn test_board_fen_overlong_skip_count() {
+        let overlong = "99999999999999999999999/8/8/8/8/8/8/8".as_ascii().unwrap();
+        assert!(matches!(
+            BoardOf::<10, 8>::try_from_fen_repr(overlong),
+            Err(BoardFromFenError::IllegalRowDimensions)
+        ));
+    }
+
+    #[test]
+    fn test_board_fen_wrong_dimensions() {
+        let standard = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR"
+            .as_ascii()
+            .unwrap();
+        assert!(matches!(
+            BoardOf::<10, 8>::try_from_fen_repr(standard),
+            Err(BoardFromFenError::IllegalRowDimensions)
+        ));
+        assert!(matches!(
+            BoardOf::<8, 10>::try_from_fen_repr(standard),
+            Err(BoardFromFenError::IllegalColDimensions)
+        ));
+    }
+
     #[test]
     fn test_initial_game_state() {
         let starting_position_fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
@@ -0,0 +1,437 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Not;
+
+use crate::game::GameState;
+use crate::game::GameStateCore;
+use crate::game::Ongoing;
+use crate::game::StepResult;
+use crate::mv::Move;
+use crate::notation::fen::GameFromFenError;
+use crate::notation::san::SanMoveError;
+use crate::notation::san::parse_san_move;
+use crate::player::PlayerKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PgnResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    /// `*`, the game was still going or nobody knows how it ended
+    Unknown,
+}
+
+impl PgnResult {
+    #[must_use]
+    pub const fn from_pgn(text: &str) -> Option<Self> {
+        match text {
+            "1-0" => Some(Self::WhiteWins),
+            "0-1" => Some(Self::BlackWins),
+            "1/2-1/2" => Some(Self::Draw),
+            "*" => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn winner(self) -> Option<PlayerKind> {
+        match self {
+            Self::WhiteWins => Some(PlayerKind::White),
+            Self::BlackWins => Some(PlayerKind::Black),
+            Self::Draw | Self::Unknown => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    /// the tag pairs in the order they were given, like `("White", "Tal, Mikhail")`
+    pub tags: Vec<(String, String)>,
+    /// the standard start, unless the `FEN` tag says otherwise
+    pub start: GameState<Ongoing>,
+    /// just the main line, variations are skipped
+    pub moves: Vec<Move>,
+    pub result: PgnResult,
+}
+
+impl PgnGame {
+    #[must_use]
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    UnterminatedComment,
+    MalformedTag,
+    MalformedFen(GameFromFenError),
+    Move {
+        /// counted from 0 at the start of the game
+        ply: usize,
+        text: String,
+        error: SanMoveError,
+    },
+    /// the game was already over, by mate or a draw rule, before this ply
+    MoveAfterTheEnd {
+        ply: usize,
+    },
+}
+
+/// every game in `text`, an export of any number of games. one broken game doesn't keep the
+/// others from being read, except for an unterminated comment, which swallows the rest.
+#[must_use]
+pub fn parse_pgn(text: &str) -> Vec<Result<PgnGame, PgnError>> {
+    let mut games = Vec::new();
+    let mut game = RawGame::default();
+    let mut variation_depth = 0_usize;
+
+    for token in Lexer::new(text) {
+        match token {
+            Err(error) => {
+                game.error.get_or_insert(error);
+            }
+            Ok(Token::Tag(name, value)) => {
+                // a game without a result is over once the next one's tags start
+                if game.moves.is_empty().not() {
+                    games.push(core::mem::take(&mut game).finish(None));
+                }
+                game.tags.push((String::from(name), value));
+            }
+            Ok(Token::VariationStart) => variation_depth += 1,
+            Ok(Token::VariationEnd) => variation_depth = variation_depth.saturating_sub(1),
+            Ok(Token::Symbol(_)) if variation_depth > 0 => {}
+            Ok(Token::Symbol(symbol)) => game.moves.push(symbol),
+            Ok(Token::Result(result)) => {
+                if variation_depth == 0 {
+                    games.push(core::mem::take(&mut game).finish(Some(result)));
+                }
+            }
+        }
+    }
+
+    if game.tags.is_empty().not() || game.moves.is_empty().not() || game.error.is_some() {
+        games.push(game.finish(None));
+    }
+    games
+}
+
+#[derive(Debug, Default)]
+struct RawGame<'a> {
+    tags: Vec<(String, String)>,
+    moves: Vec<&'a str>,
+    error: Option<PgnError>,
+}
+
+impl RawGame<'_> {
+    fn finish(self, result: Option<PgnResult>) -> Result<PgnGame, PgnError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let tag = |name| {
+            self.tags
+                .iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let start = match tag("FEN") {
+            Some(fen) => GameState::with_core(
+                GameStateCore::try_from_fen(fen).map_err(PgnError::MalformedFen)?,
+            ),
+            None => GameState::new(),
+        };
+        let result = result
+            .or_else(|| tag("Result").and_then(PgnResult::from_pgn))
+            .unwrap_or(PgnResult::Unknown);
+
+        let mut game = Some(start.clone());
+        let mut moves = Vec::with_capacity(self.moves.len());
+        for (ply, text) in self.moves.into_iter().enumerate() {
+            let Some(current) = game.take() else {
+                return Err(PgnError::MoveAfterTheEnd { ply });
+            };
+            let mv = parse_san_move(&current, text).map_err(|error| PgnError::Move {
+                ply,
+                text: String::from(text),
+                error,
+            })?;
+            moves.push(mv);
+            if let StepResult::Ongoing(next) = current.step(mv) {
+                game = Some(next);
+            }
+        }
+
+        Ok(PgnGame {
+            tags: self.tags,
+            start,
+            moves,
+            result,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Tag(&'a str, String),
+    /// a move, with any move number in front already cut off
+    Symbol(&'a str),
+    Result(PgnResult),
+    VariationStart,
+    VariationEnd,
+}
+
+struct Lexer<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Lexer<'a> {
+    const fn new(text: &'a str) -> Self {
+        Self { text, position: 0 }
+    }
+
+    const fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    fn skip_while(&mut self, predicate: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.position;
+        while self.peek().is_some_and(&predicate) {
+            self.position += 1;
+        }
+        &self.text[start..self.position]
+    }
+
+    fn skip_line(&mut self) {
+        self.skip_while(|byte| byte != b'\n');
+    }
+
+    /// `[Name "value"]`, after the `[`
+    fn tag(&mut self) -> Option<Token<'a>> {
+        self.skip_while(|byte| byte.is_ascii_whitespace());
+        let name = self.skip_while(|byte| byte.is_ascii_alphanumeric() || byte == b'_');
+        self.skip_while(|byte| byte.is_ascii_whitespace());
+        if name.is_empty() || self.peek() != Some(b'"') {
+            return None;
+        }
+        self.position += 1;
+
+        let mut value = String::new();
+        loop {
+            let part = self.skip_while(|byte| byte != b'"' && byte != b'\\' && byte != b'\n');
+            value.push_str(part);
+            match self.peek()? {
+                b'\\' => {
+                    self.position += 1;
+                    let escaped = self.text[self.position..].chars().next()?;
+                    value.push(escaped);
+                    self.position += escaped.len_utf8();
+                }
+                b'"' => break,
+                _ => return None,
+            }
+        }
+        self.position += 1;
+
+        self.skip_while(|byte| byte.is_ascii_whitespace());
+        (self.peek()? == b']').then(|| {
+            self.position += 1;
+            Token::Tag(name, value)
+        })
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.skip_while(|byte| byte.is_ascii_whitespace());
+            let at_line_start =
+                self.position == 0 || self.text.as_bytes()[self.position - 1] == b'\n';
+            match self.peek()? {
+                b'%' if at_line_start => self.skip_line(),
+                b';' => self.skip_line(),
+                b'{' => {
+                    let Some(end) = self.text[self.position..].find('}') else {
+                        self.position = self.text.len();
+                        return Some(Err(PgnError::UnterminatedComment));
+                    };
+                    self.position += end + 1;
+                }
+                b'$' => {
+                    self.position += 1;
+                    self.skip_while(|byte| byte.is_ascii_digit());
+                }
+                b'[' => {
+                    self.position += 1;
+                    let tag = self.tag();
+                    if tag.is_none() {
+                        self.skip_while(|byte| byte != b']' && byte != b'\n');
+                        self.position = (self.position + 1).min(self.text.len());
+                    }
+                    return Some(tag.ok_or(PgnError::MalformedTag));
+                }
+                b'(' => {
+                    self.position += 1;
+                    return Some(Ok(Token::VariationStart));
+                }
+                b')' => {
+                    self.position += 1;
+                    return Some(Ok(Token::VariationEnd));
+                }
+                _ => {
+                    let symbol = self.skip_while(|byte| {
+                        byte.is_ascii_whitespace().not() && b"{}()[];$\"".contains(&byte).not()
+                    });
+                    if symbol.is_empty() {
+                        // a stray `]` or `"`, nothing anyone would write on purpose
+                        self.position += 1;
+                        continue;
+                    }
+                    if let Some(result) = PgnResult::from_pgn(symbol) {
+                        return Some(Ok(Token::Result(result)));
+                    }
+                    // `12.`, `12...` and `12.e4`, but not the `0-0` of castling
+                    let digits = symbol.trim_start_matches(|char: char| char.is_ascii_digit());
+                    let symbol = if digits.starts_with('.') {
+                        digits.trim_start_matches('.')
+                    } else {
+                        symbol
+                    };
+                    if symbol.is_empty() || symbol == "e.p." {
+                        continue;
+                    }
+                    return Some(Ok(Token::Symbol(symbol)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::san::standard_algebraic_notation;
+
+    const GAMES: &str = r#"
+[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.} 3... a6
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+
+[Event "Casual"]
+[SetUp "1"]
+[FEN "k7/8/2K5/8/8/8/8/1R6 w - - 0 1"]
+
+1. Kc7 $1 (1. Rb7 $4 {stalemate} Kxb7) 1... Ka7 ; forced
+2. Ra1# 1-0
+
+[Event "Broken"]
+
+1. e4 e5 2. Ke3 *
+
+[Event "Unfinished"]
+% an escaped line
+1.d4 d5 2.c4
+"#;
+
+    #[test]
+    fn reads_every_game() {
+        let games = parse_pgn(GAMES);
+        assert_eq!(4, games.len());
+
+        let fischer = games[0].as_ref().unwrap();
+        assert_eq!(Some("Fischer, Robert J."), fischer.tag("White"));
+        assert_eq!(85, fischer.moves.len());
+        assert_eq!(PgnResult::Draw, fischer.result);
+
+        let mate = games[1].as_ref().unwrap();
+        assert_eq!(3, mate.moves.len());
+        assert_eq!(PgnResult::WhiteWins, mate.result);
+        assert_eq!(Some(PlayerKind::White), mate.result.winner());
+
+        assert!(matches!(
+            games[2],
+            Err(PgnError::Move {
+                ply: 2,
+                error: SanMoveError::Illegal,
+                ..
+            })
+        ));
+
+        let unfinished = games[3].as_ref().unwrap();
+        assert_eq!(3, unfinished.moves.len());
+        assert_eq!(PgnResult::Unknown, unfinished.result);
+    }
+
+    #[test]
+    fn variations_are_skipped() {
+        let games = parse_pgn(GAMES);
+        let mate = games[1].as_ref().unwrap();
+        let sans = mate
+            .moves
+            .iter()
+            .scan(mate.start.clone(), |game, mv| {
+                let san = standard_algebraic_notation(game.clone(), *mv);
+                if let StepResult::Ongoing(next) = game.clone().step(*mv) {
+                    *game = next;
+                }
+                Some(String::from(san.as_str()))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(["Kc7", "Ka7", "Ra1#"], sans.as_slice());
+    }
+
+    #[test]
+    fn escaped_tag_values() {
+        let games = parse_pgn(r#"[Annotator "a \"quoted\" \\ name"] *"#);
+        let game = games[0].as_ref().unwrap();
+        assert_eq!(Some(r#"a "quoted" \ name"#), game.tag("Annotator"));
+        assert!(game.moves.is_empty());
+
+        let games = parse_pgn("[A \"\\é\"] *");
+        assert_eq!(Some("é"), games[0].as_ref().unwrap().tag("A"));
+    }
+
+    #[test]
+    fn moves_after_mate() {
+        let games = parse_pgn("[FEN \"k7/8/2K5/8/8/8/8/1R6 w - - 0 1\"] 1. Kc7 Ka7 2. Ra1 Kb8 *");
+        assert!(matches!(
+            games[0],
+            Err(PgnError::MoveAfterTheEnd { ply: 3 })
+        ));
+    }
+
+    #[test]
+    fn broken_games() {
+        assert!(matches!(
+            parse_pgn("[Event \"x\" 1. e4 *")[0],
+            Err(PgnError::MalformedTag)
+        ));
+        assert!(matches!(
+            parse_pgn("1. e4 {never closed 1-0")[0],
+            Err(PgnError::UnterminatedComment)
+        ));
+        assert!(matches!(
+            parse_pgn("[FEN \"8/8 w\"] *")[0],
+            Err(PgnError::MalformedFen(_))
+        ));
+        assert!(parse_pgn("  \n").is_empty());
+    }
+}
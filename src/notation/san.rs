@@ -13,6 +13,8 @@ use crate::mv::KingMove;
 use crate::mv::Move;
 use crate::mv::MoveKind;
 use crate::mv::PawnMove;
+use crate::notation::uci::square;
+use crate::piece::Piece;
 use crate::piece::PieceKind;
 
 const O_O: [AsciiChar; 3] = [
@@ -64,7 +66,12 @@ fn notation_creator(
         | MoveKind::Bishop { .. }
         | MoveKind::Rook { .. }
         | MoveKind::Queen { .. }
-        | MoveKind::King(KingMove::Normal { .. }) => {
+        | MoveKind::King(KingMove::Normal { .. })
+        | MoveKind::Archbishop { .. }
+        | MoveKind::Chancellor { .. }
+        | MoveKind::Amazon { .. }
+        | MoveKind::Camel { .. }
+        | MoveKind::Zebra { .. } => {
             let piece_repr = (matches!(mv.kind, MoveKind::Pawn(_))).not().then_some([mv
                 .kind
                 .piece_kind()
@@ -115,7 +122,9 @@ fn notation_creator(
     let mut append = vec![];
     match outcome {
         | StepResult::Ongoing(future) => {
-            if future.core.board.is_king_checked(future.core.active_player) {
+            if future.rule_set.has_royal_king()
+                && future.core.board.is_king_checked(future.core.active_player)
+            {
                 append.push(AsciiChar::PlusSign);
             }
         }
@@ -126,7 +135,7 @@ fn notation_creator(
             append.push(AsciiChar::NumberSign);
         }
         | StepResult::Terminated(GameResult {
-            kind: GameResultKind::Draw(_),
+            kind: GameResultKind::Draw(_) | GameResultKind::Loss,
             ..
         }) => { /* TODO: nothing yet, this isn't Ascii :[ 1/2 / 1/2 or smt */ }
     }
@@ -153,7 +162,7 @@ pub fn standard_algebraic_notation(game: GameState<Ongoing>, mov: Move) -> Vec<A
         capture: Some(AsciiChar::SmallX),
         no_capture: None,
     };
-    let mut legal_moves = game.core.legal_moves().collect::<Vec<_>>();
+    let mut legal_moves = game.legal_moves().collect::<Vec<_>>();
 
     let mov_index = legal_moves
         .iter()
@@ -204,11 +213,136 @@ pub fn standard_algebraic_notation(game: GameState<Ongoing>, mov: Move) -> Vec<A
 
     notation_creator(game, mov, OriginAmbiguationLevel::Full, capture_repr)
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanMoveError {
+    Malformed,
+    Illegal,
+    /// more than one legal move fits what little the notation says about its origin
+    Ambiguous,
+}
+
+/// the legal move in `game` that `text` is the [`standard_algebraic_notation`] of.
+///
+/// lenient like most readers of hand written games: check marks, annotations like `!?`,
+/// a missing `x` or `=` and more origin than needed are all fine, and so is `0-0`.
+pub fn parse_san_move(game: &GameState<Ongoing>, text: &str) -> Result<Move, SanMoveError> {
+    let text = text.trim_end_matches(['+', '#', '!', '?']);
+
+    let castling_side = match text {
+        "O-O" | "0-0" => Some(CastlingSide::Kingside),
+        "O-O-O" | "0-0-0" => Some(CastlingSide::Queenside),
+        _ => None,
+    };
+    if let Some(side) = castling_side {
+        return game
+            .legal_moves()
+            .find(|mv| {
+                matches!(mv.kind, MoveKind::King(KingMove::Castle { castling_side, .. })
+                    if castling_side == side)
+            })
+            .ok_or(SanMoveError::Illegal);
+    }
+
+    let mut bytes = text
+        .bytes()
+        .filter(|byte| matches!(byte, b'x' | b'=' | b':').not())
+        .collect::<Vec<_>>();
+
+    let piece_kind = |byte: u8| {
+        AsciiChar::from_u8(byte)
+            .and_then(|char| Piece::try_from_fen_repr(char).ok())
+            .map(|piece| piece.kind)
+    };
+    let promotion = match bytes.last() {
+        Some(&byte) if byte.is_ascii_uppercase() => {
+            bytes.pop();
+            Some(piece_kind(byte).ok_or(SanMoveError::Malformed)?)
+        }
+        _ => None,
+    };
+    let kind = match bytes.first() {
+        Some(&byte) if byte.is_ascii_uppercase() => {
+            bytes.remove(0);
+            piece_kind(byte).ok_or(SanMoveError::Malformed)?
+        }
+        _ => PieceKind::Pawn,
+    };
+
+    let [origin @ .., destination_col, destination_row] = bytes.as_slice() else {
+        return Err(SanMoveError::Malformed);
+    };
+    let destination = square(*destination_col, *destination_row).ok_or(SanMoveError::Malformed)?;
+    let (origin_col, origin_row) = match origin {
+        [] => (None, None),
+        [col @ b'a'..=b'h'] => (Some(col - b'a' + 1), None),
+        [row @ b'1'..=b'8'] => (None, Some(row - b'0')),
+        [col, row] => {
+            let origin = square(*col, *row).ok_or(SanMoveError::Malformed)?;
+            (Some(u8::from(origin.col)), Some(u8::from(origin.row)))
+        }
+        _ => return Err(SanMoveError::Malformed),
+    };
+
+    let mut candidates = game.legal_moves().filter(|mv| {
+        mv.kind.piece_kind() == kind
+            && mv.destination == destination
+            && mv.kind.promotion_replacement().map(|piece| piece.kind) == promotion
+            && matches!(mv.kind, MoveKind::King(KingMove::Castle { .. })).not()
+            && origin_col.is_none_or(|col| u8::from(mv.origin.col) == col)
+            && origin_row.is_none_or(|row| u8::from(mv.origin.row) == row)
+    });
+    let mv = candidates.next().ok_or(SanMoveError::Illegal)?;
+    if candidates.next().is_some() {
+        return Err(SanMoveError::Ambiguous);
+    }
+    Ok(mv)
+}
 #[cfg(test)]
 mod tests {
     use std::println;
 
     use super::*;
+    use crate::coord::Square;
+    use crate::testing::game_from_fen;
+
+    #[test]
+    fn san_round_trip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "1k6/8/8/8/8/8/8/Q1Q3QK w - - 0 1",
+        ] {
+            let game = game_from_fen(fen);
+            for mv in game.legal_moves() {
+                let san = standard_algebraic_notation(game.clone(), mv);
+                assert_eq!(Ok(mv), parse_san_move(&game, san.as_str()), "{fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn lenient_san() {
+        let game = game_from_fen("r3k3/1P6/8/8/8/8/8/R3K2R w KQq - 0 1");
+        let parse = |text| parse_san_move(&game, text).map(|mv| (mv.origin, mv.destination));
+
+        assert_eq!(Ok((Square::E1, Square::G1)), parse("0-0"));
+        assert_eq!(Ok((Square::E1, Square::C1)), parse("O-O-O+!?"));
+        assert_eq!(Ok((Square::B7, Square::A8)), parse("bxa8=Q"));
+        assert_eq!(Ok((Square::B7, Square::A8)), parse("ba8Q"));
+        assert_eq!(Ok((Square::H1, Square::H8)), parse("Rh1h8+"));
+        assert_eq!(Ok((Square::A1, Square::D1)), parse("Rd1"));
+        assert_eq!(Err(SanMoveError::Illegal), parse("Rb2"));
+        assert_eq!(Err(SanMoveError::Illegal), parse("b8"));
+        assert_eq!(Err(SanMoveError::Malformed), parse("Xe4"));
+        assert_eq!(Err(SanMoveError::Malformed), parse("e9"));
+
+        let game = game_from_fen("1k6/8/8/8/8/8/8/Q1Q3QK w - - 0 1");
+        assert_eq!(Err(SanMoveError::Ambiguous), parse_san_move(&game, "Qb1"));
+        assert!(parse_san_move(&game, "Qab1").is_ok());
+    }
 
     #[test]
     fn test_thingy() {
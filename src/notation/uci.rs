@@ -0,0 +1,141 @@
+use alloc::vec::Vec;
+use core::ascii::Char as AsciiChar;
+
+use crate::coord::Col;
+use crate::coord::Row;
+use crate::coord::Square;
+use crate::game::GameState;
+use crate::game::Ongoing;
+use crate::mv::Move;
+use crate::piece::Piece;
+
+/// what uci calls long algebraic notation: origin, destination and a lowercase promotion piece,
+/// like `e2e4` or `e7e8q`. castling is the king moving two squares, `e1g1`.
+#[must_use]
+pub fn uci_notation(mv: Move) -> Vec<AsciiChar> {
+    let promotion = mv
+        .kind
+        .promotion_replacement()
+        .map(|replacement| replacement.kind.to_ascii_lower());
+
+    [
+        mv.origin.to_fen_repr().as_slice(),
+        mv.destination.to_fen_repr().as_slice(),
+        promotion.as_slice(),
+    ]
+    .concat()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UciMoveError {
+    WrongLength(usize),
+    MalformedSquare,
+    MalformedPromotion(u8),
+    Illegal,
+}
+
+/// the legal move in `game` that `text` is the [`uci_notation`] of
+pub fn parse_uci_move(game: &GameState<Ongoing>, text: &str) -> Result<Move, UciMoveError> {
+    let (squares, promotion) = match text.as_bytes() {
+        [squares @ .., promotion] if text.len() == 5 => (squares, Some(*promotion)),
+        squares if text.len() == 4 => (squares, None),
+        _ => return Err(UciMoveError::WrongLength(text.len())),
+    };
+    let &[origin_col, origin_row, destination_col, destination_row] = squares else {
+        unreachable!("the length to be checked above");
+    };
+
+    let origin = square(origin_col, origin_row).ok_or(UciMoveError::MalformedSquare)?;
+    let destination =
+        square(destination_col, destination_row).ok_or(UciMoveError::MalformedSquare)?;
+    let promotion = promotion
+        .map(|promotion| {
+            AsciiChar::from_u8(promotion)
+                .and_then(|promotion| Piece::try_from_fen_repr(promotion).ok())
+                .filter(|piece| piece.kind.to_ascii_lower().to_u8() == promotion)
+                .map(|piece| piece.kind)
+                .ok_or(UciMoveError::MalformedPromotion(promotion))
+        })
+        .transpose()?;
+
+    game.legal_moves()
+        .find(|mv| {
+            mv.origin == origin
+                && mv.destination == destination
+                && mv.kind.promotion_replacement().map(|piece| piece.kind) == promotion
+        })
+        .ok_or(UciMoveError::Illegal)
+}
+
+pub(super) const fn square(col: u8, row: u8) -> Option<Square> {
+    let col = Col::try_from(col.checked_sub(b'a')? + 1).ok()?;
+    let row = Row::try_from(row.checked_sub(b'0')?).ok()?;
+    Some(Square::new(col, row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::game_from_fen;
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        ] {
+            let game = game_from_fen(fen);
+            for mv in game.legal_moves() {
+                let notation = uci_notation(mv);
+                assert_eq!(Ok(mv), parse_uci_move(&game, notation.as_str()), "{fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn notation() {
+        let game = game_from_fen("r3k3/1P6/8/8/8/8/8/4K2R w K - 0 1");
+        let notations = game
+            .legal_moves()
+            .map(|mv| std::string::String::from(uci_notation(mv).as_str()))
+            .collect::<Vec<_>>();
+
+        assert!(notations.contains(&"e1g1".into()));
+        assert!(notations.contains(&"b7a8q".into()));
+        assert!(notations.contains(&"b7b8n".into()));
+    }
+
+    #[test]
+    fn errors() {
+        let game = GameState::new();
+
+        assert_eq!(Err(UciMoveError::Illegal), parse_uci_move(&game, "e2e5"));
+        assert_eq!(Err(UciMoveError::Illegal), parse_uci_move(&game, "e2e4q"));
+        assert_eq!(
+            Err(UciMoveError::WrongLength(3)),
+            parse_uci_move(&game, "e2e")
+        );
+        assert_eq!(
+            Err(UciMoveError::MalformedSquare),
+            parse_uci_move(&game, "ä2e")
+        );
+        assert_eq!(
+            Err(UciMoveError::MalformedSquare),
+            parse_uci_move(&game, "e9e4")
+        );
+        assert_eq!(
+            Err(UciMoveError::MalformedSquare),
+            parse_uci_move(&game, "!2e4")
+        );
+        assert_eq!(
+            Err(UciMoveError::MalformedPromotion(b'x')),
+            parse_uci_move(&game, "e2e4x")
+        );
+        assert_eq!(
+            Err(UciMoveError::MalformedPromotion(b'Q')),
+            parse_uci_move(&game, "e2e4Q")
+        );
+    }
+}
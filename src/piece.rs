@@ -14,6 +14,11 @@ pub enum PieceKind {
     Rook,
     Queen,
     King,
+    Archbishop,
+    Chancellor,
+    Amazon,
+    Camel,
+    Zebra,
 }
 impl PieceKind {
     pub const ALL: [Self; 6] = [
@@ -25,8 +30,32 @@ impl PieceKind {
         Self::King,
     ];
 
+    pub const COUNT: usize = 11;
+
+    pub const FAIRY: [Self; 5] = [
+        Self::Archbishop,
+        Self::Chancellor,
+        Self::Amazon,
+        Self::Camel,
+        Self::Zebra,
+    ];
+
     pub const PROMOTION_OPTIONS: [Self; 4] = [Self::Knight, Self::Bishop, Self::Rook, Self::Queen];
 
+    pub const ANTICHESS_PROMOTION_OPTIONS: [Self; 5] = [
+        Self::Knight,
+        Self::Bishop,
+        Self::Rook,
+        Self::Queen,
+        Self::King,
+    ];
+
+    /// dense index into per-kind tables, in declaration order
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
     #[must_use]
     pub const fn to_black_piece(self) -> Piece {
         Piece::new(PlayerKind::Black, self)
@@ -69,6 +98,18 @@ impl Piece {
     pub const BLACK_ROOK:   Self = Self::new(PlayerKind::Black, PieceKind::Rook);
     pub const BLACK_QUEEN:  Self = Self::new(PlayerKind::Black, PieceKind::Queen);
     pub const BLACK_KING:   Self = Self::new(PlayerKind::Black, PieceKind::King);
+
+    pub const WHITE_ARCHBISHOP: Self = Self::new(PlayerKind::White, PieceKind::Archbishop);
+    pub const WHITE_CHANCELLOR: Self = Self::new(PlayerKind::White, PieceKind::Chancellor);
+    pub const WHITE_AMAZON:     Self = Self::new(PlayerKind::White, PieceKind::Amazon);
+    pub const WHITE_CAMEL:      Self = Self::new(PlayerKind::White, PieceKind::Camel);
+    pub const WHITE_ZEBRA:      Self = Self::new(PlayerKind::White, PieceKind::Zebra);
+
+    pub const BLACK_ARCHBISHOP: Self = Self::new(PlayerKind::Black, PieceKind::Archbishop);
+    pub const BLACK_CHANCELLOR: Self = Self::new(PlayerKind::Black, PieceKind::Chancellor);
+    pub const BLACK_AMAZON:     Self = Self::new(PlayerKind::Black, PieceKind::Amazon);
+    pub const BLACK_CAMEL:      Self = Self::new(PlayerKind::Black, PieceKind::Camel);
+    pub const BLACK_ZEBRA:      Self = Self::new(PlayerKind::Black, PieceKind::Zebra);
     }
 
     pub const ALL: [Self; 12] = [
@@ -86,16 +127,38 @@ impl Piece {
         Self::BLACK_KING,
     ];
 
+    pub const FAIRY: [Self; 10] = [
+        Self::WHITE_ARCHBISHOP,
+        Self::WHITE_CHANCELLOR,
+        Self::WHITE_AMAZON,
+        Self::WHITE_CAMEL,
+        Self::WHITE_ZEBRA,
+        Self::BLACK_ARCHBISHOP,
+        Self::BLACK_CHANCELLOR,
+        Self::BLACK_AMAZON,
+        Self::BLACK_CAMEL,
+        Self::BLACK_ZEBRA,
+    ];
+
+    /// every piece is just a bunch of leaps (`Range::One`) and rides (`Range::Unlimited`)
     #[must_use]
-    pub(crate) const fn threat_directions(self) -> (&'static [Offset], Range) {
+    pub(crate) const fn threat_directions(self) -> &'static [(&'static [Offset], Range)] {
+        use Range::One as Leap;
+        use Range::Unlimited as Ride;
+
         match (self.kind, self.owner) {
-            (PieceKind::Pawn, PlayerKind::White) => (&Offset::PAWN_UP_DIAGONAL, Range::One),
-            (PieceKind::Pawn, PlayerKind::Black) => (&Offset::PAWN_DOWN_DIAGONAL, Range::One),
-            (PieceKind::Knight, _) => (&Offset::KNIGHT, Range::One),
-            (PieceKind::Bishop, _) => (&Offset::BISHOP, Range::Unlimited),
-            (PieceKind::Rook, _) => (&Offset::ROOK, Range::Unlimited),
-            (PieceKind::Queen, _) => (&Offset::QUEEN, Range::Unlimited),
-            (PieceKind::King, _) => (&Offset::KING_DIRECT, Range::One),
+            (PieceKind::Pawn, PlayerKind::White) => &[(&Offset::PAWN_UP_DIAGONAL, Leap)],
+            (PieceKind::Pawn, PlayerKind::Black) => &[(&Offset::PAWN_DOWN_DIAGONAL, Leap)],
+            (PieceKind::Knight, _) => &[(&Offset::KNIGHT, Leap)],
+            (PieceKind::Bishop, _) => &[(&Offset::BISHOP, Ride)],
+            (PieceKind::Rook, _) => &[(&Offset::ROOK, Ride)],
+            (PieceKind::Queen, _) => &[(&Offset::QUEEN, Ride)],
+            (PieceKind::King, _) => &[(&Offset::KING_DIRECT, Leap)],
+            (PieceKind::Archbishop, _) => &[(&Offset::KNIGHT, Leap), (&Offset::BISHOP, Ride)],
+            (PieceKind::Chancellor, _) => &[(&Offset::KNIGHT, Leap), (&Offset::ROOK, Ride)],
+            (PieceKind::Amazon, _) => &[(&Offset::KNIGHT, Leap), (&Offset::QUEEN, Ride)],
+            (PieceKind::Camel, _) => &[(&Offset::CAMEL, Leap)],
+            (PieceKind::Zebra, _) => &[(&Offset::ZEBRA, Leap)],
         }
     }
 }
@@ -116,11 +179,22 @@ impl core::fmt::Display for Piece {
             Self::BLACK_ROOK => "♜",
             Self::BLACK_QUEEN => "♛",
             Self::BLACK_KING => "♚",
+
+            // no unicode chess symbols for these, so just use their fen letters
+            Self {
+                kind,
+                owner: PlayerKind::White,
+            } => return write!(f, "{}", kind.to_ascii_upper()),
+            Self {
+                kind,
+                owner: PlayerKind::Black,
+            } => return write!(f, "{}", kind.to_ascii_lower()),
         };
         write!(f, "{symbol}")
     }
 }
 
+#[derive(Clone, Copy)]
 pub(crate) enum Range {
     One,
     Unlimited,
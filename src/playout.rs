@@ -0,0 +1,273 @@
+//! random playouts whose moves are picked by a policy instead of uniformly, recording everything
+//! that happened on the way for training data
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ascii::Char as AsciiChar;
+
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::eval::evaluate;
+use crate::game::GameResult;
+use crate::game::GameResultKind;
+use crate::game::GameState;
+use crate::game::GameStateCore;
+use crate::game::Ongoing;
+use crate::game::StepResult;
+use crate::mv::Move;
+
+/// how likely each of the legal moves is to be played, in the order they're given. the weights
+/// don't need to add up to anything; if none of them is positive the move is picked uniformly.
+pub trait Policy: Fn(&GameStateCore, &[Move]) -> Vec<f64> {}
+
+impl<F: Fn(&GameStateCore, &[Move]) -> Vec<f64>> Policy for F {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trajectory {
+    /// the position before every move, the first one being where the playout started
+    pub fens: Vec<Vec<AsciiChar>>,
+    pub moves: Vec<Move>,
+    /// the position the playout stopped in, whether the game ended there or not
+    pub final_core: GameStateCore,
+    /// for the player who made the last move, `None` when the playout ran out of plies before
+    /// the game ended
+    pub result: Option<GameResultKind>,
+}
+
+impl GameState<Ongoing> {
+    /// like [`GameState::random_walk`], with the moves picked by `policy`
+    ///
+    /// # Panics
+    ///
+    /// if `policy` doesn't give exactly one weight for every legal move
+    pub fn policy_walk(
+        self,
+        max_depth: u32,
+        rng: &mut impl Rng,
+        policy: impl Policy,
+    ) -> Trajectory {
+        let mut game = self;
+        let mut fens = vec![];
+        let mut moves = vec![];
+
+        for _ in 0..max_depth {
+            let legal_moves = game.legal_moves().collect::<Vec<_>>();
+            let mv = pick(&game.core, &legal_moves, rng, &policy);
+            fens.push(game.core.to_fen());
+            moves.push(mv);
+
+            match game.step(mv) {
+                StepResult::Ongoing(next) => game = next,
+                StepResult::Terminated(GameResult {
+                    kind,
+                    final_game_state,
+                }) => {
+                    return Trajectory {
+                        fens,
+                        moves,
+                        final_core: final_game_state.core,
+                        result: Some(kind),
+                    };
+                }
+            }
+        }
+        Trajectory {
+            fens,
+            moves,
+            final_core: game.core,
+            result: None,
+        }
+    }
+}
+
+fn pick(
+    core: &GameStateCore,
+    legal_moves: &[Move],
+    rng: &mut impl Rng,
+    policy: &impl Policy,
+) -> Move {
+    let weights = policy(core, legal_moves);
+    assert_eq!(
+        legal_moves.len(),
+        weights.len(),
+        "a policy to weigh every legal move"
+    );
+    let weighted = legal_moves
+        .iter()
+        .zip(weights)
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect::<Vec<_>>();
+    weighted
+        .choose_weighted(rng, |(_, weight)| *weight)
+        .map_or_else(
+            |_| {
+                *legal_moves
+                    .choose(rng)
+                    .expect("a GameState<Ongoing> to always have legal moves")
+            },
+            |(mv, _)| **mv,
+        )
+}
+
+/// every move equally likely, the same as [`GameState::random_walk`]
+#[must_use]
+pub fn uniform(_: &GameStateCore, legal_moves: &[Move]) -> Vec<f64> {
+    vec![1.0; legal_moves.len()]
+}
+
+/// captures `bias` times as likely as quiet moves
+#[must_use]
+pub fn captures_first(bias: f64) -> impl Policy {
+    move |_, legal_moves| {
+        legal_moves
+            .iter()
+            .map(|mv| if mv.is_capture() { bias } else { 1.0 })
+            .collect()
+    }
+}
+
+/// moves that give check `bias` times as likely as the others
+#[must_use]
+pub fn checks_first(bias: f64) -> impl Policy {
+    move |core, legal_moves| {
+        legal_moves
+            .iter()
+            .map(|mv| {
+                let mut board = core.board;
+                board.apply_move(*mv);
+                if board.is_king_checked(core.active_player.opponent()) {
+                    bias
+                } else {
+                    1.0
+                }
+            })
+            .collect()
+    }
+}
+
+/// every move weighted by `exp(score / temperature)`
+///
+/// the score is the evaluation in centipawns of the position the move leads to, from the mover's
+/// point of view. the lower the temperature, the closer it gets to always playing the move that
+/// evaluates best, which is all a temperature of 0 or below plays.
+#[must_use]
+pub fn softmax_eval(temperature: f64) -> impl Policy {
+    move |core, legal_moves| {
+        let scores = legal_moves
+            .iter()
+            .map(|mv| {
+                let mut next = *core;
+                next.board.apply_move(*mv);
+                -f64::from(evaluate(&next.with_opponent_active()).0)
+            })
+            .collect::<Vec<_>>();
+        // the best move weighs 1, so nothing overflows
+        let best = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        scores
+            .into_iter()
+            .map(|score| {
+                if temperature > 0.0 {
+                    ((score - best) / temperature).exp()
+                } else if score < best {
+                    0.0
+                } else {
+                    1.0
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use crate::testing::game_from_fen;
+
+    #[test]
+    fn trajectories_line_up() {
+        let trajectory =
+            GameState::new().policy_walk(300, &mut StdRng::seed_from_u64(1), captures_first(10.0));
+
+        assert_eq!(trajectory.fens.len(), trajectory.moves.len());
+        let mut game = GameState::new();
+        for (fen, mv) in trajectory.fens.iter().zip(&trajectory.moves) {
+            assert_eq!(game.core.to_fen(), *fen);
+            match game.step(*mv) {
+                StepResult::Ongoing(next) => game = next,
+                StepResult::Terminated(GameResult {
+                    kind,
+                    final_game_state,
+                }) => {
+                    assert_eq!(Some(kind), trajectory.result);
+                    assert_eq!(final_game_state.core, trajectory.final_core);
+                    return;
+                }
+            }
+        }
+        assert_eq!(None, trajectory.result);
+        assert_eq!(game.core, trajectory.final_core);
+    }
+
+    #[test]
+    fn policies_bias_the_moves() {
+        // the rook can take the queen or go anywhere else
+        let game = game_from_fen("4k3/8/8/8/8/8/q7/R3K3 w - - 0 1");
+        let captures = (0..20)
+            .filter(|seed| {
+                let trajectory = game.clone().policy_walk(
+                    1,
+                    &mut StdRng::seed_from_u64(*seed),
+                    captures_first(1_000.0),
+                );
+                trajectory.moves[0].is_capture()
+            })
+            .count();
+        assert!(captures >= 18, "{captures}");
+
+        let greedy = game
+            .clone()
+            .policy_walk(1, &mut StdRng::seed_from_u64(0), softmax_eval(1.0));
+        assert!(greedy.moves[0].is_capture());
+        // no temperature at all only leaves the best move
+        let legal_moves = game.legal_moves().collect::<Vec<_>>();
+        let weights = softmax_eval(0.0)(&game.core, &legal_moves);
+        for (mv, weight) in legal_moves.iter().zip(weights) {
+            assert!(weight.is_finite(), "{mv:?} {weight}");
+            assert_eq!(mv.is_capture(), weight > 0.0, "{mv:?} {weight}");
+        }
+
+        // only the rook going to the eighth row gives check
+        let game = game_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let checking =
+            game.clone()
+                .policy_walk(1, &mut StdRng::seed_from_u64(0), checks_first(1.0e9));
+        let mut board = game.core.board;
+        board.apply_move(checking.moves[0]);
+        assert!(board.is_king_checked(crate::player::PlayerKind::Black));
+    }
+
+    #[test]
+    fn zero_weights_fall_back_to_uniform() {
+        let trajectory = GameState::new().policy_walk(
+            10,
+            &mut StdRng::seed_from_u64(0),
+            |_: &GameStateCore, moves: &[Move]| vec![0.0; moves.len()],
+        );
+        assert_eq!(10, trajectory.moves.len());
+    }
+
+    #[test]
+    fn cut_off_playouts_keep_where_they_stopped() {
+        let game = game_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let trajectory = game
+            .clone()
+            .policy_walk(0, &mut StdRng::seed_from_u64(0), uniform);
+        assert!(trajectory.moves.is_empty());
+        assert_eq!(None, trajectory.result);
+        assert_eq!(game.core, trajectory.final_core);
+    }
+}
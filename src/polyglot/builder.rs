@@ -0,0 +1,301 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::ops::Not;
+
+use crate::game::GameState;
+use crate::game::Ongoing;
+use crate::game::StepResult;
+use crate::mv::Move;
+use crate::notation::pgn::PgnGame;
+use crate::notation::pgn::PgnResult;
+use crate::polyglot::PolyglotBook;
+use crate::polyglot::PolyglotEntry;
+use crate::polyglot::polyglot_move;
+
+/// how a move did in the games it was played in, seen from the side that played it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MoveStats {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MoveStats {
+    #[must_use]
+    pub const fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// what polyglot books usually weigh a move by, two points for a win and one for a draw
+    #[must_use]
+    pub const fn score(&self) -> u32 {
+        2 * self.wins + self.draws
+    }
+}
+
+/// whose moves make it into the book, by how their game ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResultFilter {
+    #[default]
+    All,
+    /// only the moves of the side that went on to win, drawn games don't count at all
+    WinnersOnly,
+    /// everything but the moves of the side that went on to lose
+    NoLosers,
+}
+
+impl ResultFilter {
+    const fn allows(self, won: Option<bool>) -> bool {
+        match self {
+            Self::All => true,
+            Self::WinnersOnly => matches!(won, Some(true)),
+            Self::NoLosers => matches!(won, Some(false)).not(),
+        }
+    }
+}
+
+/// collects the moves of many games into a polyglot book
+#[derive(Debug, Clone, Default)]
+pub struct BookBuilder {
+    max_ply: usize,
+    min_games: u32,
+    results: ResultFilter,
+    stats: BTreeMap<(u64, u16), MoveStats>,
+}
+
+impl BookBuilder {
+    /// only the first `max_ply` moves of every game end up in the book
+    #[must_use]
+    pub const fn new(max_ply: usize) -> Self {
+        Self {
+            max_ply,
+            min_games: 1,
+            results: ResultFilter::All,
+            stats: BTreeMap::new(),
+        }
+    }
+
+    /// leaves out the moves played in fewer games than this
+    #[must_use]
+    pub const fn with_min_games(mut self, min_games: u32) -> Self {
+        self.min_games = min_games;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_results(mut self, results: ResultFilter) -> Self {
+        self.results = results;
+        self
+    }
+
+    /// `moves` have to be legal, one after the other from `start`.
+    /// games with an unknown result are skipped, there's nothing to score them by.
+    pub fn add_game(&mut self, start: &GameState<Ongoing>, moves: &[Move], result: PgnResult) {
+        if result == PgnResult::Unknown {
+            return;
+        }
+
+        let mut game = start.clone();
+        for mv in moves.iter().take(self.max_ply) {
+            let Some(key) = game.core.polyglot_key() else {
+                return;
+            };
+            let won = result
+                .winner()
+                .map(|winner| winner == game.core.active_player);
+
+            if self.results.allows(won) {
+                let stats = self.stats.entry((key, polyglot_move(*mv))).or_default();
+                match won {
+                    Some(true) => stats.wins += 1,
+                    Some(false) => stats.losses += 1,
+                    None => stats.draws += 1,
+                }
+            }
+
+            match game.step(*mv) {
+                StepResult::Ongoing(next) => game = next,
+                StepResult::Terminated(_) => return,
+            }
+        }
+    }
+
+    pub fn add_pgn_game(&mut self, game: &PgnGame) {
+        self.add_game(&game.start, &game.moves, game.result);
+    }
+
+    /// every move seen so far in the position with this key, by its [`polyglot_move`]
+    pub fn stats(&self, key: u64) -> impl Iterator<Item = (u16, MoveStats)> {
+        self.stats
+            .range((key, 0)..=(key, u16::MAX))
+            .map(|(&(_, raw_move), &stats)| (raw_move, stats))
+    }
+
+    /// the book, sorted like the format wants it, the best move of every position first.
+    /// moves that never scored a point are left out, nobody would pick them anyway.
+    #[must_use]
+    pub fn build(&self) -> PolyglotBook {
+        let mut entries = Vec::new();
+        let mut moves = self
+            .stats
+            .iter()
+            .filter(|(_, stats)| stats.games() >= self.min_games && stats.score() > 0)
+            .peekable();
+
+        while let Some(&(&(key, _), _)) = moves.peek() {
+            let mut position = Vec::new();
+            while let Some(((_, raw_move), stats)) = moves.next_if(|((next, _), _)| *next == key) {
+                position.push((*raw_move, stats.score()));
+            }
+            position.sort_by_key(|(_, score)| core::cmp::Reverse(*score));
+
+            // the weights have to fit a u16, scaled down together so they keep their ratios
+            let max = position[0].1;
+            entries.extend(position.into_iter().map(|(raw_move, score)| {
+                let weight = if max > u32::from(u16::MAX) {
+                    u64::from(score) * u64::from(u16::MAX) / u64::from(max)
+                } else {
+                    u64::from(score)
+                };
+                PolyglotEntry {
+                    key,
+                    raw_move,
+                    weight: u16::try_from(weight.max(1)).unwrap_or(u16::MAX),
+                    learn: 0,
+                }
+            }));
+        }
+
+        PolyglotBook::from_entries(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::pgn::parse_pgn;
+    use crate::notation::uci::parse_uci_move;
+
+    const GAMES: &str = "
+        1. e4 e5 2. Nf3 Nc6 1-0
+        1. e4 c5 2. Nf3 d6 0-1
+        1. e4 e5 2. Bc4 Nf6 1/2-1/2
+        1. d4 d5 2. c4 e6 1-0
+        1. d4 Nf6 *
+    ";
+
+    fn builder(builder: BookBuilder) -> BookBuilder {
+        let mut builder = builder;
+        for game in parse_pgn(GAMES) {
+            builder.add_pgn_game(&game.unwrap());
+        }
+        builder
+    }
+
+    fn weights(book: &PolyglotBook, game: &GameState<Ongoing>) -> Vec<(u16, u16)> {
+        let key = game.core.polyglot_key().unwrap();
+        book.entries_for(key)
+            .iter()
+            .map(|entry| (entry.raw_move, entry.weight))
+            .collect()
+    }
+
+    fn raw(game: &GameState<Ongoing>, text: &str) -> u16 {
+        polyglot_move(parse_uci_move(game, text).unwrap())
+    }
+
+    #[test]
+    fn counts_every_result() {
+        let builder = builder(BookBuilder::new(2));
+        let start = GameState::new();
+        let stats = builder
+            .stats(start.core.polyglot_key().unwrap())
+            .collect::<Vec<_>>();
+
+        // the unfinished 1. d4 Nf6 doesn't count
+        assert_eq!(
+            [
+                (
+                    raw(&start, "d2d4"),
+                    MoveStats {
+                        wins: 1,
+                        draws: 0,
+                        losses: 0,
+                    }
+                ),
+                (
+                    raw(&start, "e2e4"),
+                    MoveStats {
+                        wins: 1,
+                        draws: 1,
+                        losses: 1,
+                    }
+                ),
+            ],
+            stats.as_slice()
+        );
+    }
+
+    #[test]
+    fn sorted_by_weight() {
+        let book = builder(BookBuilder::new(2)).build();
+        let start = GameState::new();
+
+        assert_eq!(
+            [(raw(&start, "e2e4"), 3), (raw(&start, "d2d4"), 2)],
+            weights(&book, &start).as_slice()
+        );
+        assert!(book.entries().is_sorted_by_key(|entry| entry.key));
+        // only the first two plies of every game, and 1... d5 never scored
+        assert_eq!(4, book.entries().len());
+    }
+
+    #[test]
+    fn filters() {
+        let start = GameState::new();
+        let book = builder(BookBuilder::new(2).with_min_games(2)).build();
+        assert_eq!(
+            [(raw(&start, "e2e4"), 3)],
+            weights(&book, &start).as_slice()
+        );
+
+        let book = builder(BookBuilder::new(2).with_results(ResultFilter::WinnersOnly)).build();
+        let e4 = parse_uci_move(&start, "e2e4").unwrap();
+        let StepResult::Ongoing(after_e4) = start.clone().step(e4) else {
+            unreachable!()
+        };
+        assert_eq!(
+            [(raw(&start, "d2d4"), 2), (raw(&start, "e2e4"), 2)],
+            weights(&book, &start).as_slice()
+        );
+        assert_eq!(
+            [(raw(&after_e4, "c7c5"), 2)],
+            weights(&book, &after_e4).as_slice()
+        );
+    }
+
+    #[test]
+    fn large_weights_are_scaled_down() {
+        let start = GameState::new();
+        let e4 = parse_uci_move(&start, "e2e4").unwrap();
+        let d4 = parse_uci_move(&start, "d2d4").unwrap();
+        let mut builder = BookBuilder::new(1);
+        builder.add_game(&start, &[e4], PgnResult::WhiteWins);
+        builder.add_game(&start, &[d4], PgnResult::Draw);
+        // as if it had been won that often, replaying that many games takes a while
+        let key = start.core.polyglot_key().unwrap();
+        builder.stats.insert(
+            (key, polyglot_move(e4)),
+            MoveStats {
+                wins: 40_000,
+                ..MoveStats::default()
+            },
+        );
+        let book = PolyglotBook::from_bytes(&builder.build().to_bytes()).unwrap();
+
+        assert_eq!(
+            [(polyglot_move(e4), u16::MAX), (polyglot_move(d4), 1)],
+            weights(&book, &start).as_slice()
+        );
+    }
+}
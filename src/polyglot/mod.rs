@@ -0,0 +1,474 @@
+pub mod builder;
+mod random;
+
+use alloc::vec::Vec;
+use core::ops::Not;
+
+use random::RANDOM;
+
+use crate::coord::Row;
+use crate::coord::Square;
+use crate::game::GameState;
+use crate::game::GameStateCore;
+use crate::game::Ongoing;
+use crate::mv::KingMove;
+use crate::mv::Move;
+use crate::mv::MoveKind;
+use crate::piece::PieceKind;
+use crate::player::PlayerKind;
+
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+impl GameStateCore {
+    /// the key polyglot books look this position up by. unlike [`GameStateCore::zobrist_hash`]
+    /// it has to match every other program, so it uses the published numbers.
+    /// `None` if there are fairy pieces on the board, the format has no room for them.
+    #[must_use]
+    pub fn polyglot_key(&self) -> Option<u64> {
+        let mut key = 0;
+
+        for square in Square::ALL {
+            let Some(piece) = self.board[square] else {
+                continue;
+            };
+            // black pawn, white pawn, black knight, ... white king
+            let kind = match piece.kind {
+                PieceKind::Pawn => 0,
+                PieceKind::Knight => 1,
+                PieceKind::Bishop => 2,
+                PieceKind::Rook => 3,
+                PieceKind::Queen => 4,
+                PieceKind::King => 5,
+                _ => return None,
+            };
+            let kind = kind * 2 + usize::from(piece.owner == PlayerKind::White);
+            key ^= RANDOM[64 * kind + square.index()];
+        }
+
+        let rights = self.castling_rights;
+        for (i, has_right) in [
+            rights.white_kingside,
+            rights.white_queenside,
+            rights.black_kingside,
+            rights.black_queenside,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if has_right {
+                key ^= RANDOM[CASTLING_OFFSET + i];
+            }
+        }
+
+        if let Some(target) = self.en_passant_target
+            && self.can_capture_towards(target)
+        {
+            key ^= RANDOM[EN_PASSANT_OFFSET + usize::from(target.col) - 1];
+        }
+
+        if self.active_player == PlayerKind::White {
+            key ^= RANDOM[TURN_OFFSET];
+        }
+
+        Some(key)
+    }
+
+    /// polyglot only counts an en passant square if a pawn of the side to move stands next to
+    /// the pawn that just moved, whether or not taking it would be legal
+    fn can_capture_towards(&self, target: Square) -> bool {
+        let pawn_row = match self.active_player {
+            PlayerKind::White => Row::_5,
+            PlayerKind::Black => Row::_4,
+        };
+        [-1, 1].into_iter().any(|delta| {
+            (target.col + delta).is_ok_and(|col| {
+                self.board[Square::new(col, pawn_row)].is_some_and(|piece| {
+                    piece.kind == PieceKind::Pawn && piece.owner == self.active_player
+                })
+            })
+        })
+    }
+}
+
+/// one line of a polyglot book, 16 bytes big endian on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolyglotEntry {
+    pub key: u64,
+    /// see [`polyglot_move`]
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl PolyglotEntry {
+    pub const SIZE: usize = 16;
+
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; Self::SIZE]) -> Self {
+        let [key @ .., m0, m1, w0, w1, l0, l1, l2, l3] = bytes;
+        Self {
+            key: u64::from_be_bytes(key),
+            raw_move: u16::from_be_bytes([m0, m1]),
+            weight: u16::from_be_bytes([w0, w1]),
+            learn: u32::from_be_bytes([l0, l1, l2, l3]),
+        }
+    }
+
+    #[must_use]
+    pub const fn to_bytes(self) -> [u8; Self::SIZE] {
+        let [k0, k1, k2, k3, k4, k5, k6, k7] = self.key.to_be_bytes();
+        let [m0, m1] = self.raw_move.to_be_bytes();
+        let [w0, w1] = self.weight.to_be_bytes();
+        let [l0, l1, l2, l3] = self.learn.to_be_bytes();
+        [
+            k0, k1, k2, k3, k4, k5, k6, k7, m0, m1, w0, w1, l0, l1, l2, l3,
+        ]
+    }
+}
+
+/// how polyglot writes a move.
+///
+/// destination file and rank in the lowest 6 bits, then the origin, then the promotion piece
+/// from 1 for a knight to 4 for a queen. castling is written as the king taking its own rook, `e1h1` instead of our `e1g1`.
+#[must_use]
+pub fn polyglot_move(mv: Move) -> u16 {
+    const fn square_bits(square: Square) -> u16 {
+        (u16::from(u8::from(square.row)) - 1) << 3 | (u16::from(u8::from(square.col)) - 1)
+    }
+
+    let destination = match mv.kind {
+        MoveKind::King(KingMove::Castle { rook_start, .. }) => rook_start,
+        _ => mv.destination,
+    };
+    let promotion = match mv.kind.promotion_replacement().map(|piece| piece.kind) {
+        Some(PieceKind::Knight) => 1,
+        Some(PieceKind::Bishop) => 2,
+        Some(PieceKind::Rook) => 3,
+        Some(PieceKind::Queen) => 4,
+        _ => 0,
+    };
+
+    promotion << 12 | square_bits(mv.origin) << 6 | square_bits(destination)
+}
+
+/// the legal move in `game` that `raw_move` stands for, see [`polyglot_move`]
+#[must_use]
+pub fn decode_polyglot_move(game: &GameState<Ongoing>, raw_move: u16) -> Option<Move> {
+    game.legal_moves().find(|mv| polyglot_move(*mv) == raw_move)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PolyglotBookError {
+    /// the length of the file, which isn't a multiple of [`PolyglotEntry::SIZE`]
+    TruncatedEntry(usize),
+}
+
+/// a polyglot `.bin` opening book, all of it kept in memory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolyglotBook {
+    /// sorted by key, entries for the same key keep the order of the file
+    entries: Vec<PolyglotEntry>,
+}
+
+impl PolyglotBook {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PolyglotBookError> {
+        let (chunks, rest) = bytes.as_chunks::<{ PolyglotEntry::SIZE }>();
+        if rest.is_empty().not() {
+            return Err(PolyglotBookError::TruncatedEntry(bytes.len()));
+        }
+        Ok(Self::from_entries(
+            chunks.iter().map(|chunk| PolyglotEntry::from_bytes(*chunk)),
+        ))
+    }
+
+    /// the entries don't have to be sorted yet
+    pub fn from_entries(entries: impl IntoIterator<Item = PolyglotEntry>) -> Self {
+        let mut entries = entries.into_iter().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.key);
+        Self { entries }
+    }
+
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map(|entry| entry.to_bytes())
+            .collect()
+    }
+
+    #[must_use]
+    pub const fn entries(&self) -> &[PolyglotEntry] {
+        &self.entries
+    }
+
+    /// every entry stored for `key`, found by binary search
+    #[must_use]
+    pub fn entries_for(&self, key: u64) -> &[PolyglotEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = start + self.entries[start..].partition_point(|entry| entry.key == key);
+        &self.entries[start..end]
+    }
+
+    /// the book moves for `game` with their weights, leaving out any the book has wrong
+    #[must_use]
+    pub fn moves(&self, game: &GameState<Ongoing>) -> Vec<(Move, u16)> {
+        let Some(key) = game.core.polyglot_key() else {
+            return Vec::new();
+        };
+        self.entries_for(key)
+            .iter()
+            .filter_map(|entry| {
+                decode_polyglot_move(game, entry.raw_move).map(|mv| (mv, entry.weight))
+            })
+            .collect()
+    }
+
+    /// the book move with the highest weight, the first one in the file on a tie
+    #[must_use]
+    pub fn best_move(&self, game: &GameState<Ongoing>) -> Option<Move> {
+        self.moves(game)
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, weight)| *weight)
+            .map(|(mv, _)| mv)
+    }
+
+    /// picks a book move with a chance proportional to its weight. `roll` is taken modulo the
+    /// total weight, so any evenly spread number works. moves with weight 0 are never picked.
+    #[must_use]
+    pub fn weighted_move(&self, game: &GameState<Ongoing>, roll: u32) -> Option<Move> {
+        let moves = self.moves(game);
+        let total = moves
+            .iter()
+            .map(|(_, weight)| u32::from(*weight))
+            .sum::<u32>();
+        let mut roll = roll.checked_rem(total)?;
+        moves.into_iter().find_map(|(mv, weight)| {
+            if roll < u32::from(weight) {
+                Some(mv)
+            } else {
+                roll -= u32::from(weight);
+                None
+            }
+        })
+    }
+
+    /// [`PolyglotBook::weighted_move`] with a roll from `rng`, so the same seed always picks the
+    /// same moves
+    #[cfg(feature = "rand")]
+    #[must_use]
+    pub fn random_move(&self, game: &GameState<Ongoing>, rng: &mut impl rand::Rng) -> Option<Move> {
+        self.weighted_move(game, rng.random())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::StepResult;
+    use crate::notation::uci::parse_uci_move;
+    use crate::testing::game_from_fen;
+
+    fn play(mut game: GameState<Ongoing>, moves: &[&str]) -> GameState<Ongoing> {
+        for text in moves {
+            let mv = parse_uci_move(&game, text).unwrap();
+            let StepResult::Ongoing(next) = game.step(mv) else {
+                panic!("{text} to not end the game");
+            };
+            game = next;
+        }
+        game
+    }
+
+    fn entry(key: u64, game: &GameState<Ongoing>, text: &str, weight: u16) -> PolyglotEntry {
+        PolyglotEntry {
+            key,
+            raw_move: polyglot_move(parse_uci_move(game, text).unwrap()),
+            weight,
+            learn: 0,
+        }
+    }
+
+    #[test]
+    fn reference_keys() {
+        // the examples from the format's description
+        for (fen, key) in [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0x463b_9618_1691_fc9c,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                0x823c_9b50_fd11_4196,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                0x0756_b944_61c5_0fb0,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+                0x662f_afb9_65db_29d4,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                0x22a4_8b5a_8e47_ff78,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+                0x652a_607c_a3f2_42c1,
+            ),
+            (
+                "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+                0x00fd_d303_c946_bdd9,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+                0x3c81_23ea_7b06_7637,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4",
+                0x5c3f_9b82_9b27_9560,
+            ),
+        ] {
+            let core = GameStateCore::try_from_fen(fen).unwrap();
+            assert_eq!(Some(key), core.polyglot_key(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn keys_of_played_moves_match_the_fen() {
+        let game = play(GameState::new(), &["e2e4", "d7d5", "e4e5", "f7f5"]);
+        assert_eq!(Some(0x22a4_8b5a_8e47_ff78), game.core.polyglot_key());
+    }
+
+    #[test]
+    fn fairy_pieces_have_no_key() {
+        let core = GameStateCore::try_from_fen("4k3/8/8/8/8/8/8/3AK3 w - - 0 1").unwrap();
+        assert_eq!(None, core.polyglot_key());
+    }
+
+    #[test]
+    fn moves_round_trip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ] {
+            let game = game_from_fen(fen);
+            for mv in game.legal_moves() {
+                assert_eq!(
+                    Some(mv),
+                    decode_polyglot_move(&game, polyglot_move(mv)),
+                    "{fen}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn castling_is_the_king_taking_its_rook() {
+        let game = game_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        // e1h1, e1a1
+        let kingside = decode_polyglot_move(&game, 4 << 6 | 7).unwrap();
+        let queenside = decode_polyglot_move(&game, 4 << 6).unwrap();
+
+        assert_eq!(Square::G1, kingside.destination);
+        assert_eq!(Square::C1, queenside.destination);
+        // the plain two square king move isn't how polyglot writes it
+        assert_eq!(None, decode_polyglot_move(&game, 4 << 6 | 6));
+    }
+
+    #[test]
+    fn promotions() {
+        let game = game_from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        let mv = decode_polyglot_move(&game, 4 << 12 | (6 << 3 | 1) << 6 | (7 << 3 | 1)).unwrap();
+        assert_eq!(
+            Some(PieceKind::Queen),
+            mv.kind.promotion_replacement().map(|piece| piece.kind)
+        );
+    }
+
+    #[test]
+    fn lookup_and_weights() {
+        let game = GameState::new();
+        let key = game.core.polyglot_key().unwrap();
+        let book = PolyglotBook::from_entries([
+            entry(key + 1, &game, "a2a3", 100),
+            entry(key, &game, "e2e4", 3),
+            entry(key, &game, "d2d4", 5),
+            entry(key, &game, "g1f3", 0),
+            entry(key - 1, &game, "h2h3", 100),
+        ]);
+        let book = PolyglotBook::from_bytes(&book.to_bytes()).unwrap();
+
+        assert_eq!(3, book.entries_for(key).len());
+        assert_eq!(3, book.moves(&game).len());
+        assert_eq!(parse_uci_move(&game, "d2d4").ok(), book.best_move(&game));
+        assert_eq!(
+            parse_uci_move(&game, "e2e4").ok(),
+            book.weighted_move(&game, 2)
+        );
+        assert_eq!(
+            parse_uci_move(&game, "d2d4").ok(),
+            book.weighted_move(&game, 3)
+        );
+        assert_eq!(
+            parse_uci_move(&game, "d2d4").ok(),
+            book.weighted_move(&game, 7)
+        );
+        assert_eq!(
+            parse_uci_move(&game, "e2e4").ok(),
+            book.weighted_move(&game, 8)
+        );
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_moves_replay_from_their_seed() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let game = GameState::new();
+        let key = game.core.polyglot_key().unwrap();
+        let book = PolyglotBook::from_entries([
+            entry(key, &game, "e2e4", 3),
+            entry(key, &game, "d2d4", 5),
+            entry(key, &game, "g1f3", 0),
+        ]);
+        let picks = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..32)
+                .map(|_| book.random_move(&game, &mut rng).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(picks(7), picks(7));
+        assert!(picks(7).contains(&parse_uci_move(&game, "e2e4").unwrap()));
+        assert!(
+            picks(7)
+                .contains(&parse_uci_move(&game, "g1f3").unwrap())
+                .not()
+        );
+    }
+
+    #[test]
+    fn positions_out_of_book() {
+        let game = GameState::new();
+        let book = PolyglotBook::from_entries([entry(1, &game, "e2e4", 1)]);
+
+        assert!(book.moves(&game).is_empty());
+        assert_eq!(None, book.best_move(&game));
+        assert_eq!(None, book.weighted_move(&game, 0));
+    }
+
+    #[test]
+    fn truncated_files() {
+        assert_eq!(
+            Err(PolyglotBookError::TruncatedEntry(17)),
+            PolyglotBook::from_bytes(&[0; 17])
+        );
+        assert_eq!(Ok(PolyglotBook::default()), PolyglotBook::from_bytes(&[]));
+    }
+}
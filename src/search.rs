@@ -0,0 +1,1105 @@
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ascii::Char as AsciiChar;
+use core::ops::Not;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+
+use crate::board::Board;
+use crate::coord::Square;
+use crate::eval::EvalWeights;
+use crate::eval::Score;
+use crate::eval::evaluate_with;
+use crate::game::GameResult;
+use crate::game::GameResultKind;
+use crate::game::GameState;
+use crate::game::Ongoing;
+use crate::game::StepResult;
+use crate::move_picker::Heuristics;
+use crate::move_picker::MovePicker;
+use crate::mv::Move;
+use crate::mv::MoveKind;
+use crate::mv::PawnMove;
+use crate::notation::san::standard_algebraic_notation;
+use crate::piece::Piece;
+use crate::piece::PieceKind;
+use crate::player::PlayerKind;
+use crate::time::Clock;
+use crate::time::SearchLimits;
+use crate::time::TimeManager;
+use crate::tt::Bound;
+use crate::tt::PackedMove;
+use crate::tt::TranspositionTable;
+use crate::tt::TtEntry;
+
+pub const MAX_PLY: u32 = 128;
+
+const ASPIRATION_WINDOW: i32 = 50;
+const ASPIRATION_MIN_DEPTH: u32 = 3;
+/// how many nodes to search between looking at the clock and the stop flag
+const CHECK_INTERVAL: u64 = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// `None` when the game is already over, with nothing left to move
+    pub best_move: Option<Move>,
+    pub score: Score,
+    pub pv: Vec<Move>,
+    /// one per root move asked for with [`SearchLimits::multipv`], best first.
+    /// the first one is the same as `score` and `pv`.
+    pub lines: Vec<PvLine>,
+    pub nodes: u64,
+    pub depth: u32,
+    /// by the searchers clock, zero without one
+    pub elapsed: Duration,
+}
+
+impl SearchResult {
+    /// for a position made up with no legal moves, which [`GameState::step`] never leaves
+    /// ongoing: mate, stalemate or whatever the rule set makes of it
+    fn without_moves(game: &GameState<Ongoing>) -> Self {
+        // the result is for whoever moved into the position
+        let kind = game.rule_set.out_of_moves_result(&game.core);
+        Self {
+            best_move: None,
+            score: -terminal_score(kind, 0),
+            pv: vec![],
+            lines: vec![],
+            nodes: 0,
+            depth: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+/// the legal root moves [`SearchLimits::searchmoves`] leaves out
+fn left_out_root_moves(game: &GameState<Ongoing>, limits: &SearchLimits) -> Vec<Move> {
+    let (searched, left_out) = game
+        .legal_moves()
+        .partition::<Vec<_>, _>(|mv| limits.searchmoves.contains(mv));
+    if searched.is_empty() {
+        return vec![];
+    }
+    left_out
+}
+
+/// the line the search expects after one of the root moves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvLine {
+    pub score: Score,
+    pub pv: Vec<Move>,
+    /// the [`standard_algebraic_notation`] of every move in `pv`, each in the position it's played in
+    pub san: Vec<Vec<AsciiChar>>,
+}
+
+impl PvLine {
+    #[must_use]
+    pub fn new(game: &GameState<Ongoing>, score: Score, pv: Vec<Move>) -> Self {
+        let mut san = Vec::with_capacity(pv.len());
+        let mut game = game.clone();
+        for mv in &pv {
+            san.push(standard_algebraic_notation(game.clone(), *mv));
+            match game.step(*mv) {
+                StepResult::Ongoing(next) => game = next,
+                StepResult::Terminated(_) => break,
+            }
+        }
+        Self { score, pv, san }
+    }
+}
+
+#[derive(Debug)]
+pub struct Searcher {
+    nodes: u64,
+    table: Arc<TranspositionTable>,
+    heuristics: Heuristics,
+    stop: Arc<AtomicBool>,
+    /// while set, the clock limits don't count yet. they start counting once it's cleared.
+    pondering: Arc<AtomicBool>,
+    /// without one, only the depth and node limits apply
+    clock: Option<Arc<dyn Clock>>,
+    weights: Arc<EvalWeights>,
+    // only valid during a search
+    started_at: Duration,
+    time_manager: TimeManager,
+    node_limit: Option<u64>,
+    can_abort: bool,
+    aborted: bool,
+    was_pondering: bool,
+    /// root moves that already have a line in this iteration of a multipv search
+    excluded_root_moves: Vec<Move>,
+    /// how many threads a search runs on, all but this one as helpers
+    threads: usize,
+    /// `Some` for the helpers of a parallel search, which only fill the shared table. they start
+    /// their iterations this many plies deeper than the main searcher, to not all search the same.
+    helper_depth_offset: Option<u32>,
+    /// what the helpers of a parallel search visited, added up in batches as they go
+    helper_nodes: Arc<AtomicU64>,
+}
+
+impl Searcher {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_table(table: Arc<TranspositionTable>) -> Self {
+        #[cfg(any(test, feature = "std"))]
+        let clock: Option<Arc<dyn Clock>> = Some(Arc::new(crate::time::StdClock::new()));
+        #[cfg(not(any(test, feature = "std")))]
+        let clock: Option<Arc<dyn Clock>> = None;
+
+        Self {
+            nodes: 0,
+            table,
+            heuristics: Heuristics::new(),
+            stop: Arc::new(AtomicBool::new(false)),
+            pondering: Arc::new(AtomicBool::new(false)),
+            clock,
+            weights: Arc::new(EvalWeights::STANDARD),
+            started_at: Duration::ZERO,
+            time_manager: TimeManager::unlimited(),
+            node_limit: None,
+            can_abort: false,
+            aborted: false,
+            was_pondering: false,
+            excluded_root_moves: vec![],
+            threads: 1,
+            helper_depth_offset: None,
+            helper_nodes: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// lazy smp: every search runs on `threads` threads sharing the transposition table.
+    /// without `std` there are no threads to spread out on, so it's ignored.
+    #[must_use]
+    pub const fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// a searcher for another thread of a parallel search, stopped through `stop` once this
+    /// one is done
+    #[cfg(any(test, feature = "std"))]
+    pub(crate) fn helper(&self, depth_offset: u32, stop: Arc<AtomicBool>) -> Self {
+        Self {
+            clock: self.clock.clone(),
+            weights: Arc::clone(&self.weights),
+            stop,
+            helper_depth_offset: Some(depth_offset),
+            helper_nodes: Arc::clone(&self.helper_nodes),
+            ..Self::with_table(Arc::clone(&self.table))
+        }
+    }
+
+    #[must_use]
+    pub const fn threads(&self) -> usize {
+        self.threads
+    }
+
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// what the positions are evaluated with, [`EvalWeights::STANDARD`] without this
+    #[must_use]
+    pub fn with_weights(mut self, weights: Arc<EvalWeights>) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    #[must_use]
+    pub fn weights(&self) -> &EvalWeights {
+        &self.weights
+    }
+
+    #[must_use]
+    pub const fn table(&self) -> &Arc<TranspositionTable> {
+        &self.table
+    }
+
+    /// setting this makes a running search return as soon as it can. it stays set until
+    /// cleared from outside, so a stop that arrives before the search even started still counts.
+    #[must_use]
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// while this is set the time limits don't count, they start once it's cleared
+    #[must_use]
+    pub fn ponder_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.pondering)
+    }
+
+    #[must_use]
+    pub fn search(&mut self, game: &GameState<Ongoing>, limits: &SearchLimits) -> SearchResult {
+        self.search_with_info(game, limits, |_| ())
+    }
+
+    /// the best [`SearchLimits::multipv`] root moves with their lines, best first
+    #[must_use]
+    pub fn search_multipv(
+        &mut self,
+        game: &GameState<Ongoing>,
+        limits: &SearchLimits,
+    ) -> Vec<PvLine> {
+        self.search(game, limits).lines
+    }
+
+    /// the quiescence search on its own, from an open window: what the position is worth once the
+    /// captures on the board have played out. it's quiet if that's just its evaluation.
+    #[must_use]
+    pub fn quiescence_score(&mut self, game: &GameState<Ongoing>) -> Score {
+        self.prepare(game, &SearchLimits::default());
+        self.can_abort = false;
+        self.quiescence(game, -Score::INFINITY, Score::INFINITY, 0)
+    }
+
+    /// iterative deepening until one of the `limits` is hit, each iteration searching the
+    /// previous principal variation first. the first iteration always runs to completion,
+    /// so there always is a move to play. `info` sees the result of every finished iteration.
+    ///
+    /// with more than one line asked for, each iteration searches the root again for every line,
+    /// leaving out the root moves that already have a line.
+    pub fn search_with_info(
+        &mut self,
+        game: &GameState<Ongoing>,
+        limits: &SearchLimits,
+        info: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        if game.legal_moves().next().is_none() {
+            return SearchResult::without_moves(game);
+        }
+
+        #[cfg(any(test, feature = "std"))]
+        if self.threads > 1 {
+            return self.search_parallel(game, limits, info);
+        }
+
+        self.prepare(game, limits);
+        self.iterate(game, limits, info)
+            .expect("the first iteration to never be aborted")
+    }
+
+    pub(crate) fn prepare(&mut self, game: &GameState<Ongoing>, limits: &SearchLimits) {
+        self.nodes = 0;
+        self.started_at = self.now();
+        self.was_pondering = false;
+        self.time_manager = TimeManager::new(limits, game.core.active_player);
+        self.node_limit = limits.nodes;
+        self.aborted = false;
+        self.heuristics.new_search();
+        // the helpers are started by the main searcher, after it did this for everyone
+        if self.is_helper().not() {
+            self.table.new_search();
+            self.helper_nodes.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// the iterative deepening itself. `None` only for a helper stopped before its
+    /// first iteration was done.
+    pub(crate) fn iterate(
+        &mut self,
+        game: &GameState<Ongoing>,
+        limits: &SearchLimits,
+        mut info: impl FnMut(&SearchResult),
+    ) -> Option<SearchResult> {
+        let max_depth = limits.depth.unwrap_or(MAX_PLY).clamp(1, MAX_PLY);
+        let first_depth = (1 + self.helper_depth_offset.unwrap_or(0)).min(max_depth);
+        let left_out = left_out_root_moves(game, limits);
+        let line_count = usize::try_from(limits.multipv.unwrap_or(1))
+            .unwrap_or(usize::MAX)
+            .clamp(1, (game.legal_moves().count() - left_out.len()).max(1));
+        let mut stable_iterations = 0;
+        let mut result: Option<SearchResult> = None;
+        for depth in first_depth..=max_depth {
+            if result.is_some()
+                && (self.stop.load(Ordering::Relaxed)
+                    || self.elapsed_since_pondering().is_some_and(|elapsed| {
+                        self.time_manager
+                            .should_start_iteration(elapsed, stable_iterations)
+                            .not()
+                    }))
+            {
+                break;
+            }
+
+            self.can_abort = result.is_some() || self.is_helper();
+            self.excluded_root_moves.clone_from(&left_out);
+            let mut lines = Vec::with_capacity(line_count);
+            for index in 0..line_count {
+                let previous = result
+                    .as_ref()
+                    .and_then(|previous| previous.lines.get(index));
+                let (score, pv) = self.aspiration_search(game, depth, previous);
+                if self.aborted {
+                    break;
+                }
+                self.excluded_root_moves
+                    .push(*pv.first().expect("the root to always have a best move"));
+                lines.push(PvLine::new(game, score, pv));
+            }
+            // a partial iteration could be missing the best line, so all of it is thrown away
+            if self.aborted {
+                break;
+            }
+            lines.sort_by_key(|line| -line.score);
+
+            let PvLine { score, pv, .. } = lines[0].clone();
+            let best_move = Some(pv[0]);
+            stable_iterations = match &result {
+                Some(previous) if previous.best_move == best_move => stable_iterations + 1,
+                _ => 0,
+            };
+            result = Some(SearchResult {
+                best_move,
+                score,
+                pv,
+                lines,
+                nodes: self.total_nodes(),
+                depth,
+                elapsed: self.elapsed(),
+            });
+            info(result.as_ref().expect("the result to be set just above"));
+
+            let mate_moves = score.mate_moves();
+            if limits
+                .mate
+                .zip(mate_moves)
+                .is_some_and(|(limit, moves)| moves > 0 && moves.unsigned_abs() <= limit)
+            {
+                break;
+            }
+            // nothing deeper is going to find a faster mate than one we can already see the end of,
+            // but the other lines might still change
+            if line_count == 1
+                && mate_moves.is_some_and(|moves| moves.unsigned_abs() * 2 - 1 <= depth)
+            {
+                break;
+            }
+        }
+
+        if self.is_helper() {
+            self.helper_nodes
+                .fetch_add(self.nodes % CHECK_INTERVAL, Ordering::Relaxed);
+        }
+        let mut result = result?;
+        result.nodes = self.total_nodes();
+        result.elapsed = self.elapsed();
+        Some(result)
+    }
+
+    const fn is_helper(&self) -> bool {
+        self.helper_depth_offset.is_some()
+    }
+
+    /// including the ones the helpers of a parallel search visited so far
+    pub(crate) fn total_nodes(&self) -> u64 {
+        self.nodes + self.helper_nodes.load(Ordering::Relaxed)
+    }
+
+    fn now(&self) -> Duration {
+        self.clock
+            .as_ref()
+            .map_or(Duration::ZERO, |clock| clock.now())
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.now().saturating_sub(self.started_at)
+    }
+
+    /// `None` while pondering, the time limits start counting once that's over
+    fn elapsed_since_pondering(&mut self) -> Option<Duration> {
+        if self.pondering.load(Ordering::Relaxed) {
+            self.was_pondering = true;
+            return None;
+        }
+        if self.was_pondering {
+            self.was_pondering = false;
+            self.started_at = self.now();
+        }
+        Some(self.elapsed())
+    }
+
+    /// counts the node, and checks whether the search has to stop.
+    /// once it returns true it keeps doing so until the next search.
+    fn visit_node(&mut self) -> bool {
+        self.nodes += 1;
+        if self.is_helper() && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.helper_nodes
+                .fetch_add(CHECK_INTERVAL, Ordering::Relaxed);
+        }
+        if self.can_abort.not() || self.aborted {
+            return self.aborted;
+        }
+
+        self.aborted = self
+            .node_limit
+            .is_some_and(|limit| self.total_nodes() > limit)
+            || (self.nodes.is_multiple_of(CHECK_INTERVAL)
+                && (self.stop.load(Ordering::Relaxed)
+                    || self
+                        .elapsed_since_pondering()
+                        .is_some_and(|elapsed| self.time_manager.is_past_hard_deadline(elapsed))));
+        self.aborted
+    }
+
+    fn aspiration_search(
+        &mut self,
+        game: &GameState<Ongoing>,
+        depth: u32,
+        previous: Option<&PvLine>,
+    ) -> (Score, Vec<Move>) {
+        let pv_hint = previous
+            .map(|previous| previous.pv.as_slice())
+            .unwrap_or_default();
+
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = match previous {
+            Some(previous) if depth >= ASPIRATION_MIN_DEPTH && previous.score.is_mate().not() => (
+                Score(previous.score.0 - delta).max(-Score::INFINITY),
+                Score(previous.score.0 + delta).min(Score::INFINITY),
+            ),
+            _ => (-Score::INFINITY, Score::INFINITY),
+        };
+
+        loop {
+            let mut pv = vec![];
+            let score = self.negamax(game, depth, alpha, beta, 0, pv_hint, None, &mut pv);
+            if self.aborted {
+                return (score, pv);
+            }
+
+            if score <= alpha && alpha > -Score::INFINITY {
+                delta *= 2;
+                alpha = Score(alpha.0 - delta).max(-Score::INFINITY);
+            } else if score >= beta && beta < Score::INFINITY {
+                delta *= 2;
+                beta = Score(beta.0 + delta).min(Score::INFINITY);
+            } else {
+                return (score, pv);
+            }
+        }
+    }
+
+    /// fail-hard negamax. `pv_hint` is the line we expect to be best, it gets searched first,
+    /// otherwise the move from the transposition table does.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        game: &GameState<Ongoing>,
+        depth: u32,
+        mut alpha: Score,
+        beta: Score,
+        ply: u32,
+        pv_hint: &[Move],
+        previous_move: Option<Move>,
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        pv.clear();
+        if self.visit_node() {
+            return alpha;
+        }
+
+        if depth == 0 {
+            return self.quiescence(game, alpha, beta, ply);
+        }
+        if ply >= MAX_PLY {
+            return evaluate_with(&game.core, &self.weights);
+        }
+
+        let hash = game.core.zobrist_hash();
+        let entry = self.table.probe(hash);
+        if ply > 0
+            && let Some(entry) = entry
+            && u32::from(entry.depth) >= depth
+        {
+            let score = score_from_table(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => {
+                    if alpha < score && score < beta {
+                        pv.extend(
+                            entry
+                                .best_move
+                                .and_then(|mv| mv.unpack(&game.core, game.rule_set)),
+                        );
+                    }
+                    return score.clamp(alpha, beta);
+                }
+                Bound::Lower if score >= beta => return beta,
+                Bound::Upper if score <= alpha => return alpha,
+                Bound::Lower | Bound::Upper => {}
+            }
+        }
+
+        let hash_move = pv_hint
+            .first()
+            .map(|hint| PackedMove::new(*hint))
+            .or_else(|| entry.and_then(|entry| entry.best_move));
+        let mut picker = MovePicker::new(&game.core, game.rule_set, hash_move, ply, previous_move);
+
+        let mut child_pv = vec![];
+        while let Some(mv) = picker.next(&self.heuristics) {
+            if ply == 0 && self.excluded_root_moves.contains(&mv) {
+                continue;
+            }
+            let child_hint = match pv_hint {
+                [hint, rest @ ..] if *hint == mv => rest,
+                _ => &[],
+            };
+
+            let score = match game.clone().step(mv) {
+                StepResult::Ongoing(child) => -self.negamax(
+                    &child,
+                    depth - 1,
+                    -beta,
+                    -alpha,
+                    ply + 1,
+                    child_hint,
+                    Some(mv),
+                    &mut child_pv,
+                ),
+                StepResult::Terminated(GameResult { kind, .. }) => {
+                    child_pv.clear();
+                    terminal_score(kind, ply + 1)
+                }
+            };
+            // whatever the aborted child returned is meaningless, nothing of it may be kept
+            if self.aborted {
+                return alpha;
+            }
+
+            if score >= beta {
+                self.heuristics.record_cutoff(
+                    game.core.active_player,
+                    mv,
+                    ply,
+                    depth,
+                    previous_move,
+                );
+                self.store(hash, depth, Bound::Lower, beta, Some(mv), ply);
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+            }
+        }
+
+        match pv.first() {
+            Some(best_move) => self.store(hash, depth, Bound::Exact, alpha, Some(*best_move), ply),
+            None => self.store(hash, depth, Bound::Upper, alpha, None, ply),
+        }
+        alpha
+    }
+
+    fn store(
+        &self,
+        hash: u64,
+        depth: u32,
+        bound: Bound,
+        score: Score,
+        best_move: Option<Move>,
+        ply: u32,
+    ) {
+        // with some root moves left out, the root score isn't the score of the position
+        if ply == 0 && self.excluded_root_moves.is_empty().not() {
+            return;
+        }
+        self.table.store(
+            hash,
+            TtEntry {
+                depth: u8::try_from(depth).unwrap_or(u8::MAX),
+                bound,
+                score: score_to_table(score, ply),
+                best_move: best_move.map(PackedMove::new),
+                age: 0,
+            },
+        );
+    }
+
+    /// only looks at captures and promotions until the position is quiet, so the leaves of
+    /// the main search never stop in the middle of an exchange.
+    fn quiescence(
+        &mut self,
+        game: &GameState<Ongoing>,
+        mut alpha: Score,
+        beta: Score,
+        ply: u32,
+    ) -> Score {
+        if self.visit_node() {
+            return alpha;
+        }
+
+        let stand_pat = evaluate_with(&game.core, &self.weights);
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return stand_pat.min(beta);
+        }
+        alpha = alpha.max(stand_pat);
+
+        // losing captures can't raise alpha over standing pat, so they aren't worth searching
+        let mut moves = game
+            .tactical_moves()
+            .map(|mv| (static_exchange_evaluation(&game.core.board, mv), mv))
+            .filter(|(see, _)| *see >= Score::ZERO)
+            .collect::<Vec<_>>();
+        moves.sort_by_key(|(see, _)| -*see);
+
+        for (_, mv) in moves {
+            let score = match game.clone().step(mv) {
+                StepResult::Ongoing(child) => -self.quiescence(&child, -beta, -alpha, ply + 1),
+                StepResult::Terminated(GameResult { kind, .. }) => terminal_score(kind, ply + 1),
+            };
+            if self.aborted {
+                return alpha;
+            }
+
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::with_table(Arc::default())
+    }
+}
+
+/// the material balance of trading off everything that attacks `mv.destination`.
+///
+/// both sides recapture with their least valuable attacker and stop once that would lose
+/// material. attackers hidden behind sliders join in once the piece in front of them has captured.
+#[must_use]
+pub fn static_exchange_evaluation(board: &Board, mv: Move) -> Score {
+    let target = mv.destination;
+    let mover = board[mv.origin].expect("a move to start on a piece");
+
+    let captured = match mv.kind {
+        MoveKind::Pawn(PawnMove::EnPassant { .. }) => see_value(PieceKind::Pawn),
+        _ => board[target].map_or(0, |piece| see_value(piece.kind)),
+    };
+    let mut board = board.with_move_applied(mv);
+    let on_target = board[target].expect("the moved piece to be on its destination");
+
+    let mut gains = vec![captured + see_value(on_target.kind) - see_value(mover.kind)];
+    let mut on_target_value = see_value(on_target.kind);
+    let mut side = mover.owner.opponent();
+
+    while let Some((origin, attacker)) = least_valuable_attacker(&board, target, side) {
+        gains.push(on_target_value - gains.last().expect("gains to never be empty"));
+        board.mov(origin, target);
+        on_target_value = see_value(attacker.kind);
+        side = side.opponent();
+    }
+
+    // each side only carries on with the exchange if that's better for them than stopping
+    while gains.len() > 1 {
+        let last = gains.pop().expect("gains to have at least two entries");
+        let previous = gains.last_mut().expect("gains to have at least one entry");
+        *previous = -(-*previous).max(last);
+    }
+    Score(gains[0])
+}
+
+fn least_valuable_attacker(
+    board: &Board,
+    target: Square,
+    side: PlayerKind,
+) -> Option<(Square, Piece)> {
+    board
+        .threatening_moves_by(side)
+        .filter(|threat| threat.destination == target)
+        .min_by_key(|threat| see_value(threat.piece.kind))
+        .map(|threat| (threat.origin, threat.piece))
+}
+
+pub(crate) const fn see_value(kind: PieceKind) -> i32 {
+    match kind {
+        // high enough that the king only ever captures last, onto an undefended square
+        PieceKind::King => Score::MATE.0,
+        _ => EvalWeights::STANDARD.material[kind.index()].middlegame,
+    }
+}
+
+/// mate scores count plies from the root, but the table is shared between nodes at any ply,
+/// so it stores them counted from the node itself instead.
+const fn score_to_table(score: Score, ply: u32) -> Score {
+    #[allow(clippy::cast_possible_wrap)]
+    let ply = ply as i32;
+    match score {
+        _ if score.is_mate().not() => score,
+        Score(mate) if mate > 0 => Score(mate + ply),
+        Score(mated) => Score(mated - ply),
+    }
+}
+
+const fn score_from_table(score: Score, ply: u32) -> Score {
+    #[allow(clippy::cast_possible_wrap)]
+    let ply = ply as i32;
+    match score {
+        _ if score.is_mate().not() => score,
+        Score(mate) if mate > 0 => Score(mate - ply),
+        Score(mated) => Score(mated + ply),
+    }
+}
+
+/// `kind` is from the point of view of whoever made the last move, `ply` is the ply it ended on
+const fn terminal_score(kind: GameResultKind, ply: u32) -> Score {
+    match kind {
+        GameResultKind::Win => Score::mate_in(ply),
+        GameResultKind::Loss => Score::mated_in(ply),
+        GameResultKind::Draw(_) => Score::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::AtomicU64;
+
+    use super::*;
+    use crate::eval::evaluate;
+    use crate::notation::san::standard_algebraic_notation;
+    use crate::testing::game_from_fen;
+
+    #[test]
+    fn finds_mate_in_one() {
+        let game = game_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let result = Searcher::new().search(&game, &SearchLimits::depth(3));
+
+        assert_eq!(
+            "Ra8#",
+            standard_algebraic_notation(game, result.best_move.unwrap()).as_str()
+        );
+        assert_eq!(Some(1), result.score.mate_moves());
+        assert_eq!(1, result.depth); // a mate in one can't be improved on
+    }
+
+    #[test]
+    fn no_best_move_without_legal_moves() {
+        let stalemate = game_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        let result = Searcher::new().search(&stalemate, &SearchLimits::depth(2));
+        assert_eq!(None, result.best_move);
+        assert_eq!(Score::ZERO, result.score);
+        assert!(result.pv.is_empty());
+
+        let mate = game_from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1");
+        let result = Searcher::new()
+            .with_threads(2)
+            .search(&mate, &SearchLimits::depth(2));
+        assert_eq!(None, result.best_move);
+        assert_eq!(Score::mated_in(0), result.score);
+    }
+
+    #[test]
+    fn finds_mate_in_two() {
+        let game = game_from_fen("k7/8/2K5/8/8/8/8/1R6 w - - 0 1");
+        let result = Searcher::new().search(&game, &SearchLimits::depth(3));
+
+        assert_eq!(Some(2), result.score.mate_moves());
+        assert_eq!(3, result.pv.len());
+    }
+
+    #[test]
+    fn takes_the_free_queen() {
+        let game = game_from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        let result = Searcher::new().search(&game, &SearchLimits::depth(2));
+
+        assert_eq!(
+            "Rxd5",
+            standard_algebraic_notation(game, result.best_move.unwrap()).as_str()
+        );
+        assert!(result.score > Score(500));
+    }
+
+    fn find_move(game: &GameState<Ongoing>, san: &str) -> Move {
+        game.legal_moves()
+            .find(|mv| standard_algebraic_notation(game.clone(), *mv).as_str() == san)
+            .unwrap()
+    }
+
+    #[test]
+    fn multipv_lines_have_distinct_root_moves() {
+        let game = game_from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        let limits = SearchLimits {
+            depth: Some(2),
+            multipv: Some(3),
+            ..SearchLimits::default()
+        };
+        let result = Searcher::new().search(&game, &limits);
+
+        assert_eq!(3, result.lines.len());
+        assert_eq!(result.pv, result.lines[0].pv);
+        assert_eq!("Rxd5", result.lines[0].san[0].as_str());
+        assert!(result.lines.is_sorted_by_key(|line| -line.score));
+        for (i, line) in result.lines.iter().enumerate() {
+            assert_eq!(line.pv.len(), line.san.len());
+            assert!(
+                result.lines[..i]
+                    .iter()
+                    .all(|other| other.pv[0] != line.pv[0])
+            );
+        }
+    }
+
+    #[test]
+    fn multipv_is_capped_by_the_legal_moves() {
+        let game = game_from_fen("7k/8/8/8/8/8/r7/K7 w - - 0 1");
+        let limits = SearchLimits {
+            depth: Some(2),
+            multipv: Some(5),
+            ..SearchLimits::default()
+        };
+        let lines = Searcher::new().search_multipv(&game, &limits);
+
+        assert_eq!(game.legal_moves().count(), lines.len());
+    }
+
+    #[test]
+    fn searchmoves_leaves_out_the_other_root_moves() {
+        let game = game_from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        let rd3 = find_move(&game, "Rd3");
+        let limits = SearchLimits {
+            depth: Some(3),
+            multipv: Some(5),
+            searchmoves: vec![rd3, find_move(&game, "Ke2")],
+            ..SearchLimits::default()
+        };
+        let result = Searcher::new().with_threads(2).search(&game, &limits);
+
+        assert_ne!(Some(find_move(&game, "Rxd5")), result.best_move);
+        assert_eq!(2, result.lines.len());
+        assert!(
+            result
+                .lines
+                .iter()
+                .all(|line| limits.searchmoves.contains(&line.pv[0]))
+        );
+
+        // none of them legal is the same as none given
+        let limits = SearchLimits {
+            depth: Some(2),
+            searchmoves: vec![rd3],
+            ..SearchLimits::default()
+        };
+        let other = game_from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1");
+        assert_eq!(
+            "Rxd5",
+            standard_algebraic_notation(
+                other.clone(),
+                Searcher::new().search(&other, &limits).best_move.unwrap()
+            )
+            .as_str()
+        );
+    }
+
+    #[test]
+    fn static_exchange() {
+        for (fen, san, expected) in [
+            // undefended queen
+            ("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", "Rxd5", 900),
+            // pawn defended by a pawn
+            ("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", "Qxd5", 100 - 900),
+            // the second rook only joins in through the first one
+            ("3rk3/8/8/3n4/8/8/3R4/3RK3 w - - 0 1", "Rxd5", 320),
+            // the king can't finish off the exchange while the square is still defended
+            ("3rk3/3r4/8/8/3n4/4K3/8/3R4 w - - 0 1", "Rxd4", 320 - 500),
+            ("4k3/8/8/8/8/4p3/4K3/8 w - - 0 1", "Kxe3", 100),
+            // quiet move onto an attacked square
+            ("4k3/8/2p5/8/3N4/8/8/4K3 w - - 0 1", "Nb5", -320),
+        ] {
+            let game = game_from_fen(fen);
+            let mv = find_move(&game, san);
+
+            assert_eq!(
+                Score(expected),
+                static_exchange_evaluation(&game.core.board, mv),
+                "{fen} {san}"
+            );
+        }
+    }
+
+    #[test]
+    fn quiescence_sees_the_recapture() {
+        let game = game_from_fen("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1");
+        let result = Searcher::new().search(&game, &SearchLimits::depth(1));
+
+        assert_ne!(
+            "Qxd5",
+            standard_algebraic_notation(game, result.best_move.unwrap()).as_str()
+        );
+    }
+
+    #[test]
+    fn quiescence_score_plays_out_the_captures() {
+        let quiet = game_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let hanging = game_from_fen("4k3/8/8/3q4/8/8/3Q4/4K3 w - - 0 1");
+        let mut searcher = Searcher::new();
+
+        assert_eq!(evaluate(&quiet.core), searcher.quiescence_score(&quiet));
+        assert!(searcher.quiescence_score(&hanging) > evaluate(&hanging.core) + Score(500));
+    }
+
+    #[test]
+    fn searches_with_the_weights_it_was_given() {
+        let game = game_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let mut weights = EvalWeights::STANDARD;
+        weights.material[PieceKind::Queen.index()].endgame += 300;
+        let weights = Arc::new(weights);
+        let mut searcher = Searcher::new().with_weights(Arc::clone(&weights));
+
+        let score = searcher.quiescence_score(&game);
+        assert_eq!(evaluate_with(&game.core, &weights), score);
+        assert_ne!(evaluate(&game.core), score);
+    }
+
+    #[test]
+    fn table_carries_over_between_searches() {
+        let game =
+            game_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let mut searcher = Searcher::new();
+
+        let first = searcher.search(&game, &SearchLimits::depth(3));
+        let second = searcher.search(&game, &SearchLimits::depth(3));
+
+        assert_eq!(first.best_move, second.best_move);
+        assert!(second.nodes < first.nodes);
+        assert!(searcher.table().probe(game.core.zobrist_hash()).is_some());
+    }
+
+    #[test]
+    fn mate_scores_survive_the_table() {
+        for ply in [0, 1, 7] {
+            for score in [
+                Score(123),
+                Score::mate_in(ply + 3),
+                Score::mated_in(ply + 2),
+            ] {
+                assert_eq!(score, score_from_table(score_to_table(score, ply), ply));
+            }
+        }
+        // a mate in 3 from a node 2 plies deep is a mate in 1 from that node
+        assert_eq!(Score::mate_in(1), score_to_table(Score::mate_in(3), 2));
+    }
+
+    #[test]
+    fn node_limit() {
+        let game = GameState::new();
+        let result = Searcher::new().search(&game, &SearchLimits::nodes(500));
+
+        assert!(result.depth >= 1);
+        assert!(result.nodes <= 501, "{}", result.nodes);
+    }
+
+    #[test]
+    fn mate_limit_stops_at_the_first_short_enough_mate() {
+        let game = game_from_fen("k7/8/2K5/8/8/8/8/1R6 w - - 0 1");
+        let limits = SearchLimits {
+            mate: Some(2),
+            ..SearchLimits::default()
+        };
+        let result = Searcher::new().search(&game, &limits);
+
+        assert_eq!(Some(2), result.score.mate_moves());
+    }
+
+    /// moves on by a millisecond every time it's read
+    #[derive(Debug, Default)]
+    struct TickingClock(AtomicU64);
+    impl Clock for TickingClock {
+        fn now(&self) -> Duration {
+            Duration::from_millis(self.0.fetch_add(1, Ordering::Relaxed))
+        }
+    }
+
+    #[test]
+    fn movetime_with_injected_clock() {
+        let game = GameState::new();
+        let clock = Arc::new(TickingClock::default());
+        let mut searcher = Searcher::new().with_clock(Arc::<TickingClock>::clone(&clock));
+
+        let result = searcher.search(&game, &SearchLimits::movetime(Duration::from_millis(5)));
+
+        assert!(result.depth < MAX_PLY);
+        assert!(clock.0.load(Ordering::Relaxed) < 20);
+    }
+
+    /// pretends to be a clock to get at the stop flag while the search is running
+    #[derive(Debug)]
+    struct StoppingClock {
+        stop: Arc<AtomicBool>,
+        reads: AtomicU64,
+    }
+    impl Clock for StoppingClock {
+        fn now(&self) -> Duration {
+            if self.reads.fetch_add(1, Ordering::Relaxed) == 2 {
+                self.stop.store(true, Ordering::Relaxed);
+            }
+            Duration::ZERO
+        }
+    }
+
+    #[test]
+    fn stop_flag_ends_an_infinite_search() {
+        let game = GameState::new();
+        let mut searcher = Searcher::new();
+        let clock = StoppingClock {
+            stop: searcher.stop_flag(),
+            reads: AtomicU64::new(0),
+        };
+        searcher = searcher.with_clock(Arc::new(clock));
+
+        let limits = SearchLimits {
+            infinite: true,
+            ..SearchLimits::default()
+        };
+        let result = searcher.search(&game, &limits);
+
+        assert!(result.depth < MAX_PLY);
+        assert!(searcher.stop_flag().load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn pondering_ignores_the_clock() {
+        let game = GameState::new();
+        let mut searcher = Searcher::new().with_clock(Arc::new(TickingClock::default()));
+        searcher.ponder_flag().store(true, Ordering::Relaxed);
+
+        let limits = SearchLimits {
+            depth: Some(3),
+            movetime: Some(Duration::from_millis(5)),
+            ..SearchLimits::default()
+        };
+        let result = searcher.search(&game, &limits);
+
+        assert_eq!(3, result.depth);
+    }
+
+    #[test]
+    fn principal_variation_is_playable() {
+        let game = GameState::new();
+        let result = Searcher::new().search(&game, &SearchLimits::depth(3));
+
+        assert_eq!(result.best_move, Some(result.pv[0]));
+        let mut game = game;
+        for mv in result.pv {
+            assert!(game.legal_moves().any(|legal| legal == mv));
+            let StepResult::Ongoing(next) = game.step(mv) else {
+                panic!("the start position doesn't end this quickly");
+            };
+            game = next;
+        }
+    }
+}
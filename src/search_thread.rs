@@ -0,0 +1,245 @@
+use alloc::sync::Arc;
+use core::ops::Not;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+use std::thread::JoinHandle;
+
+use crate::game::GameState;
+use crate::game::Ongoing;
+use crate::search::SearchResult;
+use crate::search::Searcher;
+use crate::time::SearchLimits;
+
+/// what a background search reports while it runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchEvent<'a> {
+    /// another iteration is done, the search goes on
+    Iteration(&'a SearchResult),
+    /// the search is over, this is the move to play
+    Finished(&'a SearchResult),
+}
+
+/// a search running on its own thread, see [`Searcher::spawn`]
+#[derive(Debug)]
+pub struct SearchHandle {
+    thread: JoinHandle<(Searcher, SearchResult)>,
+    stop: Arc<AtomicBool>,
+    pondering: Arc<AtomicBool>,
+}
+
+impl Searcher {
+    /// lazy smp: the helpers search the same position without talking to each other, except
+    /// through the shared table. that alone makes the main searcher find more cutoffs.
+    /// only its result counts, the helpers are stopped as soon as it's done.
+    pub(crate) fn search_parallel(
+        &mut self,
+        game: &GameState<Ongoing>,
+        limits: &SearchLimits,
+        info: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        self.prepare(game, limits);
+        let helpers_stop = Arc::new(AtomicBool::new(false));
+        // the main searcher keeps the time, the helpers just follow it
+        let helper_limits = SearchLimits {
+            depth: limits.depth,
+            searchmoves: limits.searchmoves.clone(),
+            ..SearchLimits::default()
+        };
+
+        let result = std::thread::scope(|scope| {
+            for index in 1..self.threads() {
+                let depth_offset = u32::from(index % 2 == 1);
+                let mut helper = self.helper(depth_offset, Arc::clone(&helpers_stop));
+                let helper_limits = &helper_limits;
+                scope.spawn(move || {
+                    helper.prepare(game, helper_limits);
+                    helper.iterate(game, helper_limits, |_| ())
+                });
+            }
+
+            let result = self.iterate(game, limits, info);
+            helpers_stop.store(true, Ordering::Relaxed);
+            result
+        });
+
+        // the helpers only added the last of their nodes once they were done
+        let mut result = result.expect("the first iteration to never be aborted");
+        result.nodes = self.total_nodes();
+        result
+    }
+
+    /// searches `game` on a new thread, calling `on_event` from there after every iteration
+    /// and once at the end. an infinite search only finishes once it's stopped through the
+    /// handle, even if it ran out of depth before that. the searcher comes back on `join`.
+    pub fn spawn(
+        self,
+        game: GameState<Ongoing>,
+        limits: SearchLimits,
+        on_event: impl FnMut(SearchEvent<'_>) + Send + 'static,
+    ) -> SearchHandle {
+        self.spawn_with_ponder(game, limits, false, on_event)
+    }
+
+    /// like [`Searcher::spawn`], but the time limits don't start counting until
+    /// [`SearchHandle::ponderhit`]. until then it behaves like an infinite search.
+    pub fn spawn_pondering(
+        self,
+        game: GameState<Ongoing>,
+        limits: SearchLimits,
+        on_event: impl FnMut(SearchEvent<'_>) + Send + 'static,
+    ) -> SearchHandle {
+        self.spawn_with_ponder(game, limits, true, on_event)
+    }
+
+    fn spawn_with_ponder(
+        mut self,
+        game: GameState<Ongoing>,
+        limits: SearchLimits,
+        ponder: bool,
+        mut on_event: impl FnMut(SearchEvent<'_>) + Send + 'static,
+    ) -> SearchHandle {
+        let stop = self.stop_flag();
+        let pondering = self.ponder_flag();
+        // set before the thread starts, so a stop or ponderhit right after this returns counts
+        stop.store(false, Ordering::Relaxed);
+        pondering.store(ponder, Ordering::Relaxed);
+
+        let thread = {
+            let stop = Arc::clone(&stop);
+            let pondering = Arc::clone(&pondering);
+            std::thread::spawn(move || {
+                let result = self.search_with_info(&game, &limits, |result| {
+                    on_event(SearchEvent::Iteration(result));
+                });
+                // uci doesn't allow a move to be played before it's asked for in these cases.
+                // the handle unparks the thread whenever it changes one of the flags.
+                while (limits.infinite || pondering.load(Ordering::Relaxed))
+                    && stop.load(Ordering::Relaxed).not()
+                {
+                    std::thread::park();
+                }
+                on_event(SearchEvent::Finished(&result));
+                (self, result)
+            })
+        };
+
+        SearchHandle {
+            thread,
+            stop,
+            pondering,
+        }
+    }
+}
+
+impl SearchHandle {
+    /// makes the search finish as soon as it can, with the best move it found so far
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.thread().unpark();
+    }
+
+    /// the opponent played the move that was pondered on, so the clock starts counting
+    pub fn ponderhit(&self) {
+        self.pondering.store(false, Ordering::Relaxed);
+        self.thread.thread().unpark();
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// waits for the search to finish on its own, [`SearchHandle::stop`] it first to not wait long
+    #[must_use]
+    pub fn join(self) -> (Searcher, SearchResult) {
+        self.thread.join().expect("the search thread not to panic")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::sync::mpsc;
+    use std::vec;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::testing::game_from_fen;
+
+    #[test]
+    fn parallel_search_counts_the_helpers_nodes() {
+        let game = game_from_fen("k7/8/2K5/8/8/8/8/1R6 w - - 0 1");
+        let single = Searcher::new().search(&game, &SearchLimits::depth(3));
+        let mut searcher = Searcher::new().with_threads(2);
+        let parallel = searcher.search(&game, &SearchLimits::depth(3));
+
+        assert_eq!(Some(2), parallel.score.mate_moves());
+        assert!(parallel.nodes > single.nodes, "{parallel:?} {single:?}");
+        // the helpers are done for once the search returns
+        assert_eq!(parallel.nodes, searcher.total_nodes());
+    }
+
+    #[test]
+    fn parallel_search_stops_on_the_shared_stop_flag() {
+        let limits = SearchLimits {
+            infinite: true,
+            ..SearchLimits::default()
+        };
+        let handle = Searcher::new()
+            .with_threads(3)
+            .spawn(GameState::new(), limits, |_| ());
+        std::thread::sleep(Duration::from_millis(50));
+
+        handle.stop();
+        let (_, result) = handle.join();
+        assert!(result.depth >= 1);
+    }
+
+    #[test]
+    fn events_arrive_through_a_channel() {
+        let (sender, receiver) = mpsc::channel();
+        let handle =
+            Searcher::new().spawn(GameState::new(), SearchLimits::depth(2), move |event| {
+                let event = match event {
+                    SearchEvent::Iteration(result) => (false, result.depth),
+                    SearchEvent::Finished(result) => (true, result.depth),
+                };
+                sender.send(event).unwrap();
+            });
+        let (_, result) = handle.join();
+
+        assert_eq!(2, result.depth);
+        assert_eq!(
+            vec![(false, 1), (false, 2), (true, 2)],
+            receiver.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn infinite_search_waits_to_be_stopped() {
+        let limits = SearchLimits {
+            depth: Some(1),
+            infinite: true,
+            ..SearchLimits::default()
+        };
+        let handle = Searcher::new().spawn(GameState::new(), limits, |_| ());
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(handle.is_finished().not());
+
+        handle.stop();
+        let (_, result) = handle.join();
+        assert_eq!(1, result.depth);
+    }
+
+    #[test]
+    fn pondering_waits_for_the_ponderhit() {
+        let handle =
+            Searcher::new().spawn_pondering(GameState::new(), SearchLimits::depth(1), |_| ());
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(handle.is_finished().not());
+
+        handle.ponderhit();
+        let (searcher, _) = handle.join();
+        assert!(searcher.stop_flag().load(Ordering::Relaxed).not());
+    }
+}
@@ -0,0 +1,224 @@
+//! the lookup tables that turn piece placements into table indices, worked out at compile time.
+//! squares are dense indices, a1 is 0 and h8 is 63.
+
+/// the number of ways to place both kings when neither side has a piece of its own
+pub(super) const KK_PLACEMENTS: u64 = 462;
+/// the number of ways to place the first three pieces when one of them is unique
+pub(super) const UNIQUE_PLACEMENTS: u64 = 31332;
+
+pub(super) struct Maps {
+    /// the squares below the a1-h8 diagonal
+    pub(super) b1h1h7: [usize; 64],
+    /// the a1-d1-d4 triangle, the squares on the diagonal last
+    pub(super) a1d1d4: [usize; 64],
+    /// both kings, the first one in the a1-d1-d4 triangle
+    pub(super) kk: [[usize; 64]; 10],
+    /// `binomial[k][n]` is n choose k
+    pub(super) binomial: [[usize; 64]; 7],
+    /// pawns closer to the a and h files and to their own side come first
+    pub(super) pawns: [usize; 64],
+    /// by the number of leading pawns
+    pub(super) lead_pawn_index: [[usize; 64]; 6],
+    /// by the number of leading pawns and the file of the first one
+    pub(super) lead_pawns_size: [[usize; 4]; 6],
+}
+
+pub(super) static MAPS: Maps = Maps::new();
+
+/// 0 on the a1-h8 diagonal, negative below it
+pub(super) const fn off_diagonal(square: usize) -> isize {
+    rank(square).cast_signed() - file(square).cast_signed()
+}
+
+pub(super) const fn file(square: usize) -> usize {
+    square % 8
+}
+
+pub(super) const fn rank(square: usize) -> usize {
+    square / 8
+}
+
+/// a1 to h1, b2 to g2 and so on
+pub(super) const fn flip_file(square: usize) -> usize {
+    square ^ 0b111
+}
+
+/// a1 to a8, b2 to b7 and so on
+pub(super) const fn flip_rank(square: usize) -> usize {
+    square ^ 0b11_1000
+}
+
+/// mirrors along the a1-h8 diagonal
+pub(super) const fn transpose(square: usize) -> usize {
+    (square >> 3) | ((square & 0b111) << 3)
+}
+
+const fn kings_touch(a: usize, b: usize) -> bool {
+    file(a).abs_diff(file(b)) <= 1 && rank(a).abs_diff(rank(b)) <= 1
+}
+
+impl Maps {
+    const fn new() -> Self {
+        let mut maps = Self {
+            b1h1h7: [0; 64],
+            a1d1d4: [0; 64],
+            kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 7],
+            pawns: [0; 64],
+            lead_pawn_index: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+        maps.set_diagonal_maps();
+        maps.set_king_maps();
+        maps.set_binomial();
+        maps.set_pawn_maps();
+        maps
+    }
+
+    const fn set_diagonal_maps(&mut self) {
+        let mut code = 0;
+        let mut square = 0;
+        while square < 64 {
+            if off_diagonal(square) < 0 {
+                self.b1h1h7[square] = code;
+                code += 1;
+            }
+            square += 1;
+        }
+
+        let mut diagonal = [0; 4];
+        let mut diagonal_count = 0;
+        code = 0;
+        square = 0;
+        while square < 64 {
+            if file(square) <= 3 && rank(square) <= 3 {
+                if off_diagonal(square) < 0 {
+                    self.a1d1d4[square] = code;
+                    code += 1;
+                } else if off_diagonal(square) == 0 {
+                    diagonal[diagonal_count] = square;
+                    diagonal_count += 1;
+                }
+            }
+            square += 1;
+        }
+        let mut i = 0;
+        while i < diagonal_count {
+            self.a1d1d4[diagonal[i]] = code;
+            code += 1;
+            i += 1;
+        }
+    }
+
+    const fn set_king_maps(&mut self) {
+        // with both kings on the diagonal, the second one only counts once the others are done
+        let mut both_on_diagonal = [(0, 0); 32];
+        let mut both_count = 0;
+        let mut code = 0;
+        let mut index = 0;
+        while index < 10 {
+            let mut first = 0;
+            while first < 64 {
+                let in_triangle = file(first) <= 3 && rank(first) <= 3;
+                // a1 shares the 0 with every square outside the triangle, b1 stands in for it
+                if in_triangle && self.a1d1d4[first] == index && (index != 0 || first == 1) {
+                    let mut second = 0;
+                    while second < 64 {
+                        // touching kings can't happen, and the diagonal flips the second one below
+                        let impossible = kings_touch(first, second)
+                            || (off_diagonal(first) == 0 && off_diagonal(second) > 0);
+                        if impossible {
+                            // no code for these
+                        } else if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                            both_on_diagonal[both_count] = (index, second);
+                            both_count += 1;
+                        } else {
+                            self.kk[index][second] = code;
+                            code += 1;
+                        }
+                        second += 1;
+                    }
+                }
+                first += 1;
+            }
+            index += 1;
+        }
+        let mut i = 0;
+        while i < both_count {
+            let (index, second) = both_on_diagonal[i];
+            self.kk[index][second] = code;
+            code += 1;
+            i += 1;
+        }
+    }
+
+    const fn set_binomial(&mut self) {
+        self.binomial[0][0] = 1;
+        let mut n = 1;
+        while n < 64 {
+            let mut k = 0;
+            while k < 7 && k <= n {
+                let with = if k > 0 {
+                    self.binomial[k - 1][n - 1]
+                } else {
+                    0
+                };
+                let without = if k < n { self.binomial[k][n - 1] } else { 0 };
+                self.binomial[k][n] = with + without;
+                k += 1;
+            }
+            n += 1;
+        }
+    }
+
+    const fn set_pawn_maps(&mut self) {
+        let mut available = 47;
+        let mut lead = 1;
+        while lead <= 5 {
+            let mut lead_file = 0;
+            while lead_file < 4 {
+                let mut index = 0;
+                let mut lead_rank = 1;
+                while lead_rank <= 6 {
+                    let square = lead_rank * 8 + lead_file;
+                    if lead == 1 {
+                        self.pawns[square] = available;
+                        self.pawns[flip_file(square)] = available - 1;
+                        available = available.saturating_sub(2);
+                    }
+                    self.lead_pawn_index[lead][square] = index;
+                    index += self.binomial[lead - 1][self.pawns[square]];
+                    lead_rank += 1;
+                }
+                self.lead_pawns_size[lead][lead_file] = index;
+                lead_file += 1;
+            }
+            lead += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn king_placements() {
+        let codes = MAPS.kk.iter().flatten().copied().max().unwrap() + 1;
+        assert_eq!(KK_PLACEMENTS, codes as u64);
+        assert_eq!(9, MAPS.a1d1d4[27]);
+        assert_eq!(27, MAPS.b1h1h7[55]);
+    }
+
+    #[test]
+    fn pawn_order() {
+        // a2 first, then h2, then a3, file by file up to d7 and e7
+        assert_eq!(
+            [47, 46, 45],
+            [MAPS.pawns[8], MAPS.pawns[15], MAPS.pawns[16]]
+        );
+        assert_eq!([1, 0], [MAPS.pawns[51], MAPS.pawns[52]]);
+        assert_eq!(6, MAPS.lead_pawns_size[1][0]);
+        assert_eq!(35, MAPS.binomial[3][7]);
+    }
+}
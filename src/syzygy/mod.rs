@@ -0,0 +1,543 @@
+//! probing syzygy endgame tablebases, the `.rtbw` (win, draw or loss) and `.rtbz` (distance to
+//! the next capture or pawn move) files of up to six pieces.
+//!
+//! tables are read from disk whole, the first time a position needs them. positions with
+//! castling rights or fairy pieces are never in a table.
+
+mod encoding;
+mod table;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Neg;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use table::MAX_PIECES;
+use table::Material;
+use table::Probe;
+use table::Table;
+use table::TableKind;
+
+use crate::game::CastlingRights;
+use crate::game::GameStateCore;
+use crate::mv::Move;
+use crate::piece::PieceKind;
+use crate::player::PlayerKind;
+
+/// the result with best play, for the side to move. cursed wins and blessed losses are the
+/// ones the fifty move rule turns into draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Neg for Wdl {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        match self {
+            Self::Loss => Self::Win,
+            Self::BlessedLoss => Self::CursedWin,
+            Self::Draw => Self::Draw,
+            Self::CursedWin => Self::BlessedLoss,
+            Self::Win => Self::Loss,
+        }
+    }
+}
+
+impl Wdl {
+    const fn signum(self) -> i32 {
+        match self {
+            Self::Loss | Self::BlessedLoss => -1,
+            Self::Draw => 0,
+            Self::CursedWin | Self::Win => 1,
+        }
+    }
+
+    /// the dtz of a position where the best move captures or moves a pawn
+    const fn dtz_before_zeroing(self) -> i32 {
+        match self {
+            Self::Loss => -1,
+            Self::BlessedLoss => -101,
+            Self::Draw => 0,
+            Self::CursedWin => 101,
+            Self::Win => 1,
+        }
+    }
+}
+
+/// a legal move of the probed position, and what it leads to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RootMove {
+    pub mv: Move,
+    /// for the side playing the move, with the fifty move rule clock counted in
+    pub wdl: Wdl,
+    /// plies until the next capture or pawn move, counting this one, positive when winning
+    pub dtz: i32,
+}
+
+/// the tables found in a directory
+#[derive(Debug, Default)]
+pub struct Tablebase {
+    /// by name, like `KRPvKR`
+    tables: BTreeMap<String, TableFiles>,
+    max_pieces: usize,
+}
+
+#[derive(Debug, Default)]
+struct TableFiles {
+    wdl: Option<LazyTable>,
+    dtz: Option<LazyTable>,
+}
+
+#[derive(Debug)]
+struct LazyTable {
+    path: PathBuf,
+    kind: TableKind,
+    material: Material,
+    /// `None` if the file can't be read, or isn't a table
+    table: OnceLock<Option<Table>>,
+}
+
+impl LazyTable {
+    fn get(&self) -> Option<&Table> {
+        self.table
+            .get_or_init(|| {
+                let bytes = std::fs::read(&self.path).ok()?;
+                Table::parse(bytes, self.kind, self.material.clone())
+            })
+            .as_ref()
+    }
+}
+
+impl Tablebase {
+    /// finds the tables in `directory`, they're only read once a position needs them
+    pub fn open(directory: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut tablebase = Self::default();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            let (Some(name), Some(extension)) = (
+                path.file_stem().and_then(OsStr::to_str),
+                path.extension().and_then(OsStr::to_str),
+            ) else {
+                continue;
+            };
+            let kind = match extension {
+                "rtbw" => TableKind::Wdl,
+                "rtbz" => TableKind::Dtz,
+                _ => continue,
+            };
+            let Some(material) = Material::from_name(name) else {
+                continue;
+            };
+            if material.piece_count > MAX_PIECES {
+                continue;
+            }
+
+            tablebase.max_pieces = tablebase.max_pieces.max(material.piece_count);
+            let files = tablebase.tables.entry(String::from(name)).or_default();
+            let table = Some(LazyTable {
+                path,
+                kind,
+                material,
+                table: OnceLock::new(),
+            });
+            match kind {
+                TableKind::Wdl => files.wdl = table,
+                TableKind::Dtz => files.dtz = table,
+            }
+        }
+        Ok(tablebase)
+    }
+
+    /// the most pieces, kings included, of any table found
+    #[must_use]
+    pub const fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// the result of `core` with best play, ignoring the fifty move rule clock.
+    /// `None` if there's no table for it.
+    #[must_use]
+    pub fn probe_wdl(&self, core: &GameStateCore) -> Option<Wdl> {
+        if !self.covers(core) {
+            return None;
+        }
+        self.search(core, false).map(|(wdl, _)| wdl)
+    }
+
+    /// plies until the next capture or pawn move with best play, positive if the side to move
+    /// wins. wins and losses that come too late for the fifty move rule are beyond 100.
+    /// can be off by one, the tables sometimes round up to even plies.
+    #[must_use]
+    pub fn probe_dtz(&self, core: &GameStateCore) -> Option<i32> {
+        if !self.covers(core) {
+            return None;
+        }
+        self.dtz(core)
+    }
+
+    /// every legal move of `core`, and where it leads
+    #[must_use]
+    pub fn probe_root_moves(&self, core: &GameStateCore) -> Option<Vec<RootMove>> {
+        if !self.covers(core) {
+            return None;
+        }
+        let clock = core.fifty_move_rule_clock.0;
+        core.legal_moves()
+            .map(|mv| {
+                let next = play(core, mv);
+                let dtz = if is_mate(&next) {
+                    1
+                } else if mv.is_pawn_or_capture() {
+                    (-self.search(&next, false)?.0).dtz_before_zeroing()
+                } else {
+                    let dtz = -self.dtz(&next)?;
+                    dtz + dtz.signum()
+                };
+
+                let in_time = clock + u64::from(dtz.unsigned_abs()) < 100;
+                let wdl = match dtz.signum() {
+                    1 if in_time => Wdl::Win,
+                    1 => Wdl::CursedWin,
+                    -1 if in_time => Wdl::Loss,
+                    -1 => Wdl::BlessedLoss,
+                    _ => Wdl::Draw,
+                };
+                Some(RootMove { mv, wdl, dtz })
+            })
+            .collect()
+    }
+
+    /// the move that keeps the best result the fifty move rule allows: the fastest win, or the
+    /// slowest loss
+    #[must_use]
+    pub fn probe_root(&self, core: &GameStateCore) -> Option<RootMove> {
+        self.probe_root_moves(core)?
+            .into_iter()
+            .max_by_key(|root| (root.wdl, -root.dtz))
+    }
+
+    fn covers(&self, core: &GameStateCore) -> bool {
+        core.castling_rights == CastlingRights::none_available()
+            && core.board.pieces().count() <= self.max_pieces
+    }
+
+    /// the wdl of `core`, and whether the best move is a capture, or a pawn move if those are
+    /// searched as well. the tables don't know about captures when a capture wins, or when
+    /// the position is lost anyway, so those are searched instead.
+    fn search(&self, core: &GameStateCore, pawn_moves_too: bool) -> Option<(Wdl, bool)> {
+        let moves = core.legal_moves().collect::<Vec<_>>();
+        if moves.is_empty() {
+            let wdl = if core.board.is_king_checked(core.active_player) {
+                Wdl::Loss
+            } else {
+                Wdl::Draw
+            };
+            return Some((wdl, false));
+        }
+
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for mv in &moves {
+            let zeroing =
+                mv.is_capture() || (pawn_moves_too && mv.kind.piece_kind() == PieceKind::Pawn);
+            if !zeroing {
+                continue;
+            }
+            searched += 1;
+            let wdl = -self.search(&play(core, *mv), false)?.0;
+            if wdl > best {
+                best = wdl;
+                if wdl == Wdl::Win {
+                    return Some((wdl, true));
+                }
+            }
+        }
+
+        let all_searched = searched == moves.len();
+        let wdl = if all_searched {
+            best
+        } else {
+            match self.probe_table(core, TableKind::Wdl, Wdl::Draw)? {
+                Probe::Wdl(wdl) => wdl,
+                Probe::Dtz(_) | Probe::OtherSideToMove => return None,
+            }
+        };
+        if best >= wdl {
+            Some((best, best > Wdl::Draw || all_searched))
+        } else {
+            Some((wdl, false))
+        }
+    }
+
+    fn dtz(&self, core: &GameStateCore) -> Option<i32> {
+        let (wdl, zeroing_is_best) = self.search(core, true)?;
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+        if is_mate(core) {
+            return Some(-1);
+        }
+        if zeroing_is_best {
+            return Some(wdl.dtz_before_zeroing());
+        }
+
+        match self.probe_table(core, TableKind::Dtz, wdl)? {
+            Probe::Dtz(dtz) => {
+                let beyond_fifty_moves = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+                Some((dtz + if beyond_fifty_moves { 100 } else { 0 }) * wdl.signum())
+            }
+            Probe::OtherSideToMove => {
+                // one ply deeper, the shortest dtz that keeps the result. when losing, that's
+                // the most negative one.
+                let mut best = None;
+                for mv in core.legal_moves() {
+                    let next = play(core, mv);
+                    let dtz = if mv.is_pawn_or_capture() {
+                        (-self.search(&next, false)?.0).dtz_before_zeroing()
+                    } else if is_mate(&next) {
+                        1
+                    } else {
+                        let dtz = -self.dtz(&next)?;
+                        dtz + dtz.signum()
+                    };
+                    if dtz.signum() == wdl.signum() && best.is_none_or(|best| dtz < best) {
+                        best = Some(dtz);
+                    }
+                }
+                Some(best.unwrap_or(-1))
+            }
+            Probe::Wdl(_) => None,
+        }
+    }
+
+    fn probe_table(&self, core: &GameStateCore, kind: TableKind, wdl: Wdl) -> Option<Probe> {
+        let mut pieces = Vec::new();
+        let mut names = [String::new(), String::new()];
+        for (square, piece) in core.board.pieces() {
+            let number = match piece.kind {
+                PieceKind::Pawn => 1,
+                PieceKind::Knight => 2,
+                PieceKind::Bishop => 3,
+                PieceKind::Rook => 4,
+                PieceKind::Queen => 5,
+                PieceKind::King => 6,
+                _ => return None,
+            };
+            let black = piece.owner == PlayerKind::Black;
+            pieces.push((
+                u8::try_from(square.index()).ok()?,
+                number + if black { 8 } else { 0 },
+            ));
+            names[usize::from(black)].push(char::from(b"PNBRQK"[usize::from(number - 1)]));
+        }
+        if pieces.len() == 2 {
+            return Some(match kind {
+                TableKind::Wdl => Probe::Wdl(Wdl::Draw),
+                TableKind::Dtz => Probe::Dtz(0),
+            });
+        }
+
+        // the table names go from the strongest piece down
+        let order = |piece: char| "KQRBNP".find(piece);
+        for name in &mut names {
+            let mut sorted = name.chars().collect::<Vec<_>>();
+            sorted.sort_by_key(|piece| order(*piece));
+            *name = sorted.into_iter().collect();
+        }
+        let [white, black] = names;
+        let (name, mut flip) = match format!("{white}v{black}") {
+            name if self.tables.contains_key(&name) => (name, false),
+            _ => (format!("{black}v{white}"), true),
+        };
+        // symmetric tables are stored with white to move
+        if white == black && core.active_player == PlayerKind::Black {
+            flip = true;
+        }
+
+        let files = self.tables.get(&name)?;
+        let table = match kind {
+            TableKind::Wdl => files.wdl.as_ref(),
+            TableKind::Dtz => files.dtz.as_ref(),
+        }?;
+        table
+            .get()?
+            .probe(&pieces, core.active_player == PlayerKind::Black, flip, wdl)
+    }
+}
+
+/// `core` after `mv`, without ending the game on a draw like [`GameState::step`] would
+///
+/// [`GameState::step`]: crate::game::GameState::step
+fn play(core: &GameStateCore, mv: Move) -> GameStateCore {
+    let mut next = *core;
+    next.board.apply_move(mv);
+    next.en_passant_target = mv.kind.is_pawn_double_step().then(|| {
+        (mv.destination + core.active_player.backwards_one_row())
+            .expect("the square a pawn skipped to be on the board")
+    });
+    if mv.is_pawn_or_capture() {
+        next.fifty_move_rule_clock.reset();
+    } else {
+        next.fifty_move_rule_clock.increase();
+    }
+    next.with_opponent_active()
+}
+
+fn is_mate(core: &GameStateCore) -> bool {
+    core.board.is_king_checked(core.active_player) && core.legal_moves().next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::uci::parse_uci_move;
+    use crate::testing::game_from_fen;
+
+    /// a tablebase in a directory of its own, removed again once the test is done with it
+    struct TempTablebase {
+        tablebase: Tablebase,
+        directory: std::path::PathBuf,
+    }
+
+    impl core::ops::Deref for TempTablebase {
+        type Target = Tablebase;
+
+        fn deref(&self) -> &Tablebase {
+            &self.tablebase
+        }
+    }
+
+    impl Drop for TempTablebase {
+        fn drop(&mut self) {
+            // a leftover directory in the temp dir isn't worth failing a test over
+            let _ = std::fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    /// `KRvK` tables that know nothing but that white to move wins, in 7 plies, and black to
+    /// move loses. a real table would know better, but it's enough to see what's probed.
+    fn tablebase(test: &str) -> TempTablebase {
+        let directory =
+            std::env::temp_dir().join(format!("schach-syzygy-{test}-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let pieces = [1, 0x00, 0x66, 0x44, 0xEE, 0];
+        let mut wdl = [0x71, 0xE8, 0x23, 0x5D]
+            .into_iter()
+            .chain(pieces)
+            .collect::<Vec<_>>();
+        wdl.extend([0x80, 4, 0x80, 0]);
+        wdl.resize(64, 0);
+        let mut dtz = [0xD7, 0x66, 0x0C, 0xA5]
+            .into_iter()
+            .chain(pieces)
+            .collect::<Vec<_>>();
+        dtz.extend([0x80, 3]);
+        dtz.resize(64, 0);
+
+        std::fs::write(directory.join("KRvK.rtbw"), wdl).unwrap();
+        std::fs::write(directory.join("KRvK.rtbz"), dtz).unwrap();
+        std::fs::write(directory.join("README.txt"), "not a table").unwrap();
+        TempTablebase {
+            tablebase: Tablebase::open(&directory).unwrap(),
+            directory,
+        }
+    }
+
+    #[test]
+    fn wdl() {
+        let tablebase = tablebase("wdl");
+        assert_eq!(3, tablebase.max_pieces());
+
+        let probe = |fen| tablebase.probe_wdl(&game_from_fen(fen).core);
+        assert_eq!(Some(Wdl::Win), probe("k7/8/1K6/8/8/8/8/7R w - - 0 1"));
+        assert_eq!(Some(Wdl::Loss), probe("k7/8/1K6/8/8/8/8/7R b - - 0 1"));
+        // the colors flipped
+        assert_eq!(Some(Wdl::Win), probe("7r/8/8/8/8/1k6/8/K7 b - - 0 1"));
+        // the rook is lost, which the table doesn't know
+        assert_eq!(Some(Wdl::Draw), probe("kR6/8/8/8/8/8/8/7K b - - 0 1"));
+        assert_eq!(Some(Wdl::Draw), probe("k7/8/8/8/8/8/8/7K w - - 0 1"));
+
+        assert_eq!(None, probe("k7/8/1K6/8/8/8/8/7Q w - - 0 1"));
+        assert_eq!(None, probe("k7/8/1K6/8/8/8/8/R3K3 w Q - 0 1"));
+    }
+
+    #[test]
+    fn dtz() {
+        let tablebase = tablebase("dtz");
+        let probe = |fen| tablebase.probe_dtz(&game_from_fen(fen).core);
+        assert_eq!(Some(7), probe("k7/8/1K6/8/8/8/8/7R w - - 0 1"));
+        // only white to move is stored, black's only move leads there
+        assert_eq!(Some(-8), probe("k7/8/1K6/8/8/8/8/7R b - - 0 1"));
+        assert_eq!(Some(-1), probe("k6R/8/1K6/8/8/8/8/8 b - - 0 1"));
+        assert_eq!(Some(0), probe("kR6/8/8/8/8/8/8/7K b - - 0 1"));
+    }
+
+    #[test]
+    fn root_moves() {
+        let tablebase = tablebase("root");
+        let root = |fen, uci| {
+            let game = game_from_fen(fen);
+            let mv = parse_uci_move(&game, uci).unwrap();
+            let moves = tablebase.probe_root_moves(&game.core).unwrap();
+            let root = *moves.iter().find(|root| root.mv == mv).unwrap();
+            (root.wdl, root.dtz)
+        };
+
+        let fen = "k7/8/1K6/8/8/8/8/7R w - - 0 1";
+        assert_eq!((Wdl::Win, 1), root(fen, "h1h8"));
+        assert_eq!((Wdl::Win, 9), root(fen, "h1h7"));
+        let best = tablebase.probe_root(&game_from_fen(fen).core).unwrap();
+        assert_eq!((Wdl::Win, 1), (best.wdl, best.dtz));
+
+        let fen = "k7/8/2K5/8/8/8/8/1R6 w - - 95 60";
+        // stalemate
+        assert_eq!((Wdl::Draw, 0), root(fen, "b1b7"));
+        // too slow for the fifty move rule
+        assert_eq!((Wdl::CursedWin, 9), root(fen, "b1b2"));
+        let best = tablebase.probe_root(&game_from_fen(fen).core).unwrap();
+        assert_eq!((Wdl::CursedWin, 9), (best.wdl, best.dtz));
+    }
+
+    /// the real `KRvK` and `KPvK` tables, from the directory in `SCHACH_SYZYGY_PATH`
+    #[test]
+    fn real_tables() {
+        let Ok(directory) = std::env::var("SCHACH_SYZYGY_PATH") else {
+            std::println!(
+                "skipped without real tables, set env var SCHACH_SYZYGY_PATH to a directory \
+                 with KRvK and KPvK in it to run this"
+            );
+            return;
+        };
+        let tablebase = Tablebase::open(directory).unwrap();
+        let wdl = |fen| tablebase.probe_wdl(&game_from_fen(fen).core);
+        let dtz = |fen| tablebase.probe_dtz(&game_from_fen(fen).core);
+
+        // mate in one, and black only moving into it
+        assert_eq!(Some(Wdl::Win), wdl("k7/8/1K6/8/8/8/8/7R w - - 0 1"));
+        assert_eq!(Some(1), dtz("k7/8/1K6/8/8/8/8/7R w - - 0 1"));
+        assert_eq!(Some(Wdl::Loss), wdl("k7/8/1K6/8/8/8/8/7R b - - 0 1"));
+        assert_eq!(Some(-2), dtz("k7/8/1K6/8/8/8/8/7R b - - 0 1"));
+        // the rook hangs
+        assert_eq!(Some(Wdl::Draw), wdl("kR6/8/8/8/8/8/8/7K b - - 0 1"));
+        assert_eq!(Some(0), dtz("kR6/8/8/8/8/8/8/7K b - - 0 1"));
+
+        // the king on the sixth in front of its pawn wins whoever is to move
+        assert_eq!(Some(Wdl::Win), wdl("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"));
+        assert_eq!(Some(Wdl::Loss), wdl("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"));
+        // further back it's down to who has the opposition
+        assert_eq!(Some(Wdl::Draw), wdl("8/4k3/8/4K3/4P3/8/8/8 w - - 0 1"));
+        assert_eq!(Some(Wdl::Loss), wdl("8/4k3/8/4K3/4P3/8/8/8 b - - 0 1"));
+    }
+}
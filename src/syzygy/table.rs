@@ -0,0 +1,896 @@
+//! reading the `.rtbw` and `.rtbz` files: the header, the piece placement index and the
+//! compressed values behind it
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::Wdl;
+use super::encoding::KK_PLACEMENTS;
+use super::encoding::MAPS;
+use super::encoding::UNIQUE_PLACEMENTS;
+use super::encoding::file;
+use super::encoding::flip_file;
+use super::encoding::flip_rank;
+use super::encoding::off_diagonal;
+use super::encoding::rank;
+use super::encoding::transpose;
+
+pub(super) const MAX_PIECES: usize = 6;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+// how a single table is stored
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+/// what the name of a table, like `KRPvKR`, says about its pieces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Material {
+    pub(super) piece_count: usize,
+    has_pawns: bool,
+    /// some piece kind only one side has exactly one of
+    has_unique_pieces: bool,
+    /// both sides have the same pieces, one table covers both sides to move
+    symmetric: bool,
+    /// the pawns of the side the pawn placement starts with, then the other side's.
+    /// that's the side with fewer pawns, as long as it has any.
+    pawn_counts: [usize; 2],
+}
+
+impl Material {
+    /// the white pieces first, each side starting with its king
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+        let valid = |side: &str| {
+            side.strip_prefix('K')
+                .is_some_and(|rest| rest.bytes().all(|piece| b"QRBNP".contains(&piece)))
+        };
+        if !valid(white) || !valid(black) {
+            return None;
+        }
+
+        let count = |side: &str, piece: u8| side.bytes().filter(|b| *b == piece).count();
+        let pawns = [count(white, b'P'), count(black, b'P')];
+        let white_leads = pawns[1] == 0 || (pawns[0] > 0 && pawns[0] <= pawns[1]);
+        Some(Self {
+            piece_count: white.len() + black.len(),
+            has_pawns: pawns != [0, 0],
+            has_unique_pieces: [white, black]
+                .into_iter()
+                .any(|side| b"QRBNP".iter().any(|piece| count(side, *piece) == 1)),
+            symmetric: white == black,
+            pawn_counts: if white_leads {
+                pawns
+            } else {
+                [pawns[1], pawns[0]]
+            },
+        })
+    }
+}
+
+/// the result of looking a position up in a single table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Probe {
+    Wdl(Wdl),
+    /// plies to the next capture or pawn move, not signed yet
+    Dtz(i32),
+    /// dtz tables only store one side to move, the other one takes a search
+    OtherSideToMove,
+}
+
+/// one side to move, with the leading pawn on one file
+#[derive(Debug, Clone, Default)]
+struct PairsData {
+    flags: u8,
+    block_size: usize,
+    span: u64,
+    block_count: usize,
+    /// or the only value there is, with [`SINGLE_VALUE`]
+    min_symbol_length: u8,
+    // all of these are offsets into the file
+    lowest_symbols: usize,
+    tree: usize,
+    block_lengths: usize,
+    sparse_index: usize,
+    blocks: usize,
+
+    block_lengths_count: usize,
+    sparse_index_count: usize,
+    /// the lowest code of every symbol length, left aligned
+    base: Vec<u64>,
+    /// how many values, minus one, a symbol stands for
+    symbol_lengths: Vec<u8>,
+    /// the table's piece codes, in the order they're encoded
+    pieces: [u8; MAX_PIECES],
+    /// where each group of pieces starts in the index
+    group_indices: [u64; MAX_PIECES + 1],
+    /// pieces in each group, the first empty group marks the end
+    group_lengths: [usize; MAX_PIECES + 1],
+    /// where the map of each wdl starts, for dtz tables
+    dtz_map_indices: [usize; 4],
+}
+
+pub(super) struct Table {
+    bytes: Vec<u8>,
+    kind: TableKind,
+    material: Material,
+    /// by the file of the leading pawn, just one without pawns, then by the side to move.
+    /// dtz tables and symmetric wdl tables only have one side.
+    pairs: Vec<Vec<PairsData>>,
+    dtz_map: usize,
+}
+
+impl fmt::Debug for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("kind", &self.kind)
+            .field("material", &self.material)
+            .field("len", &self.bytes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+fn read<const N: usize>(bytes: &[u8], at: usize) -> Option<[u8; N]> {
+    bytes.get(at..at.checked_add(N)?)?.try_into().ok()
+}
+
+/// like [`read`], but zeros past the end of the file
+fn read_padded<const N: usize>(bytes: &[u8], at: usize) -> [u8; N] {
+    let mut padded = [0; N];
+    for (i, byte) in padded.iter_mut().enumerate() {
+        *byte = bytes.get(at + i).copied().unwrap_or(0);
+    }
+    padded
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    read(bytes, at).map(u16::from_le_bytes)
+}
+
+/// the children of a symbol in the huffman tree, 12 bits each
+fn tree_node(bytes: &[u8], tree: usize, symbol: usize) -> Option<(usize, usize)> {
+    let [a, b, c] = read::<3>(bytes, tree.checked_add(3 * symbol)?)?;
+    let left = (usize::from(b & 0xF) << 8) | usize::from(a);
+    let right = (usize::from(c) << 4) | usize::from(b >> 4);
+    Some((left, right))
+}
+
+impl Table {
+    /// `None` if the file doesn't look like a table for `material`
+    pub(super) fn parse(bytes: Vec<u8>, kind: TableKind, material: Material) -> Option<Self> {
+        let magic = match kind {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        };
+        if read::<4>(&bytes, 0)? != magic || material.piece_count > MAX_PIECES {
+            return None;
+        }
+        let header = *bytes.get(4)?;
+        if (header & 1 == 0) != material.symmetric || (header & 2 != 0) != material.has_pawns {
+            return None;
+        }
+
+        let sides = if kind == TableKind::Wdl && !material.symmetric {
+            2
+        } else {
+            1
+        };
+        let files = if material.has_pawns { 4 } else { 1 };
+        let both_have_pawns = material.pawn_counts[1] > 0;
+        let mut pairs = vec![vec![PairsData::default(); sides]; files];
+
+        let mut at = 5;
+        for (file, file_pairs) in pairs.iter_mut().enumerate() {
+            let order = [
+                *bytes.get(at)?,
+                if both_have_pawns {
+                    *bytes.get(at + 1)?
+                } else {
+                    0xFF
+                },
+            ];
+            at += 1 + usize::from(both_have_pawns);
+            for k in 0..material.piece_count {
+                let byte = *bytes.get(at + k)?;
+                for (side, data) in file_pairs.iter_mut().enumerate() {
+                    data.pieces[k] = if side == 0 { byte & 0xF } else { byte >> 4 };
+                }
+            }
+            at += material.piece_count;
+            for (side, data) in file_pairs.iter_mut().enumerate() {
+                let order = if side == 0 {
+                    [order[0] & 0xF, order[1] & 0xF]
+                } else {
+                    [order[0] >> 4, order[1] >> 4]
+                };
+                data.set_groups(&material, order, file)?;
+            }
+        }
+        at += at & 1;
+
+        for data in pairs.iter_mut().flatten() {
+            at = data.set_sizes(&bytes, at)?;
+        }
+
+        let dtz_map = at;
+        if kind == TableKind::Dtz {
+            for file_pairs in &mut pairs {
+                at = file_pairs[0].set_dtz_map(&bytes, dtz_map, at)?;
+            }
+            at += at & 1;
+        }
+
+        for data in pairs.iter_mut().flatten() {
+            data.sparse_index = at;
+            at = at.checked_add(data.sparse_index_count.checked_mul(6)?)?;
+        }
+        for data in pairs.iter_mut().flatten() {
+            data.block_lengths = at;
+            at = at.checked_add(data.block_lengths_count.checked_mul(2)?)?;
+        }
+        for data in pairs.iter_mut().flatten() {
+            at = at.checked_add(0x3F)? & !0x3F;
+            data.blocks = at;
+            at = at.checked_add(data.block_count.checked_mul(data.block_size)?)?;
+        }
+        if at > bytes.len() {
+            return None;
+        }
+
+        Some(Self {
+            bytes,
+            kind,
+            material,
+            pairs,
+            dtz_map,
+        })
+    }
+
+    /// `pieces` are squares and piece codes, white ones 1 to 6 from pawn to king, black ones 9
+    /// to 14. `flip` swaps the colors and mirrors the board, for positions where black has the
+    /// pieces the table's name starts with.
+    pub(super) fn probe(
+        &self,
+        pieces: &[(u8, u8)],
+        black_to_move: bool,
+        flip: bool,
+        wdl: Wdl,
+    ) -> Option<Probe> {
+        if pieces.len() != self.material.piece_count {
+            return None;
+        }
+        let pieces = pieces.iter().map(|(square, code)| {
+            let square = usize::from(*square);
+            if flip {
+                (flip_rank(square), code ^ 8)
+            } else {
+                (square, *code)
+            }
+        });
+        let side_to_move = usize::from(black_to_move != flip);
+
+        let mut squares = [0; MAX_PIECES];
+        let mut codes = [0; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_count = 0;
+        let mut lead_file = 0;
+
+        let lead = self.pairs[0][0].pieces[0];
+        if self.material.has_pawns {
+            for (square, code) in pieces.clone().filter(|(_, code)| *code == lead) {
+                squares[size] = square;
+                codes[size] = code;
+                size += 1;
+            }
+            lead_count = size;
+            let first = (0..lead_count).max_by_key(|i| MAPS.pawns[squares[*i]])?;
+            squares.swap(0, first);
+            let first_file = file(squares[0]);
+            lead_file = first_file.min(7 - first_file);
+        }
+
+        let sides = &self.pairs[lead_file];
+        let both_sides_stored = self.material.symmetric && !self.material.has_pawns;
+        let stored = both_sides_stored || usize::from(sides[0].flags & STM) == side_to_move;
+        if self.kind == TableKind::Dtz && !stored {
+            return Some(Probe::OtherSideToMove);
+        }
+
+        for (square, code) in pieces.filter(|(_, code)| !self.material.has_pawns || *code != lead) {
+            *squares.get_mut(size)? = square;
+            codes[size] = code;
+            size += 1;
+        }
+
+        let data = &sides[if sides.len() == 1 { 0 } else { side_to_move }];
+        // the same order as the table, where pieces of one kind can go in any order
+        for i in lead_count..size {
+            if let Some(j) = (i..size).find(|j| codes[*j] == data.pieces[i]) {
+                codes.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+        if codes[lead_count..size] != data.pieces[lead_count..size] {
+            return None;
+        }
+
+        let index = encode(data, &self.material, &mut squares[..size], lead_count);
+        let value = self.decompress(data, index)?;
+        match self.kind {
+            TableKind::Wdl => Wdl::from_table(value).map(Probe::Wdl),
+            TableKind::Dtz => self.dtz_plies(data, value, wdl).map(Probe::Dtz),
+        }
+    }
+
+    fn dtz_plies(&self, data: &PairsData, value: u16, wdl: Wdl) -> Option<i32> {
+        let mut value = usize::from(value);
+        if data.flags & MAPPED != 0 {
+            let map = self.dtz_map
+                + data.dtz_map_indices[match wdl {
+                    Wdl::Win | Wdl::Draw => 0,
+                    Wdl::Loss => 1,
+                    Wdl::CursedWin => 2,
+                    Wdl::BlessedLoss => 3,
+                }];
+            value = if data.flags & WIDE != 0 {
+                usize::from(read_u16(&self.bytes, map + 2 * value)?)
+            } else {
+                usize::from(*self.bytes.get(map + value)?)
+            };
+        }
+
+        // unless the table says otherwise, it only stores every other ply
+        let in_plies = match wdl {
+            Wdl::Win => data.flags & WIN_PLIES != 0,
+            Wdl::Loss => data.flags & LOSS_PLIES != 0,
+            Wdl::Draw => true,
+            Wdl::CursedWin | Wdl::BlessedLoss => false,
+        };
+        let value = i32::try_from(value).ok()?;
+        Some(if in_plies { value } else { value * 2 } + 1)
+    }
+
+    /// the value at `index`. the values are split into blocks that are compressed on their own,
+    /// the sparse index points close to the right block and offset.
+    fn decompress(&self, data: &PairsData, index: u64) -> Option<u16> {
+        if data.flags & SINGLE_VALUE != 0 {
+            return Some(u16::from(data.min_symbol_length));
+        }
+        let bytes = self.bytes.as_slice();
+
+        let entry = data.sparse_index + 6 * usize::try_from(index / data.span).ok()?;
+        let mut block = usize::try_from(u32::from_le_bytes(read(bytes, entry)?)).ok()?;
+        let mut offset = i64::from(read_u16(bytes, entry + 4)?)
+            + i64::try_from(index % data.span).ok()?
+            - i64::try_from(data.span / 2).ok()?;
+
+        // the stored lengths are one short, no block is empty
+        let block_length = |block: usize| {
+            let at = data.block_lengths + 2 * block;
+            read_u16(bytes, at).map(|length| i64::from(length) + 1)
+        };
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)?;
+        }
+        while offset >= block_length(block)? {
+            offset -= block_length(block)?;
+            block += 1;
+        }
+
+        let mut at = data.blocks + block * data.block_size;
+        let mut buffer = u64::from_be_bytes(read_padded(bytes, at));
+        at += 8;
+        let mut buffer_bits: u32 = 64;
+        let min_length = u32::from(data.min_symbol_length);
+
+        let mut symbol = loop {
+            let mut length = 0;
+            while buffer < *data.base.get(length)? {
+                length += 1;
+            }
+            let length_bits = u32::try_from(length).ok()? + min_length;
+            let code = (buffer - data.base[length]).checked_shr(64 - length_bits)?;
+            let lowest = read_u16(bytes, data.lowest_symbols + 2 * length)?;
+            let symbol = usize::from(u16::try_from(code).ok()?.wrapping_add(lowest));
+
+            let values = i64::from(*data.symbol_lengths.get(symbol)?) + 1;
+            if offset < values {
+                break symbol;
+            }
+            offset -= values;
+            buffer = buffer.checked_shl(length_bits).unwrap_or(0);
+            buffer_bits = buffer_bits.checked_sub(length_bits)?;
+            if buffer_bits <= 32 {
+                buffer_bits += 32;
+                buffer |=
+                    u64::from(u32::from_be_bytes(read_padded(bytes, at))) << (64 - buffer_bits);
+                at += 4;
+            }
+        };
+
+        // a symbol can stand for a pair of symbols, each of which can be a pair again
+        while *data.symbol_lengths.get(symbol)? != 0 {
+            let (left, right) = tree_node(bytes, data.tree, symbol)?;
+            let left_values = i64::from(*data.symbol_lengths.get(left)?) + 1;
+            if offset < left_values {
+                symbol = left;
+            } else {
+                offset -= left_values;
+                symbol = right;
+            }
+        }
+        let (value, _) = tree_node(bytes, data.tree, symbol)?;
+        u16::try_from(value).ok()
+    }
+}
+
+/// the index of a placement, `squares` in the table's piece order. mirrors the board so the
+/// leading piece ends up on the left half, or in the a1-d1-d4 triangle without pawns.
+fn encode(data: &PairsData, material: &Material, squares: &mut [usize], lead_count: usize) -> u64 {
+    if file(squares[0]) > 3 {
+        for square in squares.iter_mut() {
+            *square = flip_file(*square);
+        }
+    }
+
+    let mut index;
+    if material.has_pawns {
+        index = MAPS.lead_pawn_index[lead_count][squares[0]] as u64;
+        squares[1..lead_count].sort_by_key(|square| MAPS.pawns[*square]);
+        for (i, square) in squares.iter().enumerate().take(lead_count).skip(1) {
+            index += MAPS.binomial[i][MAPS.pawns[*square]] as u64;
+        }
+    } else {
+        if rank(squares[0]) > 3 {
+            for square in squares.iter_mut() {
+                *square = flip_rank(*square);
+            }
+        }
+        // the first piece off the diagonal has to end up below it
+        for i in 0..data.group_lengths[0] {
+            match off_diagonal(squares[i]) {
+                0 => continue,
+                off if off > 0 => {
+                    for square in &mut squares[i..] {
+                        *square = transpose(*square);
+                    }
+                }
+                _ => {}
+            }
+            break;
+        }
+
+        index = if material.has_unique_pieces {
+            encode_unique(squares)
+        } else {
+            MAPS.kk[MAPS.a1d1d4[squares[0]]][squares[1]] as u64
+        };
+    }
+    index *= data.group_indices[0];
+
+    // every other group only needs the squares left over, in no particular order
+    let mut start = data.group_lengths[0];
+    let mut other_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+    for group in 1.. {
+        let length = data.group_lengths[group];
+        if length == 0 {
+            break;
+        }
+        squares[start..start + length].sort_unstable();
+        let mut group_index = 0;
+        for i in 0..length {
+            let square = squares[start + i];
+            let taken = squares[..start].iter().filter(|s| square > **s).count();
+            let free = square - taken - if other_pawns { 8 } else { 0 };
+            group_index += MAPS.binomial[i + 1][free] as u64;
+        }
+        other_pawns = false;
+        index += group_index * data.group_indices[group];
+        start += length;
+    }
+    index
+}
+
+/// the first three pieces, with the first one in the a1-d1-d4 triangle
+fn encode_unique(squares: &[usize]) -> u64 {
+    let [a, b, c] = [squares[0], squares[1], squares[2]];
+    let b_past = usize::from(b > a);
+    let c_past = usize::from(c > a) + usize::from(c > b);
+    let index = if off_diagonal(a) != 0 {
+        (MAPS.a1d1d4[a] * 63 + (b - b_past)) * 62 + c - c_past
+    } else if off_diagonal(b) != 0 {
+        (6 * 63 + rank(a) * 28 + MAPS.b1h1h7[b]) * 62 + c - c_past
+    } else if off_diagonal(c) != 0 {
+        6 * 63 * 62 + 4 * 28 * 62 + rank(a) * 7 * 28 + (rank(b) - b_past) * 28 + MAPS.b1h1h7[c]
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank(a) * 7 * 6 + (rank(b) - b_past) * 6 + rank(c)
+            - c_past
+    };
+    index as u64
+}
+
+impl PairsData {
+    /// splits the pieces into the groups they're encoded in, and works out the table size
+    fn set_groups(&mut self, material: &Material, order: [u8; 2], lead_file: usize) -> Option<()> {
+        // the leading group: pawns of one side, or the first three or two pieces
+        let mut first_length: i32 = if material.has_pawns {
+            0
+        } else if material.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+        let mut groups = 0;
+        self.group_lengths[0] = 1;
+        for i in 1..material.piece_count {
+            first_length -= 1;
+            if first_length > 0 || self.pieces[i] == self.pieces[i - 1] {
+                self.group_lengths[groups] += 1;
+            } else {
+                groups += 1;
+                self.group_lengths[groups] = 1;
+            }
+        }
+        groups += 1;
+        *self.group_lengths.get_mut(groups)? = 0;
+
+        // the pawns of the other side go second, the other groups in any order the file likes
+        let both_have_pawns = material.pawn_counts[1] > 0;
+        let mut next = if both_have_pawns { 2 } else { 1 };
+        let mut free_squares = 64
+            - self.group_lengths[0]
+            - if both_have_pawns {
+                self.group_lengths[1]
+            } else {
+                0
+            };
+        let mut size: u64 = 1;
+        let mut k = 0;
+        while next < groups || k == order[0] || k == order[1] {
+            if k >= 0xF {
+                return None;
+            }
+            if k == order[0] {
+                self.group_indices[0] = size;
+                size *= if material.has_pawns {
+                    MAPS.lead_pawns_size.get(self.group_lengths[0])?[lead_file] as u64
+                } else if material.has_unique_pieces {
+                    UNIQUE_PLACEMENTS
+                } else {
+                    KK_PLACEMENTS
+                };
+            } else if k == order[1] {
+                self.group_indices[1] = size;
+                size *=
+                    MAPS.binomial.get(self.group_lengths[1])?[48 - self.group_lengths[0]] as u64;
+            } else {
+                *self.group_indices.get_mut(next)? = size;
+                size *= MAPS.binomial.get(self.group_lengths[next])?[free_squares] as u64;
+                free_squares -= self.group_lengths[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        self.group_indices[groups] = size;
+        Some(())
+    }
+
+    /// the huffman code, returns where the next table's starts
+    fn set_sizes(&mut self, bytes: &[u8], at: usize) -> Option<usize> {
+        self.flags = *bytes.get(at)?;
+        if self.flags & SINGLE_VALUE != 0 {
+            self.min_symbol_length = *bytes.get(at + 1)?;
+            return Some(at + 2);
+        }
+
+        let groups = self.group_lengths.iter().position(|length| *length == 0)?;
+        let size = self.group_indices[groups];
+        self.block_size = 1_usize.checked_shl(u32::from(*bytes.get(at + 1)?))?;
+        self.span = 1_u64.checked_shl(u32::from(*bytes.get(at + 2)?))?;
+        self.sparse_index_count = usize::try_from(size.div_ceil(self.span)).ok()?;
+        let padding = usize::from(*bytes.get(at + 3)?);
+        self.block_count = usize::try_from(u32::from_le_bytes(read(bytes, at + 4)?)).ok()?;
+        self.block_lengths_count = self.block_count + padding;
+        let max_length = *bytes.get(at + 8)?;
+        self.min_symbol_length = *bytes.get(at + 9)?;
+        self.lowest_symbols = at + 10;
+
+        // canonical huffman: the codes of each length follow the ones of the next longer length
+        let lengths = usize::from(max_length.checked_sub(self.min_symbol_length)?) + 1;
+        let lowest =
+            |length: usize| read_u16(bytes, self.lowest_symbols + 2 * length).map(u64::from);
+        self.base = vec![0; lengths];
+        for length in (0..lengths - 1).rev() {
+            self.base[length] = self.base[length + 1]
+                .wrapping_add(lowest(length)?)
+                .wrapping_sub(lowest(length + 1)?)
+                / 2;
+        }
+        for (length, base) in self.base.iter_mut().enumerate() {
+            let bits = u32::try_from(length).ok()? + u32::from(self.min_symbol_length);
+            *base = base.checked_shl(64_u32.checked_sub(bits)?).unwrap_or(0);
+        }
+
+        let at = self.lowest_symbols + 2 * lengths;
+        let symbols = usize::from(read_u16(bytes, at)?);
+        self.tree = at + 2;
+        self.symbol_lengths = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for symbol in 0..symbols {
+            if !visited[symbol] {
+                self.symbol_lengths[symbol] = self.symbol_length(bytes, symbol, &mut visited)?;
+            }
+        }
+        Some(self.tree + 3 * symbols + (symbols & 1))
+    }
+
+    fn symbol_length(&mut self, bytes: &[u8], symbol: usize, visited: &mut [bool]) -> Option<u8> {
+        visited[symbol] = true;
+        let (left, right) = tree_node(bytes, self.tree, symbol)?;
+        if right == 0xFFF {
+            return Some(0);
+        }
+        for child in [left, right] {
+            if !*visited.get(child)? {
+                self.symbol_lengths[child] = self.symbol_length(bytes, child, visited)?;
+            }
+        }
+        Some(
+            self.symbol_lengths[left]
+                .wrapping_add(self.symbol_lengths[right])
+                .wrapping_add(1),
+        )
+    }
+
+    /// dtz tables can store small indices into a map of the actual plies, one map per wdl.
+    /// returns where the maps of the next file start.
+    fn set_dtz_map(&mut self, bytes: &[u8], dtz_map: usize, mut at: usize) -> Option<usize> {
+        if self.flags & MAPPED == 0 {
+            return Some(at);
+        }
+        let wide = self.flags & WIDE != 0;
+        if wide {
+            at += at & 1;
+        }
+        for index in &mut self.dtz_map_indices {
+            let (entries, width) = if wide {
+                (usize::from(read_u16(bytes, at)?), 2)
+            } else {
+                (usize::from(*bytes.get(at)?), 1)
+            };
+            // the map starts after its length
+            *index = at - dtz_map + width;
+            at += width * (entries + 1);
+        }
+        Some(at)
+    }
+}
+
+impl Wdl {
+    const fn from_table(value: u16) -> Option<Self> {
+        Some(match value {
+            0 => Self::Loss,
+            1 => Self::BlessedLoss,
+            2 => Self::Draw,
+            3 => Self::CursedWin,
+            4 => Self::Win,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+    use alloc::collections::BTreeSet;
+
+    use super::*;
+
+    fn pairs_data(name: &str, pieces: &[u8], lead_file: usize) -> (Material, PairsData) {
+        let material = Material::from_name(name).unwrap();
+        let mut data = PairsData::default();
+        data.pieces[..pieces.len()].copy_from_slice(pieces);
+        data.set_groups(&material, [0, 0xF], lead_file).unwrap();
+        (material, data)
+    }
+
+    fn size(data: &PairsData) -> u64 {
+        data.group_indices[data.group_lengths.iter().position(|l| *l == 0).unwrap()]
+    }
+
+    /// the 8 ways to turn and mirror the board
+    fn symmetries(square: usize) -> [usize; 8] {
+        let transposed = transpose(square);
+        [0, 7, 56, 63]
+            .map(|flip| square ^ flip)
+            .into_iter()
+            .chain([0, 7, 56, 63].map(|flip| transposed ^ flip))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap()
+    }
+
+    /// every placement has the same index as its mirror images, and no other placement does
+    fn assert_encoding(
+        name: &str,
+        pieces: &[u8],
+        placements: impl Iterator<Item = Vec<usize>>,
+        symmetry_count: usize,
+    ) {
+        let mut classes = BTreeMap::new();
+        for squares in placements {
+            let lead_count = usize::from(pieces[0] == 1);
+            let lead_file = file(squares[0]).min(7 - file(squares[0]));
+            let (material, data) = pairs_data(name, pieces, lead_file);
+
+            let images = (0..symmetry_count)
+                .map(|symmetry| {
+                    squares
+                        .iter()
+                        .map(|square| symmetries(*square)[symmetry])
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let index = encode(&data, &material, &mut squares.clone(), lead_count);
+            assert!(index < size(&data), "{squares:?}");
+            for image in &images {
+                let mut image = image.clone();
+                assert_eq!(
+                    index,
+                    encode(&data, &material, &mut image, lead_count),
+                    "{squares:?}"
+                );
+            }
+
+            let class = images.into_iter().min().unwrap();
+            let previous = classes.insert((lead_file, index), class.clone());
+            assert!(
+                previous.is_none_or(|previous| previous == class),
+                "{squares:?}"
+            );
+        }
+    }
+
+    /// a `KRvK` table where white to move has `value(index)` at every index, with black to move
+    /// always lost. the values are coded in 3 bits, and 5 stands for two wins in a row.
+    fn huffman_table(value: impl Fn(u64) -> u8) -> Vec<u8> {
+        const SPAN: u64 = 256;
+        const BLOCK_SIZE: usize = 64;
+
+        let mut symbols = Vec::new();
+        let mut index = 0;
+        while index < UNIQUE_PLACEMENTS {
+            if value(index) == 4 && index + 1 < UNIQUE_PLACEMENTS && value(index + 1) == 4 {
+                symbols.push(5);
+                index += 2;
+            } else {
+                symbols.push(value(index));
+                index += 1;
+            }
+        }
+        let blocks = symbols.chunks(BLOCK_SIZE * 8 / 3).collect::<Vec<_>>();
+        let block_values = blocks
+            .iter()
+            .map(|block| {
+                block
+                    .iter()
+                    .map(|symbol| if *symbol == 5 { 2 } else { 1 })
+                    .sum()
+            })
+            .collect::<Vec<u64>>();
+        let starts = block_values
+            .iter()
+            .scan(0, |start, values| {
+                *start += values;
+                Some(*start - values)
+            })
+            .collect::<Vec<_>>();
+
+        // the header, the pieces of both sides and a byte to align
+        let mut bytes = vec![0x71, 0xE8, 0x23, 0x5D, 1, 0x00, 0x66, 0x44, 0xEE, 0];
+        bytes.extend([0, 6, 8, 0]);
+        bytes.extend(u32::try_from(blocks.len()).unwrap().to_le_bytes());
+        bytes.extend([3, 3, 0, 0, 6, 0]);
+        for symbol in 0..5 {
+            bytes.extend([symbol, 0xF0, 0xFF]);
+        }
+        bytes.extend([4, 0x40, 0]);
+        bytes.extend([SINGLE_VALUE, 0]);
+
+        for k in 0..UNIQUE_PLACEMENTS.div_ceil(SPAN) {
+            let middle = k * SPAN + SPAN / 2;
+            let block = starts.partition_point(|start| *start <= middle) - 1;
+            bytes.extend(u32::try_from(block).unwrap().to_le_bytes());
+            bytes.extend(u16::try_from(middle - starts[block]).unwrap().to_le_bytes());
+        }
+        for values in block_values {
+            bytes.extend(u16::try_from(values - 1).unwrap().to_le_bytes());
+        }
+        bytes.resize(bytes.len().next_multiple_of(64), 0);
+        for block in blocks {
+            let mut data = [0; BLOCK_SIZE];
+            for (i, symbol) in block.iter().enumerate() {
+                for bit in 0..3 {
+                    if symbol >> (2 - bit) & 1 == 1 {
+                        data[(i * 3 + bit) / 8] |= 0x80 >> ((i * 3 + bit) % 8);
+                    }
+                }
+            }
+            bytes.extend(data);
+        }
+        bytes
+    }
+
+    #[test]
+    fn huffman_decoding() {
+        let value = |index: u64| {
+            let value = if index % 11 < 4 {
+                4
+            } else {
+                (index * 7) ^ (index >> 3)
+            };
+            u8::try_from(value % 5).unwrap()
+        };
+        let material = Material::from_name("KRvK").unwrap();
+        let table = Table::parse(huffman_table(value), TableKind::Wdl, material).unwrap();
+
+        for index in (0..UNIQUE_PLACEMENTS).step_by(7) {
+            let decoded = table.decompress(&table.pairs[0][0], index);
+            assert_eq!(Some(u16::from(value(index))), decoded, "{index}");
+        }
+        assert_eq!(Some(0), table.decompress(&table.pairs[0][1], 1234));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let bytes = huffman_table(|_| 2);
+        let material = Material::from_name("KRvK").unwrap();
+        assert!(Table::parse(bytes.clone(), TableKind::Dtz, material.clone()).is_none());
+        assert!(Table::parse(bytes[..200].to_vec(), TableKind::Wdl, material).is_none());
+        let symmetric = Material::from_name("KRvKR").unwrap();
+        assert!(Table::parse(bytes, TableKind::Wdl, symmetric).is_none());
+    }
+
+    #[test]
+    fn kings_only() {
+        let placements = (0..64)
+            .flat_map(|a| (0..64).map(move |b| vec![a, b]))
+            .filter(|squares| {
+                file(squares[0]).abs_diff(file(squares[1])) > 1
+                    || rank(squares[0]).abs_diff(rank(squares[1])) > 1
+            });
+        assert_encoding("KvK", &[6, 14], placements, 8);
+    }
+
+    #[test]
+    fn unique_piece() {
+        let placements =
+            (0..64).flat_map(|a| (0..64).flat_map(move |b| (0..64).map(move |c| vec![a, b, c])));
+        let placements =
+            placements.filter(|squares| squares.iter().collect::<BTreeSet<_>>().len() == 3);
+        assert_encoding("KRvK", &[6, 4, 14], placements, 8);
+    }
+
+    #[test]
+    fn pawns() {
+        let placements =
+            (8..56).flat_map(|a| (0..64).flat_map(move |b| (0..64).map(move |c| vec![a, b, c])));
+        let placements =
+            placements.filter(|squares| squares.iter().collect::<BTreeSet<_>>().len() == 3);
+        // only the files can be mirrored
+        assert_encoding("KPvK", &[1, 6, 14], placements, 2);
+    }
+}
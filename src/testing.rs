@@ -1,3 +1,7 @@
+use crate::game::GameState;
+use crate::game::GameStateCore;
+use crate::game::Ongoing;
+
 macro_rules! env_var {
     () => {
         "SCHACH_EXPENSIVE_TEST_OPT_IN"
@@ -20,3 +24,8 @@ macro_rules! skip_if_no_expensive_test_opt_in {
 
 pub(crate) use env_var;
 pub(crate) use skip_if_no_expensive_test_opt_in;
+
+/// the game from `fen`, which has to be a valid one
+pub fn game_from_fen(fen: &str) -> GameState<Ongoing> {
+    GameState::with_core(GameStateCore::try_from_fen(fen).unwrap())
+}
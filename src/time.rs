@@ -0,0 +1,249 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::mv::Move;
+use crate::player::PlayerKind;
+
+/// a monotonic time source, so the search can keep time without `std`.
+/// only differences between two readings matter, not where it counts from.
+pub trait Clock: core::fmt::Debug + Send + Sync {
+    fn now(&self) -> Duration;
+}
+
+#[cfg(any(test, feature = "std"))]
+#[derive(Debug, Clone, Copy)]
+pub struct StdClock(std::time::Instant);
+
+#[cfg(any(test, feature = "std"))]
+impl StdClock {
+    #[must_use]
+    pub fn new() -> Self {
+        Self(std::time::Instant::now())
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl Clock for StdClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// what a search is allowed to spend, mirrors the arguments of the uci `go` command.
+/// everything left at `None` is unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    /// moves until the next time control, sudden death if `None`
+    pub movestogo: Option<u32>,
+    /// stop as soon as a mate in this many moves is found
+    pub mate: Option<u32>,
+    /// ignore the clock and keep going until stopped from outside
+    pub infinite: bool,
+    /// how many of the best root moves to find a line for, just the best one if `None`
+    pub multipv: Option<u32>,
+    /// only these root moves are searched, all of them if it's empty or none of them is legal
+    pub searchmoves: Vec<Move>,
+}
+
+impl SearchLimits {
+    #[must_use]
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn movetime(movetime: Duration) -> Self {
+        Self {
+            movetime: Some(movetime),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            nodes: Some(nodes),
+            ..Self::default()
+        }
+    }
+
+    const fn clock_for(&self, player: PlayerKind) -> (Option<Duration>, Option<Duration>) {
+        match player {
+            PlayerKind::White => (self.wtime, self.winc),
+            PlayerKind::Black => (self.btime, self.binc),
+        }
+    }
+}
+
+/// deadlines counted from the start of the search.
+/// past the soft one no new iteration is started, past the hard one the search is aborted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeManager {
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+}
+
+impl TimeManager {
+    /// kept back from the clock for the time it takes to actually send the move
+    const OVERHEAD: Duration = Duration::from_millis(10);
+    /// how many moves the rest of the clock has to last in sudden death
+    const SUDDEN_DEATH_MOVES: u32 = 30;
+
+    #[must_use]
+    pub fn new(limits: &SearchLimits, player: PlayerKind) -> Self {
+        if limits.infinite {
+            return Self::unlimited();
+        }
+        // a fixed time per move is meant to be used up, however sure the search already is
+        if let Some(movetime) = limits.movetime {
+            return Self {
+                soft: None,
+                hard: Some(movetime.saturating_sub(Self::OVERHEAD)),
+            };
+        }
+
+        let (Some(time), increment) = limits.clock_for(player) else {
+            return Self::unlimited();
+        };
+        let increment = increment.unwrap_or_default();
+        let available = time.saturating_sub(Self::OVERHEAD);
+        let moves_to_go = limits
+            .movestogo
+            .unwrap_or(Self::SUDDEN_DEATH_MOVES)
+            .clamp(1, Self::SUDDEN_DEATH_MOVES);
+
+        let soft = (available / moves_to_go + increment * 3 / 4).min(available);
+        // never bet more than half the clock on a single move, unless it's the last one
+        let hard_cap = if moves_to_go == 1 {
+            available
+        } else {
+            available / 2
+        };
+        let hard = (soft * 4).min(hard_cap).max(soft.min(available));
+
+        Self {
+            soft: Some(soft),
+            hard: Some(hard),
+        }
+    }
+
+    #[must_use]
+    pub const fn unlimited() -> Self {
+        Self {
+            soft: None,
+            hard: None,
+        }
+    }
+
+    /// whether to start another iteration. `stable_iterations` is how many iterations in a row
+    /// came up with the same best move; a stable one gets less time, a changing one more.
+    #[must_use]
+    pub fn should_start_iteration(&self, elapsed: Duration, stable_iterations: u32) -> bool {
+        let Some(soft) = self.soft else {
+            return true;
+        };
+        let percent = 150_u32.saturating_sub(stable_iterations * 20).max(50);
+        let soft = (soft * percent / 100).min(self.hard.unwrap_or(Duration::MAX));
+        elapsed < soft
+    }
+
+    #[must_use]
+    pub fn is_past_hard_deadline(&self, elapsed: Duration) -> bool {
+        self.hard.is_some_and(|hard| elapsed >= hard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ops::Not;
+
+    use super::*;
+
+    const fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn movetime_only_has_a_hard_deadline() {
+        let manager = TimeManager::new(&SearchLimits::movetime(ms(1000)), PlayerKind::White);
+
+        assert!(manager.should_start_iteration(ms(900), 10));
+        assert!(manager.is_past_hard_deadline(ms(989)).not());
+        assert!(manager.is_past_hard_deadline(ms(990)));
+    }
+
+    #[test]
+    fn clock_uses_own_side() {
+        let limits = SearchLimits {
+            wtime: Some(ms(60_000)),
+            btime: Some(ms(1_000)),
+            ..SearchLimits::default()
+        };
+        let white = TimeManager::new(&limits, PlayerKind::White);
+        let black = TimeManager::new(&limits, PlayerKind::Black);
+
+        assert!(white.should_start_iteration(ms(1_500), 0));
+        assert!(black.should_start_iteration(ms(100), 0).not());
+        assert!(black.is_past_hard_deadline(ms(500)));
+        assert!(white.is_past_hard_deadline(ms(500)).not());
+    }
+
+    #[test]
+    fn stable_best_move_stops_earlier() {
+        let limits = SearchLimits {
+            wtime: Some(ms(30_000)),
+            winc: Some(ms(1_000)),
+            ..SearchLimits::default()
+        };
+        let manager = TimeManager::new(&limits, PlayerKind::White);
+
+        assert!(manager.should_start_iteration(ms(1_600), 0));
+        assert!(manager.should_start_iteration(ms(1_600), 5).not());
+    }
+
+    #[test]
+    fn last_move_before_time_control_may_use_everything() {
+        let limits = SearchLimits {
+            btime: Some(ms(2_000)),
+            movestogo: Some(1),
+            ..SearchLimits::default()
+        };
+        let manager = TimeManager::new(&limits, PlayerKind::Black);
+
+        assert!(manager.is_past_hard_deadline(ms(1_900)).not());
+        assert!(manager.is_past_hard_deadline(ms(2_000)));
+    }
+
+    #[test]
+    fn unlimited_without_a_clock() {
+        for limits in [
+            SearchLimits::default(),
+            SearchLimits::depth(5),
+            SearchLimits {
+                infinite: true,
+                wtime: Some(ms(10)),
+                ..SearchLimits::default()
+            },
+        ] {
+            let manager = TimeManager::new(&limits, PlayerKind::White);
+            assert_eq!(TimeManager::unlimited(), manager);
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use alloc::vec::Vec;
+use core::ops::Not;
+use core::sync::atomic::AtomicU8;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+use crate::coord::Square;
+use crate::eval::Score;
+use crate::game::GameStateCore;
+use crate::game::RuleSet;
+use crate::mv::Move;
+
+/// which side of the search window the stored score was on
+#[derive_const(PartialEq, Eq)]
+#[derive(Debug, Copy, Clone)]
+pub enum Bound {
+    /// the real score, the search ended inside its window
+    Exact,
+    /// the real score is at least this, the search failed high
+    Lower,
+    /// the real score is at most this, the search failed low
+    Upper,
+}
+
+/// decides whether a new entry may overwrite the one already in its slot.
+/// entries for the same position are always overwritten.
+#[derive_const(PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Default)]
+pub enum ReplacementScheme {
+    Always,
+    /// keep whichever entry was searched deeper
+    DepthPreferred,
+    /// like `DepthPreferred`, but anything left over from an earlier search can go
+    #[default]
+    AgedDepthPreferred,
+}
+
+/// a move reduced to origin, destination and promotion, 16 bits.
+/// only meaningful together with the position it was played in.
+#[derive_const(PartialEq, Eq)]
+#[derive(Debug, Copy, Clone)]
+pub struct PackedMove(u16);
+impl PackedMove {
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn new(mv: Move) -> Self {
+        let promotion = match mv.kind.promotion_replacement() {
+            Some(replacement) => replacement.kind.index() + 1,
+            None => 0,
+        };
+        Self((mv.origin.index() | mv.destination.index() << 6 | promotion << 12) as u16)
+    }
+
+    #[must_use]
+    pub const fn origin(self) -> Square {
+        Square::from_index((self.0 & 0b11_1111) as usize).expect("6 bits to always be a square")
+    }
+
+    #[must_use]
+    pub const fn matches(self, mv: Move) -> bool {
+        self == Self::new(mv)
+    }
+
+    /// the legal move in `core` this was packed from, if there is one.
+    /// after a hash collision there might not be.
+    #[must_use]
+    pub fn unpack(self, core: &GameStateCore, rule_set: RuleSet) -> Option<Move> {
+        core.move_candidates_from(self.origin(), rule_set)
+            .find(|mv| self.matches(*mv))
+            .filter(|mv| core.is_legal_for(*mv, rule_set))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TtEntry {
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: Score,
+    pub best_move: Option<PackedMove>,
+    /// the `TranspositionTable::new_search` generation it was stored in
+    pub age: u8,
+}
+impl TtEntry {
+    const AGE_BITS: u32 = 6;
+
+    // move: 16 bits, score: 32, depth: 8, bound: 2 (0 is an empty slot), age: 6
+    #[allow(clippy::cast_sign_loss)]
+    const fn pack(self) -> u64 {
+        let mv = match self.best_move {
+            Some(PackedMove(mv)) => mv as u64,
+            None => 0,
+        };
+        let bound: u64 = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+        mv | (self.score.0 as u32 as u64) << 16
+            | (self.depth as u64) << 48
+            | bound << 56
+            | ((self.age as u64) & ((1 << Self::AGE_BITS) - 1)) << 58
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    const fn unpack(data: u64) -> Option<Self> {
+        let bound = match (data >> 56) & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        let mv = data as u16;
+        Some(Self {
+            depth: (data >> 48) as u8,
+            bound,
+            score: Score((data >> 16) as u32 as i32),
+            best_move: if mv == 0 { None } else { Some(PackedMove(mv)) },
+            age: (data >> 58) as u8,
+        })
+    }
+}
+
+/// the stored key is xor-ed with the data, so a slot torn by two threads writing at once
+/// doesn't verify against either hash and reads as empty instead of as garbage.
+#[derive(Debug, Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// fixed-size hash table from [`crate::game::GameStateCore::zobrist_hash`] to search results.
+/// it only takes `&self`, so one table can be shared between threads without locking.
+#[derive(Debug)]
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    replacement_scheme: ReplacementScheme,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    pub const DEFAULT_MEGABYTES: usize = 16;
+
+    /// the largest power of two number of slots that fits into `megabytes`, but at least one
+    #[must_use]
+    pub fn new(megabytes: usize, replacement_scheme: ReplacementScheme) -> Self {
+        let budget = megabytes * 1024 * 1024 / size_of::<Slot>();
+        let len = if budget == 0 { 1 } else { 1 << budget.ilog2() };
+        Self {
+            slots: (0..len).map(|_| Slot::default()).collect(),
+            replacement_scheme,
+            age: AtomicU8::new(0),
+        }
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    #[must_use]
+    pub const fn replacement_scheme(&self) -> ReplacementScheme {
+        self.replacement_scheme
+    }
+
+    /// entries stored from now on count as the new generation, the old ones get replaced first
+    pub fn new_search(&self) {
+        let next = (self.age() + 1) & ((1 << TtEntry::AGE_BITS) - 1);
+        self.age.store(next, Ordering::Relaxed);
+    }
+
+    fn age(&self) -> u8 {
+        self.age.load(Ordering::Relaxed)
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    const fn slot(&self, hash: u64) -> &Slot {
+        &self.slots[hash as usize & (self.slots.len() - 1)]
+    }
+
+    #[must_use]
+    pub fn probe(&self, hash: u64) -> Option<TtEntry> {
+        let slot = self.slot(hash);
+        let data = slot.data.load(Ordering::Relaxed);
+        if slot.key.load(Ordering::Relaxed) ^ data != hash {
+            return None;
+        }
+        TtEntry::unpack(data)
+    }
+
+    /// `entry.age` is ignored, it always gets the current generation
+    pub fn store(&self, hash: u64, mut entry: TtEntry) {
+        entry.age = self.age();
+        let slot = self.slot(hash);
+
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let old_hash = slot.key.load(Ordering::Relaxed) ^ old_data;
+        if let Some(old) = TtEntry::unpack(old_data)
+            && old_hash != hash
+        {
+            let replace = match self.replacement_scheme {
+                ReplacementScheme::Always => true,
+                ReplacementScheme::DepthPreferred => entry.depth >= old.depth,
+                ReplacementScheme::AgedDepthPreferred => {
+                    old.age != entry.age || entry.depth >= old.depth
+                }
+            };
+            if replace.not() {
+                return;
+            }
+        }
+
+        // an entry for the same position without a move still knows the move we had before
+        if entry.best_move.is_none()
+            && old_hash == hash
+            && let Some(old) = TtEntry::unpack(old_data)
+        {
+            entry.best_move = old.best_move;
+        }
+
+        let data = entry.pack();
+        slot.key.store(hash ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// how full the table is in permille, estimated from the first thousand slots
+    #[must_use]
+    pub fn hashfull(&self) -> usize {
+        let age = self.age();
+        let sample = &self.slots[..self.slots.len().min(1000)];
+        let used = sample
+            .iter()
+            .filter_map(|slot| TtEntry::unpack(slot.data.load(Ordering::Relaxed)))
+            .filter(|entry| entry.age == age)
+            .count();
+        used * 1000 / sample.len()
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MEGABYTES, ReplacementScheme::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameStateCore;
+
+    fn entry(depth: u8, score: i32) -> TtEntry {
+        TtEntry {
+            depth,
+            bound: Bound::Exact,
+            score: Score(score),
+            best_move: None,
+            age: 0,
+        }
+    }
+
+    #[test]
+    fn size_is_a_power_of_two_within_budget() {
+        let table = TranspositionTable::new(3, ReplacementScheme::Always);
+        assert!(table.len().is_power_of_two());
+        assert!(table.len() * size_of::<Slot>() <= 3 * 1024 * 1024);
+        assert!(table.len() * 2 * size_of::<Slot>() > 3 * 1024 * 1024);
+
+        assert_eq!(
+            1,
+            TranspositionTable::new(0, ReplacementScheme::Always).len()
+        );
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let table = TranspositionTable::new(1, ReplacementScheme::Always);
+        let core = GameStateCore::default();
+        let hash = core.zobrist_hash();
+        let mv = core.legal_moves().last().unwrap();
+
+        assert_eq!(None, table.probe(hash));
+
+        let stored = TtEntry {
+            depth: 7,
+            bound: Bound::Upper,
+            score: -Score::mate_in(3),
+            best_move: Some(PackedMove::new(mv)),
+            age: 0,
+        };
+        table.store(hash, stored);
+
+        assert_eq!(Some(stored), table.probe(hash));
+        assert_eq!(None, table.probe(hash ^ 1));
+        assert_eq!(
+            Some(mv),
+            PackedMove::new(mv).unpack(&core, RuleSet::Standard)
+        );
+        let elsewhere = core.with_opponent_active();
+        assert_eq!(
+            None,
+            PackedMove::new(mv).unpack(&elsewhere, RuleSet::Standard)
+        );
+    }
+
+    #[test]
+    fn replacement_schemes() {
+        // both hashes land in the only slot
+        let (first, second) = (1, 2);
+
+        for (scheme, shallow_replaces_deep) in [
+            (ReplacementScheme::Always, true),
+            (ReplacementScheme::DepthPreferred, false),
+            (ReplacementScheme::AgedDepthPreferred, false),
+        ] {
+            let table = TranspositionTable::new(0, scheme);
+            table.store(first, entry(5, 10));
+            table.store(second, entry(2, 20));
+
+            assert_eq!(
+                shallow_replaces_deep,
+                table.probe(second).is_some(),
+                "{scheme:?}"
+            );
+            assert_eq!(
+                shallow_replaces_deep,
+                table.probe(first).is_none(),
+                "{scheme:?}"
+            );
+        }
+
+        let table = TranspositionTable::new(0, ReplacementScheme::AgedDepthPreferred);
+        table.store(first, entry(5, 10));
+        table.new_search();
+        table.store(second, entry(2, 20));
+        assert_eq!(Some(20), table.probe(second).map(|entry| entry.score.0));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn shared_between_threads() {
+        use rayon::prelude::*;
+
+        let table = TranspositionTable::new(0, ReplacementScheme::Always);
+
+        (0..10_000_u64).into_par_iter().for_each(|i| {
+            let hash = i % 7;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            table.store(hash, entry(hash as u8, hash as i32 * 100));
+            // whatever we read back has to be one whole entry, never two half ones
+            if let Some(read) = table.probe(hash) {
+                assert_eq!(i64::from(read.depth) * 100, i64::from(read.score.0));
+            }
+        });
+    }
+}
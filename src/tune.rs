@@ -0,0 +1,317 @@
+//! texel tuning: fitting the [`EvalWeights`] to the results of the games their positions were
+//! played in, by gradient descent on how far the evaluation's winning chances are off
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::eval::EvalTrace;
+use crate::eval::EvalWeights;
+use crate::eval::MAX_PHASE;
+use crate::eval::TaperedScore;
+use crate::eval::Term;
+use crate::eval::evaluate_with;
+use crate::eval::trace;
+use crate::eval::win_chance;
+use crate::game::GameState;
+use crate::game::GameStateCore;
+use crate::notation::fen::GameFromFenError;
+use crate::search::Searcher;
+
+#[derive(Debug)]
+pub enum LabelledPositionError {
+    MalformedFen(GameFromFenError),
+    MissingResult,
+}
+
+/// a position and the result of its game, from whites point of view: 1 for a win, 0.5 for a draw
+///
+/// the line is a fen, or the four fields of an epd, with the result after it: `1-0`, `0-1` or
+/// `1/2-1/2`, quoted or not, or `[1.0]`, `[0.5]` or `[0.0]`.
+pub fn parse_labelled_position(line: &str) -> Result<(GameStateCore, f64), LabelledPositionError> {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    let result = fields
+        .iter()
+        .skip(4)
+        .find_map(
+            |field| match field.trim_matches(|c| matches!(c, '"' | ';' | '[' | ']')) {
+                "1-0" | "1.0" => Some(1.0),
+                "0-1" | "0.0" => Some(0.0),
+                "1/2-1/2" | "0.5" => Some(0.5),
+                _ => None,
+            },
+        )
+        .ok_or(LabelledPositionError::MissingResult)?;
+
+    let counters = match fields.get(4..6) {
+        Some([halfmoves, fullmoves])
+            if halfmoves.parse::<u64>().is_ok() && fullmoves.parse::<u64>().is_ok() =>
+        {
+            [*halfmoves, *fullmoves]
+        }
+        _ => ["0", "1"],
+    };
+    let fen = fields
+        .iter()
+        .take(4)
+        .chain(&counters)
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let core = GameStateCore::try_from_fen(&fen).map_err(LabelledPositionError::MalformedFen)?;
+    Ok((core, result))
+}
+
+/// nobody is in check and the quiescence search agrees with the evaluation, so the evaluation
+/// alone says what the position is worth. both use the searcher's weights.
+pub fn is_quiet(searcher: &mut Searcher, core: &GameStateCore) -> bool {
+    !core.board.is_king_checked(core.active_player)
+        && searcher.quiescence_score(&GameState::with_core(*core))
+            == evaluate_with(core, searcher.weights())
+}
+
+#[derive(Debug, Clone)]
+struct Position {
+    trace: EvalTrace,
+    /// from whites point of view
+    result: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tuner {
+    positions: Vec<Position>,
+    /// the weights being tuned, middlegame and endgame for every [`Term`]
+    weights: Vec<[f64; 2]>,
+    /// `k` in `win_chance(k * score)`, how sure a score is about the result
+    scaling: f64,
+    /// the weights before tuning, for the phase and everything else the terms don't cover
+    start: EvalWeights,
+    /// adam's running averages of the gradient and of its square
+    momentum: Vec<[f64; 2]>,
+    velocity: Vec<[f64; 2]>,
+    steps: i32,
+}
+
+impl Tuner {
+    /// tunes `weights`, on positions with their results from whites point of view
+    #[must_use]
+    pub fn new(positions: &[(GameStateCore, f64)], weights: &EvalWeights) -> Self {
+        Self {
+            positions: positions
+                .iter()
+                .map(|(core, result)| Position {
+                    trace: trace(core, weights),
+                    result: *result,
+                })
+                .collect(),
+            weights: Term::all()
+                .map(|term| {
+                    let TaperedScore {
+                        middlegame,
+                        endgame,
+                    } = weights.term(term);
+                    [f64::from(middlegame), f64::from(endgame)]
+                })
+                .collect(),
+            scaling: 1.0,
+            start: weights.clone(),
+            momentum: vec![[0.0; 2]; Term::COUNT],
+            velocity: vec![[0.0; 2]; Term::COUNT],
+            steps: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn scaling(&self) -> f64 {
+        self.scaling
+    }
+
+    /// picks the scaling that fits the weights as they are best, which should be done once
+    /// before tuning
+    pub fn fit_scaling(&mut self) -> f64 {
+        let (mut low, mut high) = (0.0, 10.0);
+        for _ in 0..60 {
+            let third = (high - low) / 3.0;
+            self.scaling = low + third;
+            let lower = self.loss();
+            self.scaling = high - third;
+            if lower < self.loss() {
+                high -= third;
+            } else {
+                low += third;
+            }
+        }
+        self.scaling = f64::midpoint(low, high);
+        self.scaling
+    }
+
+    /// the mean squared difference between the results and the winning chances the
+    /// evaluation gives
+    #[must_use]
+    pub fn loss(&self) -> f64 {
+        if self.positions.is_empty() {
+            return 0.0;
+        }
+        let total = self
+            .positions
+            .iter()
+            .map(|position| (position.result - self.winning_chance(position)).powi(2))
+            .sum::<f64>();
+        total / count(self.positions.len())
+    }
+
+    /// one step of adam along the gradient of the loss, returning the loss before it
+    pub fn step(&mut self, learning_rate: f64) -> f64 {
+        const BETA_1: f64 = 0.9;
+        const BETA_2: f64 = 0.999;
+        const EPSILON: f64 = 1e-8;
+
+        let mut gradient = vec![[0.0; 2]; Term::COUNT];
+        let mut loss = 0.0;
+        for position in &self.positions {
+            let chance = self.winning_chance(position);
+            let error = position.result - chance;
+            loss += error * error;
+            // the derivative of the squared error by the score, before tapering
+            let slope =
+                -2.0 * error * chance * (1.0 - chance) * core::f64::consts::LN_10 * self.scaling
+                    / 400.0
+                    * f64::from(position.trace.scale.0)
+                    / 64.0;
+            let middlegame = f64::from(position.trace.phase) / f64::from(MAX_PHASE);
+            for (term, term_count) in &position.trace.terms {
+                let term_count = f64::from(*term_count);
+                gradient[term.index()][0] += slope * term_count * middlegame;
+                gradient[term.index()][1] += slope * term_count * (1.0 - middlegame);
+            }
+        }
+        let positions = count(self.positions.len().max(1));
+
+        self.steps += 1;
+        let momentum_correction = 1.0 - BETA_1.powi(self.steps);
+        let velocity_correction = 1.0 - BETA_2.powi(self.steps);
+        for (index, gradient) in gradient.iter().enumerate() {
+            for part in 0..2 {
+                let gradient = gradient[part] / positions;
+                let momentum = &mut self.momentum[index][part];
+                *momentum = BETA_1 * *momentum + (1.0 - BETA_1) * gradient;
+                let velocity = &mut self.velocity[index][part];
+                *velocity = BETA_2 * *velocity + (1.0 - BETA_2) * gradient * gradient;
+                self.weights[index][part] -= learning_rate * (*momentum / momentum_correction)
+                    / ((*velocity / velocity_correction).sqrt() + EPSILON);
+            }
+        }
+        loss / positions
+    }
+
+    /// the tuned weights, rounded to whole centipawns
+    #[must_use]
+    pub fn weights(&self) -> EvalWeights {
+        let mut weights = self.start.clone();
+        for (term, [middlegame, endgame]) in Term::all().zip(&self.weights) {
+            #[allow(clippy::cast_possible_truncation)]
+            let tapered = TaperedScore::new(middlegame.round() as i32, endgame.round() as i32);
+            *weights.term_mut(term) = tapered;
+        }
+        weights
+    }
+
+    fn score(&self, position: &Position) -> f64 {
+        let (mut middlegame, mut endgame) = (0.0, 0.0);
+        for (term, term_count) in &position.trace.terms {
+            let [term_middlegame, term_endgame] = self.weights[term.index()];
+            middlegame += term_middlegame * f64::from(*term_count);
+            endgame += term_endgame * f64::from(*term_count);
+        }
+        let phase = f64::from(position.trace.phase) / f64::from(MAX_PHASE);
+        (middlegame * phase + endgame * (1.0 - phase)) * f64::from(position.trace.scale.0) / 64.0
+    }
+
+    fn winning_chance(&self, position: &Position) -> f64 {
+        win_chance(self.scaling * self.score(position))
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn count(count: usize) -> f64 {
+    count as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::PieceKind;
+
+    #[test]
+    #[allow(clippy::float_cmp)] // they're parsed, not calculated
+    fn labelled_positions_are_parsed() {
+        let (core, result) = parse_labelled_position(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - bm e7e5; c9 \"0-1\";",
+        )
+        .unwrap();
+        assert_eq!(0.0, result);
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            core.to_fen().as_str()
+        );
+
+        let (core, result) =
+            parse_labelled_position("4k3/8/8/8/8/8/8/3QK3 w - - 5 40 [0.5]").unwrap();
+        assert_eq!(0.5, result);
+        assert_eq!("4k3/8/8/8/8/8/8/3QK3 w - - 5 40", core.to_fen().as_str());
+
+        assert!(matches!(
+            parse_labelled_position("4k3/8/8/8/8/8/8/3QK3 w - - 0 1"),
+            Err(LabelledPositionError::MissingResult)
+        ));
+    }
+
+    #[test]
+    fn quiet_positions_have_nothing_to_take() {
+        let mut searcher = Searcher::new();
+        let quiet = GameStateCore::try_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let hanging = GameStateCore::try_from_fen("4k3/8/8/3q4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let check = GameStateCore::try_from_fen("4k3/8/8/8/8/8/8/3QK2r w - - 0 1").unwrap();
+        assert!(is_quiet(&mut searcher, &quiet));
+        assert!(!is_quiet(&mut searcher, &hanging));
+        assert!(!is_quiet(&mut searcher, &check));
+    }
+
+    #[test]
+    fn tuning_lowers_the_loss() {
+        // white wins with a knight and loses without it, the knight can't be worth nothing
+        let positions = [
+            ("4k3/pppp4/8/8/8/8/PPPP4/1N2K3 w - - 0 1", 1.0),
+            ("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1", 0.5),
+            ("1n2k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1", 0.0),
+        ]
+        .map(|(fen, result)| (GameStateCore::try_from_fen(fen).unwrap(), result));
+        let mut weights = EvalWeights::STANDARD;
+        weights.material[PieceKind::Knight.index()] = TaperedScore::new(0, 0);
+
+        // no fitting the scaling, it would go to 0 with the knight counting against white
+        let mut tuner = Tuner::new(&positions, &weights);
+        let before = tuner.loss();
+        for _ in 0..200 {
+            tuner.step(5.0);
+        }
+        assert!(tuner.loss() < before, "{} not below {before}", tuner.loss());
+        let knight = tuner.weights().material[PieceKind::Knight.index()];
+        assert!(knight.middlegame > 0 && knight.endgame > 0, "{knight:?}");
+    }
+
+    #[test]
+    fn fitted_scaling_is_no_worse() {
+        let positions = [
+            ("4k3/pppp4/8/8/8/8/PPPP4/1Q2K3 w - - 0 1", 1.0),
+            ("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1", 0.5),
+            ("4k3/pppp4/8/8/8/8/PPP5/4K3 w - - 0 1", 0.5),
+            ("1r2k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1", 0.0),
+        ]
+        .map(|(fen, result)| (GameStateCore::try_from_fen(fen).unwrap(), result));
+        let mut tuner = Tuner::new(&positions, &EvalWeights::STANDARD);
+        let before = tuner.loss();
+        let scaling = tuner.fit_scaling();
+        assert!(scaling > 0.0);
+        assert!(tuner.loss() <= before);
+    }
+}
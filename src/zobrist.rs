@@ -0,0 +1,160 @@
+use crate::coord::Square;
+use crate::game::GameStateCore;
+use crate::piece::PieceKind;
+use crate::player::PlayerKind;
+
+struct Keys {
+    pieces: [[[u64; 64]; PieceKind::COUNT]; 2],
+    castling: [u64; 4],
+    en_passant_col: [u64; 8],
+    black_to_move: u64,
+}
+
+impl Keys {
+    const fn generate(mut seed: u64) -> Self {
+        // splitmix64, good enough to spread the keys and simple enough to run at compile time
+        const fn next(state: &mut u64) -> u64 {
+            *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = *state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        }
+
+        let mut keys = Self {
+            pieces: [[[0; 64]; PieceKind::COUNT]; 2],
+            castling: [0; 4],
+            en_passant_col: [0; 8],
+            black_to_move: 0,
+        };
+
+        let mut owner = 0;
+        while owner < 2 {
+            let mut kind = 0;
+            while kind < PieceKind::COUNT {
+                let mut square = 0;
+                while square < 64 {
+                    keys.pieces[owner][kind][square] = next(&mut seed);
+                    square += 1;
+                }
+                kind += 1;
+            }
+            owner += 1;
+        }
+
+        let mut i = 0;
+        while i < 4 {
+            keys.castling[i] = next(&mut seed);
+            i += 1;
+        }
+        let mut i = 0;
+        while i < 8 {
+            keys.en_passant_col[i] = next(&mut seed);
+            i += 1;
+        }
+        keys.black_to_move = next(&mut seed);
+
+        keys
+    }
+}
+
+const KEYS: Keys = Keys::generate(0x5343_4841_4348_2121);
+
+impl GameStateCore {
+    /// hash of everything that decides which moves are legal here, so equal positions reached
+    /// through different move orders share a hash. the move clocks are deliberately left out.
+    #[must_use]
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+
+        for square in Square::ALL {
+            if let Some(piece) = self.board[square] {
+                let owner = match piece.owner {
+                    PlayerKind::White => 0,
+                    PlayerKind::Black => 1,
+                };
+                hash ^= KEYS.pieces[owner][piece.kind.index()][square.index()];
+            }
+        }
+
+        let rights = self.castling_rights;
+        for (i, has_right) in [
+            rights.white_kingside,
+            rights.white_queenside,
+            rights.black_kingside,
+            rights.black_queenside,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if has_right {
+                hash ^= KEYS.castling[i];
+            }
+        }
+
+        if let Some(target) = self.en_passant_target {
+            hash ^= KEYS.en_passant_col[usize::from(target.col) - 1];
+        }
+
+        if self.active_player == PlayerKind::Black {
+            hash ^= KEYS.black_to_move;
+        }
+
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::game::Ongoing;
+    use crate::game::StepResult;
+    use crate::notation::san::standard_algebraic_notation;
+
+    fn play(mut game: GameState<Ongoing>, moves: &[&str]) -> GameState<Ongoing> {
+        for san in moves {
+            let mv = game
+                .legal_moves()
+                .find(|mv| standard_algebraic_notation(game.clone(), *mv).as_str() == *san)
+                .unwrap();
+            let StepResult::Ongoing(next) = game.step(mv) else {
+                panic!("{san} ended the game");
+            };
+            game = next;
+        }
+        game
+    }
+
+    #[test]
+    fn transpositions_share_a_hash() {
+        let a = play(GameState::new(), &["Nf3", "Nf6", "Nc3", "Nc6"]);
+        let b = play(GameState::new(), &["Nc3", "Nc6", "Nf3", "Nf6"]);
+
+        assert_ne!(
+            a.core.full_move_count,
+            GameState::new().core.full_move_count
+        );
+        assert_eq!(a.core.zobrist_hash(), b.core.zobrist_hash());
+    }
+
+    #[test]
+    fn everything_that_decides_legal_moves_changes_the_hash() {
+        let core = GameStateCore::default();
+        let hash = core.zobrist_hash();
+
+        assert_ne!(hash, core.with_opponent_active().zobrist_hash());
+
+        let mut no_castling = core;
+        no_castling.castling_rights.black_queenside = false;
+        assert_ne!(hash, no_castling.zobrist_hash());
+
+        let mut en_passant = core;
+        en_passant.en_passant_target = Some(Square::E3);
+        assert_ne!(hash, en_passant.zobrist_hash());
+
+        let mut moved = core;
+        moved.board.mov(Square::G1, Square::F3);
+        assert_ne!(hash, moved.zobrist_hash());
+    }
+}